@@ -0,0 +1,9 @@
+-- Add migration script here
+
+-- Backs the v3 `search` endpoint. `MATCH` requires an index whose column list
+-- matches the searched columns exactly, so title and tagline share one index.
+ALTER TABLE `Comic`
+    ADD FULLTEXT INDEX `ft_comic_title_tagline` (`title`, `tagline`);
+
+ALTER TABLE `News`
+    ADD FULLTEXT INDEX `ft_news_news` (`news`);
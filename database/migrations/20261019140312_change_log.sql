@@ -0,0 +1,141 @@
+-- Add migration script here
+
+-- Monotonic change sequence backing the v3 `sync` endpoint. Every write to a
+-- synced table appends a row; clients remember the highest `seq` they have
+-- seen and ask for everything after it.
+--
+-- `entity_id` is the row's primary key (`comic_id` for `News`, `comic_id` for
+-- `Occurrence`, whose `item_id` goes in `secondary_id`).
+CREATE TABLE `change_log` (
+    `seq`          BIGINT UNSIGNED   NOT NULL AUTO_INCREMENT,
+    `entity`       VARCHAR(20)       NOT NULL,
+    `entity_id`    INT UNSIGNED      NOT NULL,
+    `secondary_id` INT UNSIGNED          NULL,
+    `deleted`      TINYINT UNSIGNED  NOT NULL DEFAULT 0,
+    `changed_at`   DATETIME          NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`seq`),
+    KEY `change_log_entity` (`entity`, `entity_id`)
+);
+
+-- Seed the log with the current state so a client can bootstrap from
+-- `since=0` instead of combining full list fetches with the change feed.
+INSERT INTO `change_log` (`entity`, `entity_id`)
+SELECT 'comic', `id` FROM `Comic` ORDER BY `id`;
+
+INSERT INTO `change_log` (`entity`, `entity_id`)
+SELECT 'item', `id` FROM `Item` ORDER BY `id`;
+
+INSERT INTO `change_log` (`entity`, `entity_id`, `secondary_id`)
+SELECT 'occurrence', `comic_id`, `item_id` FROM `Occurrence` ORDER BY `comic_id`, `item_id`;
+
+INSERT INTO `change_log` (`entity`, `entity_id`)
+SELECT 'item_image', `id` FROM `ItemImage` ORDER BY `id`;
+
+INSERT INTO `change_log` (`entity`, `entity_id`)
+SELECT 'news', `comic_id` FROM `News` ORDER BY `comic_id`;
+
+-- `seq` is handed out when a row is inserted, but transactions commit in
+-- their own order: a reader could see seq N+1 while N is still uncommitted,
+-- advance its cursor past N and never receive it.
+--
+-- Every insert into `change_log` therefore first bumps this single counter
+-- row. The row lock is held until the writing transaction commits (or rolls
+-- back), so a second writer only gets its `seq` after the first one is
+-- visible, and sequence numbers become visible in order. `AUTO_INCREMENT`
+-- values are generated after `BEFORE INSERT` triggers have run.
+--
+-- The cost is that writes to the synced tables no longer run concurrently:
+-- every transaction that writes to `Comic`, `Item`, `Occurrence`,
+-- `ItemImage` or `News` (or any synced table added later) waits on this one
+-- row lock until the previous such transaction has committed. Keep those
+-- transactions short.
+CREATE TABLE `change_log_lock` (
+    `id`     TINYINT UNSIGNED NOT NULL,
+    `writes` BIGINT UNSIGNED  NOT NULL DEFAULT 0,
+    PRIMARY KEY (`id`)
+);
+
+INSERT INTO `change_log_lock` (`id`) VALUES (1);
+
+CREATE TRIGGER `trg_change_log_ins_serialize`
+BEFORE INSERT ON `change_log`
+FOR EACH ROW
+UPDATE `change_log_lock` SET `writes` = `writes` + 1 WHERE `id` = 1;
+
+-- Single-statement triggers: no BEGIN/END, no DELIMITER needed.
+-- Note that foreign key actions (`Item.primary_image` is `ON DELETE SET NULL`)
+-- do not fire triggers; clients clear a deleted primary image themselves.
+CREATE TRIGGER `trg_comic_ins_change_log`
+AFTER INSERT ON `Comic`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('comic', NEW.`id`);
+
+CREATE TRIGGER `trg_comic_upd_change_log`
+AFTER UPDATE ON `Comic`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('comic', NEW.`id`);
+
+CREATE TRIGGER `trg_comic_del_change_log`
+AFTER DELETE ON `Comic`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `deleted`) VALUES ('comic', OLD.`id`, 1);
+
+CREATE TRIGGER `trg_item_ins_change_log`
+AFTER INSERT ON `Item`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('item', NEW.`id`);
+
+CREATE TRIGGER `trg_item_upd_change_log`
+AFTER UPDATE ON `Item`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('item', NEW.`id`);
+
+CREATE TRIGGER `trg_item_del_change_log`
+AFTER DELETE ON `Item`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `deleted`) VALUES ('item', OLD.`id`, 1);
+
+CREATE TRIGGER `trg_occurrence_ins_change_log`
+AFTER INSERT ON `Occurrence`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `secondary_id`)
+VALUES ('occurrence', NEW.`comic_id`, NEW.`item_id`);
+
+CREATE TRIGGER `trg_occurrence_del_change_log`
+AFTER DELETE ON `Occurrence`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `secondary_id`, `deleted`)
+VALUES ('occurrence', OLD.`comic_id`, OLD.`item_id`, 1);
+
+CREATE TRIGGER `trg_item_image_ins_change_log`
+AFTER INSERT ON `ItemImage`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('item_image', NEW.`id`);
+
+CREATE TRIGGER `trg_item_image_upd_change_log`
+AFTER UPDATE ON `ItemImage`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('item_image', NEW.`id`);
+
+CREATE TRIGGER `trg_item_image_del_change_log`
+AFTER DELETE ON `ItemImage`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `deleted`) VALUES ('item_image', OLD.`id`, 1);
+
+CREATE TRIGGER `trg_news_ins_change_log`
+AFTER INSERT ON `News`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('news', NEW.`comic_id`);
+
+-- The news updater touches `last_updated`/`update_factor` on every check;
+-- only actual text changes are interesting to clients.
+CREATE TRIGGER `trg_news_upd_change_log`
+AFTER UPDATE ON `News`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`)
+SELECT 'news', NEW.`comic_id` FROM DUAL WHERE NOT (OLD.`news` <=> NEW.`news`);
+
+CREATE TRIGGER `trg_news_del_change_log`
+AFTER DELETE ON `News`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `deleted`) VALUES ('news', OLD.`comic_id`, 1);
@@ -0,0 +1,20 @@
+-- Row versions for optimistic concurrency. Served as the `ETag` of a comic or
+-- item and checked against `If-Match` when patching one.
+ALTER TABLE `Comic`
+    ADD COLUMN `version` INT UNSIGNED NOT NULL DEFAULT 1;
+
+ALTER TABLE `Item`
+    ADD COLUMN `version` INT UNSIGNED NOT NULL DEFAULT 1;
+
+-- Bumped by trigger rather than by the queries themselves, so that every
+-- writer (including the v1/v2 endpoints and the importer) invalidates the
+-- versions editors are holding on to.
+CREATE TRIGGER `trg_comic_upd_version`
+BEFORE UPDATE ON `Comic`
+FOR EACH ROW
+SET NEW.`version` = OLD.`version` + 1;
+
+CREATE TRIGGER `trg_item_upd_version`
+BEFORE UPDATE ON `Item`
+FOR EACH ROW
+SET NEW.`version` = OLD.`version` + 1;
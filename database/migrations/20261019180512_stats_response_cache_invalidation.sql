@@ -0,0 +1,47 @@
+-- Dirty flag for the in-memory stats response cache. `last_comic_id` is not
+-- used for this key; the responses are recomputed wholesale.
+INSERT INTO `stats_cache_meta` (`cache_key`, `last_comic_id`, `needs_refresh`)
+VALUES ('stats_responses', 0, 1);
+
+-- Unlike the stints cache, the stats responses also depend on comic metadata
+-- (publish dates, flags) and item names and colors, so every write to `Comic`,
+-- `Item` and `Occurrence` invalidates them.
+CREATE TRIGGER `trg_comic_ins_invalidate_stats_responses`
+AFTER INSERT ON `Comic`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_comic_upd_invalidate_stats_responses`
+AFTER UPDATE ON `Comic`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_comic_del_invalidate_stats_responses`
+AFTER DELETE ON `Comic`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_item_ins_invalidate_stats_responses`
+AFTER INSERT ON `Item`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_item_upd_invalidate_stats_responses`
+AFTER UPDATE ON `Item`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_item_del_invalidate_stats_responses`
+AFTER DELETE ON `Item`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_occurrence_ins_invalidate_stats_responses`
+AFTER INSERT ON `Occurrence`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_occurrence_del_invalidate_stats_responses`
+AFTER DELETE ON `Occurrence`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
@@ -0,0 +1,38 @@
+-- Lowest comic whose occurrences changed since the last refresh. Stints that
+-- start before it are unaffected, so the refresher only has to recompute the
+-- ones from there onward. NULL with `needs_refresh` set means "everything".
+ALTER TABLE `stats_cache_meta`
+    ADD COLUMN `dirty_from_comic` SMALLINT UNSIGNED NULL;
+
+DROP TRIGGER `trg_occurrence_ins_invalidate_stints`;
+DROP TRIGGER `trg_occurrence_del_invalidate_stints`;
+
+-- MySQL assigns `SET` columns left to right, so `needs_refresh` still holds
+-- its old value when `dirty_from_comic` is computed. A pending full refresh
+-- (NULL) stays one, since `LEAST` with NULL is NULL.
+CREATE TRIGGER `trg_occurrence_ins_invalidate_stints`
+AFTER INSERT ON `Occurrence`
+FOR EACH ROW
+UPDATE `stats_cache_meta`
+SET `dirty_from_comic` = IF(`needs_refresh` = 0, NEW.`comic_id`, LEAST(`dirty_from_comic`, NEW.`comic_id`)),
+    `needs_refresh` = 1
+WHERE `cache_key` = 'cast_rank_stints';
+
+CREATE TRIGGER `trg_occurrence_del_invalidate_stints`
+AFTER DELETE ON `Occurrence`
+FOR EACH ROW
+UPDATE `stats_cache_meta`
+SET `dirty_from_comic` = IF(`needs_refresh` = 0, OLD.`comic_id`, LEAST(`dirty_from_comic`, OLD.`comic_id`)),
+    `needs_refresh` = 1
+WHERE `cache_key` = 'cast_rank_stints';
+
+-- Stints are computed per item type, so an item that changes type can lead
+-- the stints of its old type and be missing from those of its new one
+-- anywhere in the series. Recompute both in full.
+CREATE TRIGGER `trg_item_upd_invalidate_stints`
+AFTER UPDATE ON `Item`
+FOR EACH ROW
+UPDATE `stats_cache_meta`
+SET `needs_refresh` = 1, `dirty_from_comic` = NULL
+WHERE OLD.`type` <> NEW.`type`
+  AND `cache_key` IN (CONCAT(OLD.`type`, '_rank_stints'), CONCAT(NEW.`type`, '_rank_stints'));
@@ -0,0 +1,44 @@
+-- Leadership stints for locations and storylines, alongside the cast ones.
+-- The stints of each item type are computed independently, so they share the
+-- table but have their own `stats_cache_meta` key and dirty range. The keys
+-- follow `<type>_rank_stints`, so `trg_item_upd_invalidate_stints` already
+-- invalidates both types when an item moves between them.
+ALTER TABLE `cast_rank_leadership_stints` RENAME TO `rank_leadership_stints`;
+
+ALTER TABLE `rank_leadership_stints`
+    ADD COLUMN `item_type` VARCHAR(255) NOT NULL DEFAULT 'cast' FIRST,
+    DROP PRIMARY KEY,
+    ADD PRIMARY KEY (`item_type`, `from_comic`);
+
+ALTER TABLE `rank_leadership_stints`
+    ALTER COLUMN `item_type` DROP DEFAULT;
+
+-- Computed by the background refresher on its next cycle.
+INSERT INTO `stats_cache_meta` (`cache_key`, `last_comic_id`, `needs_refresh`)
+VALUES
+    ('location_rank_stints', 0, 1),
+    ('storyline_rank_stints', 0, 1);
+
+-- Only the stints of the occurrence's item type are affected.
+DROP TRIGGER `trg_occurrence_ins_invalidate_stints`;
+DROP TRIGGER `trg_occurrence_del_invalidate_stints`;
+
+CREATE TRIGGER `trg_occurrence_ins_invalidate_stints`
+AFTER INSERT ON `Occurrence`
+FOR EACH ROW
+UPDATE `stats_cache_meta`
+SET `dirty_from_comic` = IF(`needs_refresh` = 0, NEW.`comic_id`, LEAST(`dirty_from_comic`, NEW.`comic_id`)),
+    `needs_refresh` = 1
+WHERE `cache_key` = (
+    SELECT CONCAT(`i`.`type`, '_rank_stints') FROM `Item` `i` WHERE `i`.`id` = NEW.`item_id`
+);
+
+CREATE TRIGGER `trg_occurrence_del_invalidate_stints`
+AFTER DELETE ON `Occurrence`
+FOR EACH ROW
+UPDATE `stats_cache_meta`
+SET `dirty_from_comic` = IF(`needs_refresh` = 0, OLD.`comic_id`, LEAST(`dirty_from_comic`, OLD.`comic_id`)),
+    `needs_refresh` = 1
+WHERE `cache_key` = (
+    SELECT CONCAT(`i`.`type`, '_rank_stints') FROM `Item` `i` WHERE `i`.`id` = OLD.`item_id`
+);
@@ -0,0 +1,44 @@
+-- Editor-defined eras of the story: named, non-overlapping comic id ranges
+-- that the year-based stats can be grouped by instead of calendar years.
+-- Unlike `Item`.`end_comic_id`, which is the first comic after a storyline,
+-- `last_comic_id` is the last comic of the era.
+CREATE TABLE `Era` (
+    `id`             SMALLINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `name`           VARCHAR(255)      NOT NULL,
+    `start_comic_id` SMALLINT UNSIGNED NOT NULL,
+    `last_comic_id`  SMALLINT UNSIGNED NOT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `era_start_comic_id` (`start_comic_id`)
+);
+
+-- Eras are synced to clients like the rest of the dataset.
+CREATE TRIGGER `trg_era_ins_change_log`
+AFTER INSERT ON `Era`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('era', NEW.`id`);
+
+CREATE TRIGGER `trg_era_upd_change_log`
+AFTER UPDATE ON `Era`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`) VALUES ('era', NEW.`id`);
+
+CREATE TRIGGER `trg_era_del_change_log`
+AFTER DELETE ON `Era`
+FOR EACH ROW
+INSERT INTO `change_log` (`entity`, `entity_id`, `deleted`) VALUES ('era', OLD.`id`, 1);
+
+-- Stats grouped by era change along with the eras.
+CREATE TRIGGER `trg_era_ins_invalidate_stats_responses`
+AFTER INSERT ON `Era`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_era_upd_invalidate_stats_responses`
+AFTER UPDATE ON `Era`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
+
+CREATE TRIGGER `trg_era_del_invalidate_stats_responses`
+AFTER DELETE ON `Era`
+FOR EACH ROW
+UPDATE `stats_cache_meta` SET `needs_refresh` = 1 WHERE `cache_key` = 'stats_responses';
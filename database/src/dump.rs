@@ -0,0 +1,518 @@
+//! Portable dumps of the comic dataset.
+//!
+//! A dump is a gzip-compressed tar archive holding a `manifest.json`, one
+//! JSON Lines file per table and the raw item image data under `images/`.
+//! `Token` rows are never exported, and the tokens recorded on `LogEntry`
+//! rows are replaced by contributor numbers that are only stable within a
+//! single dump.
+//!
+//! [`Dump::read_from`] and [`Dump::import`] load a dump back into a database.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use sqlx::Connection;
+
+use crate::models::{Comic, Item, ItemImageData, ItemOccurrence, LogEntry, News};
+
+mod import;
+
+pub use import::*;
+
+/// Bumped whenever the archive layout or a row format changes in a way older
+/// readers cannot handle.
+pub const FORMAT_VERSION: u32 = 1;
+
+pub const MANIFEST_PATH: &str = "manifest.json";
+pub const COMICS_PATH: &str = "comics.jsonl";
+pub const ITEMS_PATH: &str = "items.jsonl";
+pub const OCCURRENCES_PATH: &str = "occurrences.jsonl";
+pub const NEWS_PATH: &str = "news.jsonl";
+pub const ITEM_IMAGES_PATH: &str = "item_images.jsonl";
+pub const LOG_ENTRIES_PATH: &str = "log_entries.jsonl";
+
+/// Where the image data of the item image with the given id is stored in the
+/// archive.
+#[must_use]
+pub fn image_path(image_id: u32) -> String {
+    format!("images/{image_id}")
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportOptions {
+    /// Include hidden (advance) comics, along with their occurrences, news
+    /// and log entries.
+    pub include_hidden: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub includes_hidden: bool,
+    pub counts: DumpCounts,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpCounts {
+    pub comics: usize,
+    pub items: usize,
+    pub occurrences: usize,
+    pub news: usize,
+    pub item_images: usize,
+    pub log_entries: usize,
+}
+
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "mirrors the comic flags in the database"
+)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpComic {
+    pub id: u16,
+    pub image_type: i32,
+    pub title: String,
+    pub tagline: Option<String>,
+    pub publish_date: Option<NaiveDateTime>,
+    pub is_accurate_publish_date: bool,
+    pub is_guest_comic: bool,
+    pub is_non_canon: bool,
+    pub has_no_cast: bool,
+    pub has_no_location: bool,
+    pub has_no_storyline: bool,
+    pub has_no_title: bool,
+    pub has_no_tagline: bool,
+    pub hidden: bool,
+}
+
+impl From<Comic> for DumpComic {
+    fn from(c: Comic) -> Self {
+        Self {
+            id: c.id,
+            image_type: c.image_type,
+            title: c.title,
+            tagline: c.tagline,
+            publish_date: c.publish_date,
+            is_accurate_publish_date: c.is_accurate_publish_date != 0,
+            is_guest_comic: c.is_guest_comic != 0,
+            is_non_canon: c.is_non_canon != 0,
+            has_no_cast: c.has_no_cast != 0,
+            has_no_location: c.has_no_location != 0,
+            has_no_storyline: c.has_no_storyline != 0,
+            has_no_title: c.has_no_title != 0,
+            has_no_tagline: c.has_no_tagline != 0,
+            hidden: c.hidden != 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpItem {
+    pub id: u16,
+    pub short_name: String,
+    pub name: String,
+    pub r#type: String,
+    pub color_red: u8,
+    pub color_green: u8,
+    pub color_blue: u8,
+    pub primary_image: Option<u32>,
+    pub start_comic_id: Option<u16>,
+    pub end_comic_id: Option<u16>,
+}
+
+impl From<Item> for DumpItem {
+    fn from(i: Item) -> Self {
+        Self {
+            id: i.id,
+            short_name: i.short_name,
+            name: i.name,
+            r#type: i.r#type,
+            color_red: i.color_red,
+            color_green: i.color_green,
+            color_blue: i.color_blue,
+            primary_image: i.primary_image,
+            start_comic_id: i.start_comic_id,
+            end_comic_id: i.end_comic_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DumpOccurrence {
+    pub comic_id: u16,
+    pub item_id: u16,
+}
+
+impl From<ItemOccurrence> for DumpOccurrence {
+    fn from(o: ItemOccurrence) -> Self {
+        Self {
+            comic_id: o.comic_id,
+            item_id: o.item_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpNews {
+    pub comic_id: u16,
+    pub last_updated: NaiveDate,
+    pub news: String,
+    pub update_factor: f64,
+    pub is_locked: bool,
+}
+
+impl From<News> for DumpNews {
+    fn from(n: News) -> Self {
+        Self {
+            comic_id: n.comic_id,
+            last_updated: n.last_updated,
+            news: n.news,
+            update_factor: n.update_factor,
+            is_locked: n.is_locked != 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpItemImage {
+    pub id: u32,
+    pub item_id: u16,
+    pub crc32c_hash: u32,
+    /// Stored separately, at [`image_path`].
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl From<ItemImageData> for DumpItemImage {
+    fn from(i: ItemImageData) -> Self {
+        Self {
+            id: i.id,
+            item_id: i.item_id,
+            crc32c_hash: i.crc32c_hash,
+            data: i.image,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpLogEntry {
+    pub id: u32,
+    /// Stands in for the token that made the change. Entries made with the
+    /// same token share a number, but the numbering says nothing about the
+    /// token itself and differs between dumps.
+    pub contributor: u32,
+    pub date_time: NaiveDateTime,
+    pub action: String,
+    pub comic_involved: Option<u16>,
+    pub item_involved: Option<u16>,
+}
+
+/// Replaces the tokens on `entries` with contributor numbers, assigned in
+/// order of first appearance.
+fn anonymize_log_entries(entries: Vec<LogEntry>) -> Vec<DumpLogEntry> {
+    let mut contributors: HashMap<String, u32> = HashMap::new();
+    entries
+        .into_iter()
+        .map(|entry| {
+            let next_contributor = u32::try_from(contributors.len() + 1).unwrap_or(u32::MAX);
+            let contributor = *contributors
+                .entry(entry.user_token)
+                .or_insert(next_contributor);
+            DumpLogEntry {
+                id: entry.id,
+                contributor,
+                date_time: entry.date_time,
+                action: entry.action,
+                comic_involved: entry.involved_comic,
+                item_involved: entry.involved_item,
+            }
+        })
+        .collect()
+}
+
+/// The full contents of a dump, held in memory.
+#[derive(Debug)]
+pub struct Dump {
+    pub manifest: Manifest,
+    pub comics: Vec<DumpComic>,
+    pub items: Vec<DumpItem>,
+    pub occurrences: Vec<DumpOccurrence>,
+    pub news: Vec<DumpNews>,
+    pub item_images: Vec<DumpItemImage>,
+    pub log_entries: Vec<DumpLogEntry>,
+}
+
+impl Dump {
+    /// Reads the whole dataset inside a single transaction, so the dump is a
+    /// consistent snapshot even while edits are being made.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if any of the queries fail.
+    #[tracing::instrument(skip(conn))]
+    pub async fn load(
+        conn: &mut sqlx::MySqlConnection,
+        options: ExportOptions,
+    ) -> Result<Self, DumpError> {
+        let mut transaction = conn.begin().await?;
+
+        let mut comics = Comic::all_including_hidden(&mut *transaction).await?;
+        let hidden_comic_ids: HashSet<u16> = if options.include_hidden {
+            HashSet::new()
+        } else {
+            comics
+                .iter()
+                .filter(|c| c.hidden != 0)
+                .map(|c| c.id)
+                .collect()
+        };
+        let is_visible = |comic_id: u16| !hidden_comic_ids.contains(&comic_id);
+        comics.retain(|c| is_visible(c.id));
+
+        let mut items = Item::all(&mut *transaction).await?;
+        items.sort_unstable_by_key(|i| i.id);
+
+        let mut occurrences = Item::all_occurrences(&mut *transaction).await?;
+        occurrences.retain(|o| is_visible(o.comic_id));
+
+        let mut news = News::all(&mut *transaction).await?;
+        news.retain(|n| is_visible(n.comic_id));
+
+        let item_images = Item::all_images(&mut *transaction).await?;
+
+        let mut log_entries = LogEntry::all(&mut *transaction).await?;
+        log_entries.retain(|l| l.involved_comic.is_none_or(is_visible));
+
+        transaction.commit().await?;
+
+        let mut dump = Self {
+            manifest: Manifest {
+                format_version: FORMAT_VERSION,
+                exported_at: Utc::now(),
+                includes_hidden: options.include_hidden,
+                counts: DumpCounts::default(),
+            },
+            comics: comics.into_iter().map(From::from).collect(),
+            items: items.into_iter().map(From::from).collect(),
+            occurrences: occurrences.into_iter().map(From::from).collect(),
+            news: news.into_iter().map(From::from).collect(),
+            item_images: item_images.into_iter().map(From::from).collect(),
+            log_entries: anonymize_log_entries(log_entries),
+        };
+        dump.manifest.counts = dump.counts();
+
+        Ok(dump)
+    }
+
+    #[must_use]
+    pub const fn counts(&self) -> DumpCounts {
+        DumpCounts {
+            comics: self.comics.len(),
+            items: self.items.len(),
+            occurrences: self.occurrences.len(),
+            news: self.news.len(),
+            item_images: self.item_images.len(),
+            log_entries: self.log_entries.len(),
+        }
+    }
+
+    /// Writes the dump as a gzip-compressed tar archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` fails.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), DumpError> {
+        let mtime = u64::try_from(self.manifest.exported_at.timestamp()).unwrap_or_default();
+        let mut archive = ArchiveWriter {
+            builder: tar::Builder::new(GzEncoder::new(writer, Compression::default())),
+            mtime,
+        };
+
+        archive.append(MANIFEST_PATH, &serde_json::to_vec_pretty(&self.manifest)?)?;
+        archive.append_json_lines(COMICS_PATH, &self.comics)?;
+        archive.append_json_lines(ITEMS_PATH, &self.items)?;
+        archive.append_json_lines(OCCURRENCES_PATH, &self.occurrences)?;
+        archive.append_json_lines(NEWS_PATH, &self.news)?;
+        archive.append_json_lines(ITEM_IMAGES_PATH, &self.item_images)?;
+        archive.append_json_lines(LOG_ENTRIES_PATH, &self.log_entries)?;
+        for image in &self.item_images {
+            archive.append(&image_path(image.id), &image.data)?;
+        }
+
+        archive.builder.into_inner()?.finish()?.flush()?;
+
+        Ok(())
+    }
+}
+
+struct ArchiveWriter<W: Write> {
+    builder: tar::Builder<GzEncoder<W>>,
+    mtime: u64,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    fn append(&mut self, path: &str, data: &[u8]) -> Result<(), DumpError> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(self.mtime);
+        self.builder.append_data(&mut header, path, data)?;
+
+        Ok(())
+    }
+
+    fn append_json_lines<T: Serialize>(&mut self, path: &str, rows: &[T]) -> Result<(), DumpError> {
+        let mut data = Vec::new();
+        for row in rows {
+            serde_json::to_writer(&mut data, row)?;
+            data.push(b'\n');
+        }
+
+        self.append(path, &data)
+    }
+}
+
+#[derive(Debug)]
+pub enum DumpError {
+    Database(sqlx::Error),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The archive is not a dump this version can read.
+    Format(String),
+    /// The dump refers to rows it does not contain; see [`Dump::validate`].
+    Integrity(Vec<IntegrityProblem>),
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "database error: {e}"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Json(e) => write!(f, "JSON error: {e}"),
+            Self::Format(e) => write!(f, "invalid dump: {e}"),
+            Self::Integrity(problems) => {
+                write!(
+                    f,
+                    "dump failed validation with {} problem(s)",
+                    problems.len()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DumpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::Format(_) | Self::Integrity(_) => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for DumpError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+impl From<std::io::Error> for DumpError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DumpError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    fn log_entry(id: u32, user_token: &str) -> LogEntry {
+        LogEntry {
+            id,
+            user_token: user_token.to_owned(),
+            date_time: NaiveDateTime::default(),
+            action: format!("Change {id}"),
+            involved_comic: None,
+            involved_item: None,
+        }
+    }
+
+    #[test]
+    fn log_entry_tokens_are_replaced_by_contributor_numbers() {
+        let entries = anonymize_log_entries(vec![
+            log_entry(1, "b0c6c5c4-0000-0000-0000-000000000000"),
+            log_entry(2, "a1f3d2e1-0000-0000-0000-000000000000"),
+            log_entry(3, "b0c6c5c4-0000-0000-0000-000000000000"),
+        ]);
+
+        let contributors: Vec<u32> = entries.iter().map(|e| e.contributor).collect();
+        assert_eq!(contributors, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn archive_contains_manifest_tables_and_images() {
+        let dump = Dump {
+            manifest: Manifest {
+                format_version: FORMAT_VERSION,
+                exported_at: DateTime::default(),
+                includes_hidden: false,
+                counts: DumpCounts::default(),
+            },
+            comics: Vec::new(),
+            items: Vec::new(),
+            occurrences: vec![DumpOccurrence {
+                comic_id: 1,
+                item_id: 2,
+            }],
+            news: Vec::new(),
+            item_images: vec![DumpItemImage {
+                id: 7,
+                item_id: 2,
+                crc32c_hash: 0,
+                data: b"GIF89a".to_vec(),
+            }],
+            log_entries: Vec::new(),
+        };
+
+        let mut bytes = Vec::new();
+        dump.write_to(&mut bytes).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(&bytes[..]));
+        let mut files = HashMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).unwrap();
+            files.insert(path, contents);
+        }
+
+        assert!(files.contains_key(MANIFEST_PATH));
+        assert_eq!(
+            files[OCCURRENCES_PATH],
+            b"{\"comic_id\":1,\"item_id\":2}\n".to_vec()
+        );
+        assert_eq!(
+            files[ITEM_IMAGES_PATH],
+            b"{\"id\":7,\"item_id\":2,\"crc32c_hash\":0}\n".to_vec()
+        );
+        assert_eq!(files["images/7"], b"GIF89a".to_vec());
+    }
+}
@@ -0,0 +1,704 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sqlx::Connection;
+
+use super::{
+    COMICS_PATH, Dump, DumpComic, DumpError, DumpItem, DumpItemImage, DumpLogEntry, DumpNews,
+    FORMAT_VERSION, ITEM_IMAGES_PATH, ITEMS_PATH, LOG_ENTRIES_PATH, MANIFEST_PATH, Manifest,
+    NEWS_PATH, OCCURRENCES_PATH,
+};
+use crate::models::{Comic, Item, LogEntry, News, Occurrence, Token};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    /// Run the whole import, but roll the transaction back at the end.
+    pub dry_run: bool,
+}
+
+/// What an import did (or, for a dry run, would have done).
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ImportReport {
+    pub comics: ImportCounts,
+    pub items: ImportCounts,
+    pub occurrences: ImportCounts,
+    pub news: ImportCounts,
+    pub item_images: ImportCounts,
+    pub log_entries: ImportCounts,
+    pub committed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ImportCounts {
+    /// Rows that did not exist before.
+    pub inserted: usize,
+    /// Rows that already existed. Comics, items, news and item images are
+    /// overwritten with the dump's version; occurrences and log entries are
+    /// left as they are.
+    pub existing: usize,
+}
+
+impl ImportCounts {
+    const fn record(&mut self, inserted: bool) {
+        if inserted {
+            self.inserted += 1;
+        } else {
+            self.existing += 1;
+        }
+    }
+}
+
+/// A reference or consistency problem found in a dump before importing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityProblem {
+    DuplicateComic(u16),
+    DuplicateItem(u16),
+    DuplicateOccurrence {
+        comic_id: u16,
+        item_id: u16,
+    },
+    DuplicateNews(u16),
+    DuplicateItemImage(u32),
+    DuplicateLogEntry(u32),
+    OccurrenceOfUnknownComic {
+        comic_id: u16,
+        item_id: u16,
+    },
+    OccurrenceOfUnknownItem {
+        comic_id: u16,
+        item_id: u16,
+    },
+    NewsForUnknownComic(u16),
+    ImageOfUnknownItem {
+        image_id: u32,
+        item_id: u16,
+    },
+    ImageChecksumMismatch(u32),
+    UnknownPrimaryImage {
+        item_id: u16,
+        image_id: u32,
+    },
+    PrimaryImageOfOtherItem {
+        item_id: u16,
+        image_id: u32,
+        owner_id: u16,
+    },
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::DuplicateComic(id) => write!(f, "comic #{id} appears more than once"),
+            Self::DuplicateItem(id) => write!(f, "item #{id} appears more than once"),
+            Self::DuplicateOccurrence { comic_id, item_id } => write!(
+                f,
+                "item #{item_id} is listed more than once in comic #{comic_id}"
+            ),
+            Self::DuplicateNews(comic_id) => {
+                write!(f, "news for comic #{comic_id} appears more than once")
+            }
+            Self::DuplicateItemImage(id) => write!(f, "item image #{id} appears more than once"),
+            Self::DuplicateLogEntry(id) => write!(f, "log entry #{id} appears more than once"),
+            Self::OccurrenceOfUnknownComic { comic_id, item_id } => write!(
+                f,
+                "item #{item_id} occurs in comic #{comic_id}, which is not in the dump"
+            ),
+            Self::OccurrenceOfUnknownItem { comic_id, item_id } => write!(
+                f,
+                "comic #{comic_id} features item #{item_id}, which is not in the dump"
+            ),
+            Self::NewsForUnknownComic(comic_id) => write!(
+                f,
+                "news belongs to comic #{comic_id}, which is not in the dump"
+            ),
+            Self::ImageOfUnknownItem { image_id, item_id } => write!(
+                f,
+                "item image #{image_id} belongs to item #{item_id}, which is not in the dump"
+            ),
+            Self::ImageChecksumMismatch(id) => {
+                write!(f, "item image #{id} does not match its CRC32C hash")
+            }
+            Self::UnknownPrimaryImage { item_id, image_id } => write!(
+                f,
+                "item #{item_id} has primary image #{image_id}, which is not in the dump"
+            ),
+            Self::PrimaryImageOfOtherItem {
+                item_id,
+                image_id,
+                owner_id,
+            } => write!(
+                f,
+                "item #{item_id} has primary image #{image_id}, which belongs to item #{owner_id}"
+            ),
+        }
+    }
+}
+
+impl Dump {
+    /// Reads a dump written by [`Self::write_to`]. Fails if the archive was
+    /// written in a different format version, or if it is incomplete.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read or is malformed.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, DumpError> {
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+
+        let mut manifest: Option<Manifest> = None;
+        let mut dump = Self {
+            manifest: Manifest {
+                format_version: FORMAT_VERSION,
+                exported_at: chrono::DateTime::default(),
+                includes_hidden: false,
+                counts: super::DumpCounts::default(),
+            },
+            comics: Vec::new(),
+            items: Vec::new(),
+            occurrences: Vec::new(),
+            news: Vec::new(),
+            item_images: Vec::new(),
+            log_entries: Vec::new(),
+        };
+        let mut image_data: HashMap<u32, Vec<u8>> = HashMap::new();
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+
+            match &*path {
+                MANIFEST_PATH => {
+                    let read: Manifest = serde_json::from_slice(&contents)?;
+                    if read.format_version != FORMAT_VERSION {
+                        return Err(DumpError::Format(format!(
+                            "dump has format version {}, but only version {FORMAT_VERSION} is \
+                             supported",
+                            read.format_version
+                        )));
+                    }
+                    manifest = Some(read);
+                }
+                COMICS_PATH => dump.comics = parse_json_lines(&path, &contents)?,
+                ITEMS_PATH => dump.items = parse_json_lines(&path, &contents)?,
+                OCCURRENCES_PATH => dump.occurrences = parse_json_lines(&path, &contents)?,
+                NEWS_PATH => dump.news = parse_json_lines(&path, &contents)?,
+                ITEM_IMAGES_PATH => dump.item_images = parse_json_lines(&path, &contents)?,
+                LOG_ENTRIES_PATH => dump.log_entries = parse_json_lines(&path, &contents)?,
+                _ => {
+                    let image_id = path
+                        .strip_prefix("images/")
+                        .and_then(|id| id.parse().ok())
+                        .ok_or_else(|| {
+                            DumpError::Format(format!("unexpected file {path:?} in dump"))
+                        })?;
+                    image_data.insert(image_id, contents);
+                }
+            }
+        }
+
+        dump.manifest =
+            manifest.ok_or_else(|| DumpError::Format(format!("{MANIFEST_PATH} is missing")))?;
+
+        for image in &mut dump.item_images {
+            image.data = image_data.remove(&image.id).ok_or_else(|| {
+                DumpError::Format(format!(
+                    "image data for item image #{} is missing",
+                    image.id
+                ))
+            })?;
+        }
+
+        if dump.counts() != dump.manifest.counts {
+            return Err(DumpError::Format(format!(
+                "dump contents {:?} do not match its manifest {:?}",
+                dump.counts(),
+                dump.manifest.counts
+            )));
+        }
+
+        Ok(dump)
+    }
+
+    /// Checks that every reference inside the dump resolves to a row in the
+    /// dump, and that the image data is intact.
+    #[must_use]
+    pub fn validate(&self) -> Vec<IntegrityProblem> {
+        let mut problems = Vec::new();
+
+        let comic_ids = unique_keys(
+            &self.comics,
+            |c| c.id,
+            |id| {
+                problems.push(IntegrityProblem::DuplicateComic(id));
+            },
+        );
+        let item_ids = unique_keys(
+            &self.items,
+            |i| i.id,
+            |id| {
+                problems.push(IntegrityProblem::DuplicateItem(id));
+            },
+        );
+        unique_keys(
+            &self.news,
+            |n| n.comic_id,
+            |comic_id| {
+                problems.push(IntegrityProblem::DuplicateNews(comic_id));
+            },
+        );
+        unique_keys(
+            &self.log_entries,
+            |l| l.id,
+            |id| {
+                problems.push(IntegrityProblem::DuplicateLogEntry(id));
+            },
+        );
+        unique_keys(
+            &self.occurrences,
+            |&o| o,
+            |o| {
+                problems.push(IntegrityProblem::DuplicateOccurrence {
+                    comic_id: o.comic_id,
+                    item_id: o.item_id,
+                });
+            },
+        );
+
+        let mut image_owners = BTreeMap::new();
+        for image in &self.item_images {
+            if image_owners.insert(image.id, image.item_id).is_some() {
+                problems.push(IntegrityProblem::DuplicateItemImage(image.id));
+            }
+            if !item_ids.contains(&image.item_id) {
+                problems.push(IntegrityProblem::ImageOfUnknownItem {
+                    image_id: image.id,
+                    item_id: image.item_id,
+                });
+            }
+            if crc32c::crc32c(&image.data) != image.crc32c_hash {
+                problems.push(IntegrityProblem::ImageChecksumMismatch(image.id));
+            }
+        }
+
+        for occurrence in &self.occurrences {
+            if !comic_ids.contains(&occurrence.comic_id) {
+                problems.push(IntegrityProblem::OccurrenceOfUnknownComic {
+                    comic_id: occurrence.comic_id,
+                    item_id: occurrence.item_id,
+                });
+            }
+            if !item_ids.contains(&occurrence.item_id) {
+                problems.push(IntegrityProblem::OccurrenceOfUnknownItem {
+                    comic_id: occurrence.comic_id,
+                    item_id: occurrence.item_id,
+                });
+            }
+        }
+
+        for news in &self.news {
+            if !comic_ids.contains(&news.comic_id) {
+                problems.push(IntegrityProblem::NewsForUnknownComic(news.comic_id));
+            }
+        }
+
+        for item in &self.items {
+            let Some(image_id) = item.primary_image else {
+                continue;
+            };
+            match image_owners.get(&image_id) {
+                None => problems.push(IntegrityProblem::UnknownPrimaryImage {
+                    item_id: item.id,
+                    image_id,
+                }),
+                Some(&owner_id) if owner_id != item.id => {
+                    problems.push(IntegrityProblem::PrimaryImageOfOtherItem {
+                        item_id: item.id,
+                        image_id,
+                        owner_id,
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        problems
+    }
+
+    /// Loads the dump into the database in a single transaction, on top of
+    /// whatever is already there. Rows are matched by their ids: comics,
+    /// items, news and item images in the dump replace existing ones, while
+    /// occurrences and log entries are only added. Nothing is ever deleted.
+    ///
+    /// Log entries are attributed to one permissionless placeholder token
+    /// per contributor, named `Imported contributor N`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DumpError::Integrity`] without touching the database if
+    /// [`Self::validate`] finds any problems, or a database error if any of
+    /// the queries fail, in which case nothing is imported.
+    #[tracing::instrument(skip(self, conn), fields(counts = ?self.manifest.counts))]
+    pub async fn import(
+        &self,
+        conn: &mut sqlx::MySqlConnection,
+        options: ImportOptions,
+    ) -> Result<ImportReport, DumpError> {
+        let problems = self.validate();
+        if !problems.is_empty() {
+            return Err(DumpError::Integrity(problems));
+        }
+
+        let mut transaction = conn.begin().await?;
+        let mut report = ImportReport::default();
+
+        let existing_comic_ids: HashSet<u16> = Comic::all_including_hidden(&mut *transaction)
+            .await?
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        for comic in &self.comics {
+            Comic::upsert(&mut *transaction, &comic.to_model()).await?;
+            report
+                .comics
+                .record(!existing_comic_ids.contains(&comic.id));
+        }
+
+        let existing_item_ids: HashSet<u16> = Item::all(&mut *transaction)
+            .await?
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+        for item in &self.items {
+            Item::upsert_without_primary_image(&mut *transaction, &item.to_model()).await?;
+            report.items.record(!existing_item_ids.contains(&item.id));
+        }
+
+        let existing_image_ids: HashSet<u32> = Item::all_image_records(&mut *transaction)
+            .await?
+            .into_iter()
+            .map(|i| i.id)
+            .collect();
+        for image in &self.item_images {
+            Item::upsert_image(
+                &mut *transaction,
+                image.id,
+                image.item_id,
+                &image.data,
+                image.crc32c_hash,
+            )
+            .await?;
+            report
+                .item_images
+                .record(!existing_image_ids.contains(&image.id));
+        }
+
+        for item in &self.items {
+            if let Some(image_id) = item.primary_image {
+                Item::set_primary_image(&mut *transaction, item.id, image_id).await?;
+            }
+        }
+
+        for occurrence in &self.occurrences {
+            let result = Occurrence::create_if_missing(
+                &mut *transaction,
+                occurrence.item_id,
+                occurrence.comic_id,
+            )
+            .await?;
+            report.occurrences.record(result.rows_affected() > 0);
+        }
+
+        let existing_news_ids: HashSet<u16> = News::all(&mut *transaction)
+            .await?
+            .into_iter()
+            .map(|n| n.comic_id)
+            .collect();
+        for news in &self.news {
+            News::upsert(&mut *transaction, &news.to_model()).await?;
+            report
+                .news
+                .record(!existing_news_ids.contains(&news.comic_id));
+        }
+
+        let mut contributor_tokens: HashMap<u32, String> = HashMap::new();
+        for entry in &self.log_entries {
+            let token = if let Some(token) = contributor_tokens.get(&entry.contributor) {
+                token.clone()
+            } else {
+                let token =
+                    placeholder_token_for_contributor(&mut transaction, entry.contributor).await?;
+                contributor_tokens.insert(entry.contributor, token.clone());
+                token
+            };
+            let result =
+                LogEntry::create_with_id_if_missing(&mut *transaction, &entry.to_model(token))
+                    .await?;
+            report.log_entries.record(result.rows_affected() > 0);
+        }
+
+        if options.dry_run {
+            transaction.rollback().await?;
+        } else {
+            transaction.commit().await?;
+            report.committed = true;
+        }
+
+        Ok(report)
+    }
+}
+
+async fn placeholder_token_for_contributor(
+    conn: &mut sqlx::MySqlConnection,
+    contributor: u32,
+) -> Result<String, DumpError> {
+    let identifier = format!("Imported contributor {contributor}");
+    if let Some(token) = Token::id_by_identifier(&mut *conn, &identifier).await? {
+        return Ok(token);
+    }
+
+    let token = sqlx::types::uuid::Builder::from_random_bytes(rand::random())
+        .into_uuid()
+        .to_string();
+    Token::create(&mut *conn, &token, &identifier).await?;
+
+    Ok(token)
+}
+
+fn parse_json_lines<T: DeserializeOwned>(path: &str, contents: &[u8]) -> Result<Vec<T>, DumpError> {
+    contents
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| {
+            serde_json::from_slice(line)
+                .map_err(|e| DumpError::Format(format!("{path}, line {}: {e}", index + 1)))
+        })
+        .collect()
+}
+
+/// Collects the keys of `rows`, reporting every key after its first
+/// appearance to `duplicate`.
+fn unique_keys<T, K, F, D>(rows: &[T], key: F, mut duplicate: D) -> HashSet<K>
+where
+    K: Eq + std::hash::Hash + Copy,
+    F: Fn(&T) -> K,
+    D: FnMut(K),
+{
+    let mut keys = HashSet::with_capacity(rows.len());
+    for row in rows {
+        let key = key(row);
+        if !keys.insert(key) {
+            duplicate(key);
+        }
+    }
+    keys
+}
+
+impl DumpComic {
+    fn to_model(&self) -> Comic {
+        Comic {
+            id: self.id,
+            image_type: self.image_type,
+            is_guest_comic: self.is_guest_comic.into(),
+            is_non_canon: self.is_non_canon.into(),
+            has_no_cast: self.has_no_cast.into(),
+            has_no_location: self.has_no_location.into(),
+            has_no_storyline: self.has_no_storyline.into(),
+            has_no_title: self.has_no_title.into(),
+            has_no_tagline: self.has_no_tagline.into(),
+            title: self.title.clone(),
+            tagline: self.tagline.clone(),
+            publish_date: self.publish_date,
+            is_accurate_publish_date: self.is_accurate_publish_date.into(),
+            hidden: self.hidden.into(),
+            // Maintained by the database; upserts never write it.
+            version: 0,
+        }
+    }
+}
+
+impl DumpItem {
+    fn to_model(&self) -> Item {
+        Item {
+            id: self.id,
+            short_name: self.short_name.clone(),
+            name: self.name.clone(),
+            r#type: self.r#type.clone(),
+            color_blue: self.color_blue,
+            color_green: self.color_green,
+            color_red: self.color_red,
+            primary_image: self.primary_image,
+            start_comic_id: self.start_comic_id,
+            end_comic_id: self.end_comic_id,
+            version: 0,
+        }
+    }
+}
+
+impl DumpNews {
+    fn to_model(&self) -> News {
+        News {
+            comic_id: self.comic_id,
+            last_updated: self.last_updated,
+            news: self.news.clone(),
+            update_factor: self.update_factor,
+            is_locked: self.is_locked.into(),
+        }
+    }
+}
+
+impl DumpLogEntry {
+    fn to_model(&self, user_token: String) -> LogEntry {
+        LogEntry {
+            id: self.id,
+            user_token,
+            date_time: self.date_time,
+            action: self.action.clone(),
+            involved_comic: self.comic_involved,
+            involved_item: self.item_involved,
+        }
+    }
+}
+
+impl DumpItemImage {
+    /// Creates an image record, computing the hash from `data`.
+    #[must_use]
+    pub fn new(id: u32, item_id: u16, data: Vec<u8>) -> Self {
+        Self {
+            id,
+            item_id,
+            crc32c_hash: crc32c::crc32c(&data),
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::super::{DumpCounts, DumpOccurrence};
+    use super::*;
+
+    fn comic(id: u16) -> DumpComic {
+        DumpComic {
+            id,
+            image_type: 1,
+            title: format!("Comic {id}"),
+            tagline: None,
+            publish_date: None,
+            is_accurate_publish_date: false,
+            is_guest_comic: false,
+            is_non_canon: false,
+            has_no_cast: false,
+            has_no_location: false,
+            has_no_storyline: false,
+            has_no_title: false,
+            has_no_tagline: false,
+            hidden: false,
+        }
+    }
+
+    fn item(id: u16, primary_image: Option<u32>) -> DumpItem {
+        DumpItem {
+            id,
+            short_name: format!("Item {id}"),
+            name: format!("Item {id}"),
+            r#type: "cast".to_owned(),
+            color_red: 0,
+            color_green: 0,
+            color_blue: 0,
+            primary_image,
+            start_comic_id: None,
+            end_comic_id: None,
+        }
+    }
+
+    fn dump() -> Dump {
+        let mut dump = Dump {
+            manifest: Manifest {
+                format_version: FORMAT_VERSION,
+                exported_at: chrono::DateTime::default(),
+                includes_hidden: true,
+                counts: DumpCounts::default(),
+            },
+            comics: vec![comic(1), comic(2)],
+            items: vec![item(1, Some(10)), item(2, None)],
+            occurrences: vec![DumpOccurrence {
+                comic_id: 1,
+                item_id: 1,
+            }],
+            news: Vec::new(),
+            item_images: vec![DumpItemImage::new(10, 1, b"GIF89a".to_vec())],
+            log_entries: vec![DumpLogEntry {
+                id: 1,
+                contributor: 1,
+                date_time: NaiveDateTime::default(),
+                action: "Added Item 1 to comic #1".to_owned(),
+                comic_involved: Some(1),
+                item_involved: Some(1),
+            }],
+        };
+        dump.manifest.counts = dump.counts();
+        dump
+    }
+
+    #[test]
+    fn written_dump_reads_back() {
+        let original = dump();
+        let mut bytes = Vec::new();
+        original.write_to(&mut bytes).unwrap();
+
+        let read = Dump::read_from(&bytes[..]).unwrap();
+
+        assert_eq!(read.counts(), original.counts());
+        assert_eq!(read.item_images[0].data, b"GIF89a".to_vec());
+        assert_eq!(read.log_entries[0].action, "Added Item 1 to comic #1");
+        assert!(read.validate().is_empty());
+    }
+
+    #[test]
+    fn validation_catches_broken_references() {
+        let mut dump = dump();
+        dump.occurrences.push(DumpOccurrence {
+            comic_id: 3,
+            item_id: 2,
+        });
+        dump.items[1].primary_image = Some(10);
+        dump.item_images[0].crc32c_hash ^= 1;
+
+        assert_eq!(
+            dump.validate(),
+            vec![
+                IntegrityProblem::ImageChecksumMismatch(10),
+                IntegrityProblem::OccurrenceOfUnknownComic {
+                    comic_id: 3,
+                    item_id: 2
+                },
+                IntegrityProblem::PrimaryImageOfOtherItem {
+                    item_id: 2,
+                    image_id: 10,
+                    owner_id: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn newer_format_versions_are_rejected() {
+        let mut dump = dump();
+        dump.manifest.format_version = FORMAT_VERSION + 1;
+        let mut bytes = Vec::new();
+        dump.write_to(&mut bytes).unwrap();
+
+        assert!(matches!(
+            Dump::read_from(&bytes[..]),
+            Err(DumpError::Format(_))
+        ));
+    }
+}
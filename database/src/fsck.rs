@@ -0,0 +1,370 @@
+//! Structural integrity checks of the database, with repairs.
+//!
+//! Unlike the editorial anomalies, these are rows that reference something
+//! that is not there (or not theirs), or cache bookkeeping that no longer
+//! matches the data. [`check`] finds them; [`repair`] fixes them.
+
+use std::fmt;
+
+use sqlx::Connection;
+
+/// The `stats_cache_meta` keys the triggers and refreshers rely on.
+pub const STATS_CACHE_META_KEYS: [&str; 4] = [
+    "stats_responses",
+    "cast_rank_stints",
+    "location_rank_stints",
+    "storyline_rank_stints",
+];
+
+/// A structural problem found by [`check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsckProblem {
+    OrphanedItemImage {
+        image_id: u32,
+        item_id: u16,
+    },
+    UnknownPrimaryImage {
+        item_id: u16,
+        image_id: u32,
+    },
+    PrimaryImageOfOtherItem {
+        item_id: u16,
+        image_id: u32,
+        owner_id: u16,
+    },
+    OccurrenceOfMissingComic {
+        comic_id: u16,
+        item_id: u16,
+    },
+    OccurrenceOfMissingItem {
+        comic_id: u16,
+        item_id: u16,
+    },
+    MissingStatsCacheMeta(&'static str),
+    /// The rank stints of `item_type` are marked as up to date, but some are
+    /// led by items that are gone, of another type or not in the comic the
+    /// stint starts at.
+    StaleRankStints {
+        item_type: String,
+        stints: i64,
+    },
+}
+
+impl FsckProblem {
+    /// What [`repair`] does about the problem.
+    #[must_use]
+    pub fn repair_description(&self) -> String {
+        match self {
+            Self::OrphanedItemImage { image_id, .. } => format!("delete item image #{image_id}"),
+            Self::UnknownPrimaryImage { item_id, .. }
+            | Self::PrimaryImageOfOtherItem { item_id, .. } => {
+                format!("clear the primary image of item #{item_id}")
+            }
+            Self::OccurrenceOfMissingComic { comic_id, item_id }
+            | Self::OccurrenceOfMissingItem { comic_id, item_id } => {
+                format!("delete the occurrence of item #{item_id} in comic #{comic_id}")
+            }
+            Self::MissingStatsCacheMeta(key) => {
+                format!("add stats_cache_meta row {key:?}, marked as needing a refresh")
+            }
+            Self::StaleRankStints { item_type, .. } => {
+                format!("mark the {item_type} rank stints as needing a full refresh")
+            }
+        }
+    }
+}
+
+impl fmt::Display for FsckProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OrphanedItemImage { image_id, item_id } => write!(
+                f,
+                "item image #{image_id} belongs to item #{item_id}, which does not exist"
+            ),
+            Self::UnknownPrimaryImage { item_id, image_id } => write!(
+                f,
+                "item #{item_id} has primary image #{image_id}, which does not exist"
+            ),
+            Self::PrimaryImageOfOtherItem {
+                item_id,
+                image_id,
+                owner_id,
+            } => write!(
+                f,
+                "item #{item_id} has primary image #{image_id}, which belongs to item #{owner_id}"
+            ),
+            Self::OccurrenceOfMissingComic { comic_id, item_id } => write!(
+                f,
+                "item #{item_id} occurs in comic #{comic_id}, which does not exist"
+            ),
+            Self::OccurrenceOfMissingItem { comic_id, item_id } => write!(
+                f,
+                "comic #{comic_id} features item #{item_id}, which does not exist"
+            ),
+            Self::MissingStatsCacheMeta(key) => {
+                write!(f, "stats_cache_meta has no {key:?} row")
+            }
+            Self::StaleRankStints { item_type, stints } => write!(
+                f,
+                "{stints} {item_type} rank stint(s) no longer match the data, \
+                 but the cache is not marked as needing a refresh"
+            ),
+        }
+    }
+}
+
+/// Runs every check.
+///
+/// # Errors
+///
+/// Returns a database error if any of the queries fail.
+#[tracing::instrument(skip(conn))]
+pub async fn check(conn: &mut sqlx::MySqlConnection) -> sqlx::Result<Vec<FsckProblem>> {
+    let mut problems = Vec::new();
+    problems.extend(orphaned_item_images(&mut *conn).await?);
+    problems.extend(broken_primary_images(&mut *conn).await?);
+    problems.extend(orphaned_occurrences(&mut *conn).await?);
+    problems.extend(missing_stats_cache_meta(&mut *conn).await?);
+    problems.extend(stale_rank_stints(&mut *conn).await?);
+    Ok(problems)
+}
+
+async fn orphaned_item_images(
+    conn: &mut sqlx::MySqlConnection,
+) -> sqlx::Result<impl Iterator<Item = FsckProblem>> {
+    Ok(sqlx::query!(
+        r#"
+            SELECT `ii`.`id` AS `image_id`, `ii`.`item_id`
+            FROM `ItemImage` `ii`
+            LEFT JOIN `Item` `i` ON `i`.`id` = `ii`.`item_id`
+            WHERE `i`.`id` IS NULL
+            ORDER BY `ii`.`id`
+        "#
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|r| FsckProblem::OrphanedItemImage {
+        image_id: r.image_id,
+        item_id: r.item_id,
+    }))
+}
+
+async fn broken_primary_images(
+    conn: &mut sqlx::MySqlConnection,
+) -> sqlx::Result<impl Iterator<Item = FsckProblem>> {
+    Ok(sqlx::query!(
+        r#"
+            SELECT
+                `i`.`id` AS `item_id`,
+                `i`.`primary_image` AS `image_id!`,
+                `ii`.`item_id` AS `owner_id?`
+            FROM `Item` `i`
+            LEFT JOIN `ItemImage` `ii` ON `ii`.`id` = `i`.`primary_image`
+            WHERE `i`.`primary_image` IS NOT NULL
+                AND (`ii`.`id` IS NULL OR `ii`.`item_id` <> `i`.`id`)
+            ORDER BY `i`.`id`
+        "#
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|r| {
+        r.owner_id.map_or(
+            FsckProblem::UnknownPrimaryImage {
+                item_id: r.item_id,
+                image_id: r.image_id,
+            },
+            |owner_id| FsckProblem::PrimaryImageOfOtherItem {
+                item_id: r.item_id,
+                image_id: r.image_id,
+                owner_id,
+            },
+        )
+    }))
+}
+
+async fn orphaned_occurrences(
+    conn: &mut sqlx::MySqlConnection,
+) -> sqlx::Result<impl Iterator<Item = FsckProblem>> {
+    Ok(sqlx::query!(
+        r#"
+            SELECT
+                `o`.`comic_id`,
+                `o`.`item_id`,
+                `c`.`id` IS NULL AS `missing_comic`
+            FROM `Occurrence` `o`
+            LEFT JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+            LEFT JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+            WHERE `c`.`id` IS NULL OR `i`.`id` IS NULL
+            ORDER BY `o`.`comic_id`, `o`.`item_id`
+        "#
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|r| {
+        if r.missing_comic != 0 {
+            FsckProblem::OccurrenceOfMissingComic {
+                comic_id: r.comic_id,
+                item_id: r.item_id,
+            }
+        } else {
+            FsckProblem::OccurrenceOfMissingItem {
+                comic_id: r.comic_id,
+                item_id: r.item_id,
+            }
+        }
+    }))
+}
+
+async fn missing_stats_cache_meta(
+    conn: &mut sqlx::MySqlConnection,
+) -> sqlx::Result<impl Iterator<Item = FsckProblem>> {
+    let keys = sqlx::query_scalar!("SELECT `cache_key` FROM `stats_cache_meta`")
+        .fetch_all(conn)
+        .await?;
+    Ok(STATS_CACHE_META_KEYS
+        .into_iter()
+        .filter(move |key| !keys.iter().any(|k| k == key))
+        .map(FsckProblem::MissingStatsCacheMeta))
+}
+
+async fn stale_rank_stints(
+    conn: &mut sqlx::MySqlConnection,
+) -> sqlx::Result<impl Iterator<Item = FsckProblem>> {
+    Ok(sqlx::query!(
+        r#"
+            SELECT `s`.`item_type`, COUNT(*) AS `stints`
+            FROM `rank_leadership_stints` `s`
+            JOIN `stats_cache_meta` `m`
+                ON `m`.`cache_key` = CONCAT(`s`.`item_type`, '_rank_stints')
+            LEFT JOIN `Item` `i` ON `i`.`id` = `s`.`item_id`
+            WHERE `m`.`needs_refresh` = 0
+                AND (
+                    `i`.`id` IS NULL
+                    OR `i`.`type` <> `s`.`item_type`
+                    OR NOT EXISTS (
+                        SELECT 1 FROM `Occurrence` `o`
+                        WHERE `o`.`comic_id` = `s`.`from_comic` AND `o`.`item_id` = `s`.`item_id`
+                    )
+                )
+            GROUP BY `s`.`item_type`
+            ORDER BY `s`.`item_type`
+        "#
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|r| FsckProblem::StaleRankStints {
+        item_type: r.item_type,
+        stints: r.stints,
+    }))
+}
+
+/// Repairs `problems` in a single transaction, calling `log` with each
+/// problem just before it is repaired. Nothing is changed if any repair
+/// fails.
+///
+/// # Errors
+///
+/// Returns a database error if any of the queries fail.
+#[tracing::instrument(skip(conn, problems, log), fields(problems = problems.len()))]
+pub async fn repair(
+    conn: &mut sqlx::MySqlConnection,
+    problems: &[FsckProblem],
+    mut log: impl FnMut(&FsckProblem),
+) -> sqlx::Result<()> {
+    let mut transaction = conn.begin().await?;
+
+    for problem in problems {
+        log(problem);
+        match problem {
+            FsckProblem::OrphanedItemImage { image_id, .. } => {
+                sqlx::query!("DELETE FROM `ItemImage` WHERE `id` = ?", image_id)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            FsckProblem::UnknownPrimaryImage { item_id, .. }
+            | FsckProblem::PrimaryImageOfOtherItem { item_id, .. } => {
+                sqlx::query!(
+                    "UPDATE `Item` SET `primary_image` = NULL WHERE `id` = ?",
+                    item_id
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+            FsckProblem::OccurrenceOfMissingComic { comic_id, item_id }
+            | FsckProblem::OccurrenceOfMissingItem { comic_id, item_id } => {
+                sqlx::query!(
+                    "DELETE FROM `Occurrence` WHERE `comic_id` = ? AND `item_id` = ?",
+                    comic_id,
+                    item_id
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+            FsckProblem::MissingStatsCacheMeta(key) => {
+                sqlx::query!(
+                    r#"
+                        INSERT INTO `stats_cache_meta` (`cache_key`, `last_comic_id`, `needs_refresh`)
+                        VALUES (?, 0, 1)
+                    "#,
+                    key
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+            FsckProblem::StaleRankStints { item_type, .. } => {
+                sqlx::query!(
+                    r#"
+                        UPDATE `stats_cache_meta`
+                        SET `needs_refresh` = 1, `dirty_from_comic` = NULL
+                        WHERE `cache_key` = CONCAT(?, '_rank_stints')
+                    "#,
+                    item_type
+                )
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+    }
+
+    transaction.commit().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_describe_their_repair() {
+        let problem = FsckProblem::PrimaryImageOfOtherItem {
+            item_id: 3,
+            image_id: 40,
+            owner_id: 5,
+        };
+
+        assert_eq!(
+            problem.to_string(),
+            "item #3 has primary image #40, which belongs to item #5"
+        );
+        assert_eq!(
+            problem.repair_description(),
+            "clear the primary image of item #3"
+        );
+    }
+
+    #[test]
+    fn occurrences_of_missing_comics_are_deleted() {
+        let problem = FsckProblem::OccurrenceOfMissingComic {
+            comic_id: 4000,
+            item_id: 1,
+        };
+
+        assert_eq!(
+            problem.repair_description(),
+            "delete the occurrence of item #1 in comic #4000"
+        );
+    }
+}
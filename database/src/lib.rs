@@ -1,9 +1,11 @@
 #![allow(missing_docs)]
 
-use sqlx::migrate::MigrateError;
+use sqlx::migrate::{Migrate as _, MigrateError, Migrator};
 use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions};
 use std::ops::Deref;
 
+pub mod dump;
+pub mod fsck;
 pub mod models;
 
 type DatabaseDriver = sqlx::MySql;
@@ -25,18 +27,31 @@ impl DbPool {
         max_connections: u32,
         min_connections: u32,
     ) -> Self {
-        let database_options = database_url
-            .parse::<MySqlConnectOptions>()
-            .expect("failed to parse database URL");
+        Self::try_create(database_url, max_connections, min_connections)
+            .await
+            .expect("failed to create database pool")
+    }
 
-        Self(
+    /// Like [`Self::create`], for callers that report errors themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database URL cannot be parsed or the
+    /// connection pool cannot be created.
+    pub async fn try_create(
+        database_url: &str,
+        max_connections: u32,
+        min_connections: u32,
+    ) -> sqlx::Result<Self> {
+        let database_options = database_url.parse::<MySqlConnectOptions>()?;
+
+        Ok(Self(
             MySqlPoolOptions::new()
                 .max_connections(max_connections)
                 .min_connections(min_connections)
                 .connect_with(database_options)
-                .await
-                .expect("failed to create database pool"),
-        )
+                .await?,
+        ))
     }
 }
 
@@ -48,10 +63,29 @@ impl Deref for DbPool {
     }
 }
 
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 /// # Errors
 ///
 /// Returns a database error if the query fails.
 #[tracing::instrument(skip(pool))]
 pub async fn migrate(pool: &DbPool) -> Result<(), MigrateError> {
-    sqlx::migrate!("./migrations").run(&**pool).await
+    MIGRATOR.run(&**pool).await
+}
+
+/// The versions of the migrations that have not been applied to the database.
+///
+/// # Errors
+///
+/// Returns a database error if the applied migrations cannot be listed.
+#[tracing::instrument(skip(pool))]
+pub async fn pending_migrations(pool: &DbPool) -> Result<Vec<i64>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    let applied = conn.list_applied_migrations().await?;
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .filter(|m| !applied.iter().any(|a| a.version == m.version))
+        .map(|m| m.version)
+        .collect())
 }
@@ -1,20 +1,30 @@
+mod anomaly;
+mod change_log;
 mod comic;
+mod comic_query;
+mod era;
 mod item;
 mod item_type;
 mod log_entry;
 mod news;
 mod occurrence;
+mod search;
 pub mod stats;
 mod token;
 
 use std::borrow::Borrow;
 
+pub use anomaly::*;
+pub use change_log::*;
 pub use comic::*;
+pub use comic_query::*;
+pub use era::*;
 pub use item::*;
 pub use item_type::*;
 pub use log_entry::*;
 pub use news::*;
 pub use occurrence::*;
+pub use search::*;
 pub use token::*;
 
 #[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
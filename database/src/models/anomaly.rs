@@ -0,0 +1,382 @@
+//! Queries for data that contradicts itself, for editors to clean up.
+//!
+//! Every check reads one page of its findings, `limit` of them from `offset`
+//! on; [`AnomalyCounts`] counts the findings of all of them in one query.
+
+use chrono::NaiveDateTime;
+
+/// An item occurring in a comic it should not occur in.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OccurrenceAnomaly {
+    pub comic_id: u16,
+    pub item_id: u16,
+    pub item_name: String,
+}
+
+impl OccurrenceAnomaly {
+    /// Cast members in comics marked as having no cast.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn cast_in_comics_without_cast<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `o`.`comic_id`,
+                    `o`.`item_id`,
+                    `i`.`name` AS `item_name`
+                FROM `Occurrence` `o`
+                JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                WHERE `i`.`type` = 'cast' AND `c`.`has_no_cast`
+                ORDER BY `o`.`comic_id`, `o`.`item_id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Locations in comics marked as having no location.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn locations_in_comics_without_location<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `o`.`comic_id`,
+                    `o`.`item_id`,
+                    `i`.`name` AS `item_name`
+                FROM `Occurrence` `o`
+                JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                WHERE `i`.`type` = 'location' AND `c`.`has_no_location`
+                ORDER BY `o`.`comic_id`, `o`.`item_id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Storylines in comics marked as having no storyline.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn storylines_in_comics_without_storyline<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `o`.`comic_id`,
+                    `o`.`item_id`,
+                    `i`.`name` AS `item_name`
+                FROM `Occurrence` `o`
+                JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                WHERE `i`.`type` = 'storyline' AND `c`.`has_no_storyline`
+                ORDER BY `o`.`comic_id`, `o`.`item_id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Storylines in comics outside of their lifecycle, before
+    /// `start_comic_id` or from `end_comic_id` on.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn storylines_outside_lifecycle<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `o`.`comic_id`,
+                    `o`.`item_id`,
+                    `i`.`name` AS `item_name`
+                FROM `Occurrence` `o`
+                JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                WHERE `i`.`type` = 'storyline'
+                    AND (
+                        `o`.`comic_id` < `i`.`start_comic_id`
+                        OR `o`.`comic_id` >= `i`.`end_comic_id`
+                    )
+                ORDER BY `o`.`comic_id`, `o`.`item_id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// An item that is not in any comic.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UnusedItemAnomaly {
+    pub item_id: u16,
+    pub item_name: String,
+}
+
+impl UnusedItemAnomaly {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `i`.`id` AS `item_id`,
+                    `i`.`name` AS `item_name`
+                FROM `Item` `i`
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM `Occurrence` `o` WHERE `o`.`item_id` = `i`.`id`
+                )
+                ORDER BY `i`.`id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// A hidden comic that still has items.
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct HiddenComicAnomaly {
+    pub comic_id: u16,
+    pub occurrences: i64,
+}
+
+impl HiddenComicAnomaly {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `c`.`id` AS `comic_id`,
+                    COUNT(*) AS `occurrences`
+                FROM `Comic` `c`
+                JOIN `Occurrence` `o` ON `o`.`comic_id` = `c`.`id`
+                WHERE `c`.`hidden`
+                GROUP BY `c`.`id`
+                ORDER BY `c`.`id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// A comic published before the (non-hidden) comic preceding it.
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct PublishDateAnomaly {
+    pub comic_id: u16,
+    pub publish_date: NaiveDateTime,
+    pub previous_comic_id: u16,
+    pub previous_publish_date: NaiveDateTime,
+}
+
+impl PublishDateAnomaly {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        limit: u32,
+        offset: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                WITH `dated_comics` AS (
+                    SELECT
+                        `id`,
+                        `publish_date`,
+                        LAG(`id`) OVER (ORDER BY `id`) AS `previous_comic_id`,
+                        LAG(`publish_date`) OVER (ORDER BY `id`) AS `previous_publish_date`
+                    FROM `Comic`
+                    WHERE NOT `hidden` AND `publish_date` IS NOT NULL
+                )
+                SELECT
+                    `id` AS `comic_id`,
+                    `publish_date` AS `publish_date!`,
+                    `previous_comic_id` AS `previous_comic_id!`,
+                    `previous_publish_date` AS `previous_publish_date!`
+                FROM `dated_comics`
+                WHERE `publish_date` < `previous_publish_date`
+                ORDER BY `id`
+                LIMIT ? OFFSET ?
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// How many anomalies each check finds, counted without reading them.
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct AnomalyCounts {
+    pub cast_in_comics_without_cast: i64,
+    pub locations_in_comics_without_location: i64,
+    pub storylines_in_comics_without_storyline: i64,
+    pub storylines_outside_lifecycle: i64,
+    pub unused_items: i64,
+    pub hidden_comics: i64,
+    pub publish_dates: i64,
+}
+
+impl AnomalyCounts {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn get<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Self>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                WITH `dated_comics` AS (
+                    SELECT
+                        `publish_date`,
+                        LAG(`publish_date`) OVER (ORDER BY `id`) AS `previous_publish_date`
+                    FROM `Comic`
+                    WHERE NOT `hidden` AND `publish_date` IS NOT NULL
+                )
+                SELECT
+                    (
+                        SELECT COUNT(*)
+                        FROM `Occurrence` `o`
+                        JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                        JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                        WHERE `i`.`type` = 'cast' AND `c`.`has_no_cast`
+                    ) AS `cast_in_comics_without_cast!`,
+                    (
+                        SELECT COUNT(*)
+                        FROM `Occurrence` `o`
+                        JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                        JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                        WHERE `i`.`type` = 'location' AND `c`.`has_no_location`
+                    ) AS `locations_in_comics_without_location!`,
+                    (
+                        SELECT COUNT(*)
+                        FROM `Occurrence` `o`
+                        JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                        JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                        WHERE `i`.`type` = 'storyline' AND `c`.`has_no_storyline`
+                    ) AS `storylines_in_comics_without_storyline!`,
+                    (
+                        SELECT COUNT(*)
+                        FROM `Occurrence` `o`
+                        JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                        WHERE `i`.`type` = 'storyline'
+                            AND (
+                                `o`.`comic_id` < `i`.`start_comic_id`
+                                OR `o`.`comic_id` >= `i`.`end_comic_id`
+                            )
+                    ) AS `storylines_outside_lifecycle!`,
+                    (
+                        SELECT COUNT(*)
+                        FROM `Item` `i`
+                        WHERE NOT EXISTS (
+                            SELECT 1 FROM `Occurrence` `o` WHERE `o`.`item_id` = `i`.`id`
+                        )
+                    ) AS `unused_items!`,
+                    (
+                        SELECT COUNT(DISTINCT `c`.`id`)
+                        FROM `Comic` `c`
+                        JOIN `Occurrence` `o` ON `o`.`comic_id` = `c`.`id`
+                        WHERE `c`.`hidden`
+                    ) AS `hidden_comics!`,
+                    (
+                        SELECT COUNT(*)
+                        FROM `dated_comics`
+                        WHERE `publish_date` < `previous_publish_date`
+                    ) AS `publish_dates!`
+            "#
+        )
+        .fetch_one(executor)
+        .await
+    }
+}
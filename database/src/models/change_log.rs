@@ -0,0 +1,59 @@
+/// One row of the `change_log` table, appended by triggers on every write to
+/// `Comic`, `Item`, `Occurrence`, `ItemImage` and `News`.
+#[derive(Debug)]
+pub struct ChangeLogEntry {
+    pub seq: u64,
+    pub entity: String,
+    /// The changed row's primary key; `comic_id` for news and occurrences.
+    pub entity_id: u32,
+    /// `item_id` for occurrences, otherwise `None`.
+    pub secondary_id: Option<u32>,
+    pub deleted: u8,
+}
+
+impl ChangeLogEntry {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn since<'e, 'c: 'e, E>(
+        executor: E,
+        since: u64,
+        limit: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT `seq`, `entity`, `entity_id`, `secondary_id`, `deleted`
+                FROM `change_log`
+                WHERE `seq` > ?
+                ORDER BY `seq` ASC
+                LIMIT ?
+            "#,
+            since,
+            limit,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn latest_seq<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Option<u64>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT MAX(`seq`) FROM `change_log`
+            "#,
+        )
+        .fetch_one(executor)
+        .await
+    }
+}
@@ -1,5 +1,6 @@
 use chrono::{DateTime, NaiveDateTime, Utc};
 use futures::TryStreamExt;
+use sqlx::{MySql, QueryBuilder};
 
 use crate::models::ItemType;
 
@@ -7,7 +8,7 @@ use crate::models::ItemType;
     clippy::struct_field_names,
     reason = "field names match the database column names"
 )]
-#[derive(Debug)]
+#[derive(Debug, sqlx::FromRow)]
 pub struct Comic {
     pub id: u16,
     pub image_type: i32,
@@ -23,6 +24,8 @@ pub struct Comic {
     pub publish_date: Option<NaiveDateTime>,
     pub is_accurate_publish_date: u8,
     pub hidden: u8,
+    /// Bumped on every update to the row; see `version_by_id_for_update`.
+    pub version: u32,
 }
 
 impl Comic {
@@ -126,6 +129,28 @@ impl Comic {
         .await
     }
 
+    /// Every comic, including hidden ones. Only meant for dumps and
+    /// maintenance; API listings go through [`Self::all_with_mapping`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all_including_hidden<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT * FROM `Comic`
+                ORDER BY `id` ASC
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -171,6 +196,49 @@ impl Comic {
         .map(|c| c == 1)
     }
 
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn version_by_id<'e, 'c: 'e, E>(executor: E, id: u16) -> sqlx::Result<Option<u32>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT `version` FROM `Comic` WHERE `id` = ?
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Like [`Self::version_by_id`], but also locks the comic row until the
+    /// end of the transaction, so that the version cannot change between
+    /// checking it and writing to the row.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn version_by_id_for_update<'e, 'c: 'e, E>(
+        executor: E,
+        id: u16,
+    ) -> sqlx::Result<Option<u32>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT `version` FROM `Comic` WHERE `id` = ? FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -265,6 +333,97 @@ impl Comic {
         .await
     }
 
+    /// Like [`Self::by_id_with_navigation_and_news`], but for every comic
+    /// with an id in `from..=to`, ordered by id.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn range_with_navigation_and_news<'e, 'c: 'e, E>(
+        executor: E,
+        from: u16,
+        to: u16,
+        include_guest_comics: Option<bool>,
+        include_non_canon_comics: Option<bool>,
+        include_hidden: bool,
+    ) -> sqlx::Result<Vec<ComicWithNavigationAndNews>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ComicWithNavigationAndNews,
+            r#"
+                SELECT
+                    `c`.`id`,
+                    `c`.`image_type`,
+                    `c`.`is_guest_comic`,
+                    `c`.`is_non_canon`,
+                    `c`.`has_no_cast`,
+                    `c`.`has_no_location`,
+                    `c`.`has_no_storyline`,
+                    `c`.`has_no_title`,
+                    `c`.`has_no_tagline`,
+                    `c`.`title`,
+                    `c`.`tagline`,
+                    `c`.`publish_date`,
+                    `c`.`is_accurate_publish_date`,
+                    (SELECT `id` FROM `Comic`
+                     WHERE `id` < `c`.`id`
+                       AND (? IS NULL OR `is_guest_comic` = ?)
+                       AND (? IS NULL OR `is_non_canon` = ?)
+                       AND NOT `hidden`
+                     ORDER BY `id` DESC LIMIT 1) AS `prev_id`,
+                    (SELECT `id` FROM `Comic`
+                     WHERE `id` > `c`.`id`
+                       AND (? IS NULL OR `is_guest_comic` = ?)
+                       AND (? IS NULL OR `is_non_canon` = ?)
+                       AND NOT `hidden`
+                     ORDER BY `id` ASC LIMIT 1) AS `next_id`,
+                    `n`.`news` AS `news`
+                FROM `Comic` `c`
+                LEFT JOIN `News` `n` ON `n`.`comic_id` = `c`.`id`
+                WHERE `c`.`id` BETWEEN ? AND ? AND (? OR NOT `c`.`hidden`)
+                ORDER BY `c`.`id` ASC
+            "#,
+            include_guest_comics,
+            include_guest_comics,
+            include_non_canon_comics,
+            include_non_canon_comics,
+            include_guest_comics,
+            include_guest_comics,
+            include_non_canon_comics,
+            include_non_canon_comics,
+            from,
+            to,
+            include_hidden,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn by_ids<'e, 'c: 'e, E>(executor: E, ids: &[u16]) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new("SELECT * FROM `Comic` WHERE `id` IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        builder.push(") ORDER BY `id` ASC");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -1329,6 +1488,73 @@ impl Comic {
         .await
     }
 
+    /// Inserts `comic` with all of its fields, or overwrites the existing
+    /// comic with the same id. Used when restoring a dump.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn upsert<'e, 'c: 'e, E>(
+        executor: E,
+        comic: &Self,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT INTO `Comic`
+                    (`id`, `image_type`, `is_guest_comic`, `is_non_canon`, `has_no_cast`, `has_no_location`, `has_no_storyline`, `has_no_title`, `has_no_tagline`, `title`, `tagline`, `publish_date`, `is_accurate_publish_date`, `hidden`)
+                VALUES
+                    (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE
+                    `image_type` = ?,
+                    `is_guest_comic` = ?,
+                    `is_non_canon` = ?,
+                    `has_no_cast` = ?,
+                    `has_no_location` = ?,
+                    `has_no_storyline` = ?,
+                    `has_no_title` = ?,
+                    `has_no_tagline` = ?,
+                    `title` = ?,
+                    `tagline` = ?,
+                    `publish_date` = ?,
+                    `is_accurate_publish_date` = ?,
+                    `hidden` = ?
+            "#,
+            comic.id,
+            comic.image_type,
+            comic.is_guest_comic,
+            comic.is_non_canon,
+            comic.has_no_cast,
+            comic.has_no_location,
+            comic.has_no_storyline,
+            comic.has_no_title,
+            comic.has_no_tagline,
+            comic.title,
+            comic.tagline,
+            comic.publish_date,
+            comic.is_accurate_publish_date,
+            comic.hidden,
+            comic.image_type,
+            comic.is_guest_comic,
+            comic.is_non_canon,
+            comic.has_no_cast,
+            comic.has_no_location,
+            comic.has_no_storyline,
+            comic.has_no_title,
+            comic.has_no_tagline,
+            comic.title,
+            comic.tagline,
+            comic.publish_date,
+            comic.is_accurate_publish_date,
+            comic.hidden,
+        )
+        .execute(executor)
+        .await
+    }
+
     /// Inserts a hidden advance comic, or updates it in place if it's still hidden.
     ///
     /// Callers must check the comic isn't already a published (non-hidden) comic before
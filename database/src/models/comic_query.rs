@@ -0,0 +1,189 @@
+use futures::TryStreamExt;
+use sqlx::{MySql, QueryBuilder};
+
+use crate::models::{Comic, ItemType};
+
+/// A boolean expression over the items featured in a comic and the comic's
+/// own flags. Compiled into a single `WHERE` clause by [`Comic::matching`].
+#[derive(Debug, Clone)]
+pub enum ComicPredicate {
+    /// The comic features the item with this id.
+    Item(u16),
+    /// The comic features at least one item of this type.
+    ItemType(ItemType),
+    GuestComic,
+    NonCanon,
+    Hidden,
+    /// All sub-expressions hold. An empty list is always true.
+    And(Vec<Self>),
+    /// At least one sub-expression holds. An empty list is always false.
+    Or(Vec<Self>),
+    Not(Box<Self>),
+}
+
+impl ComicPredicate {
+    fn push_sql<'a>(&'a self, builder: &mut QueryBuilder<'a, MySql>) {
+        match self {
+            Self::Item(item_id) => {
+                builder
+                    .push(
+                        "EXISTS (SELECT 1 FROM `Occurrence` `o` \
+                         WHERE `o`.`comic_id` = `c`.`id` AND `o`.`item_id` = ",
+                    )
+                    .push_bind(item_id)
+                    .push(")");
+            }
+            Self::ItemType(item_type) => {
+                builder
+                    .push(
+                        "EXISTS (SELECT 1 FROM `Occurrence` `o` \
+                         JOIN `Item` `i` ON `i`.`id` = `o`.`item_id` \
+                         WHERE `o`.`comic_id` = `c`.`id` AND `i`.`type` = ",
+                    )
+                    .push_bind(item_type.as_str())
+                    .push(")");
+            }
+            Self::GuestComic => {
+                builder.push("`c`.`is_guest_comic` = 1");
+            }
+            Self::NonCanon => {
+                builder.push("`c`.`is_non_canon` = 1");
+            }
+            Self::Hidden => {
+                builder.push("`c`.`hidden` = 1");
+            }
+            Self::And(operands) => Self::push_junction(builder, operands, " AND ", "TRUE"),
+            Self::Or(operands) => Self::push_junction(builder, operands, " OR ", "FALSE"),
+            Self::Not(operand) => {
+                builder.push("NOT (");
+                operand.push_sql(builder);
+                builder.push(")");
+            }
+        }
+    }
+
+    fn push_junction<'a>(
+        builder: &mut QueryBuilder<'a, MySql>,
+        operands: &'a [Self],
+        separator: &str,
+        identity: &str,
+    ) {
+        if operands.is_empty() {
+            builder.push(identity);
+            return;
+        }
+
+        builder.push("(");
+        for (index, operand) in operands.iter().enumerate() {
+            if index > 0 {
+                builder.push(separator);
+            }
+            operand.push_sql(builder);
+        }
+        builder.push(")");
+    }
+
+    fn push_where<'a>(&'a self, builder: &mut QueryBuilder<'a, MySql>, include_hidden: bool) {
+        builder.push(" FROM `Comic` `c` WHERE ");
+        if !include_hidden {
+            builder.push("NOT `c`.`hidden` AND ");
+        }
+        self.push_sql(builder);
+    }
+}
+
+impl Comic {
+    /// Comics matching `predicate`, ordered by id. Hidden comics are never
+    /// returned unless `include_hidden` is set, regardless of `predicate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor, map))]
+    pub async fn matching_with_mapping<'e, 'c: 'e, E, T, F>(
+        executor: E,
+        predicate: &ComicPredicate,
+        include_hidden: bool,
+        page: u16,
+        page_size: u16,
+        map: F,
+    ) -> sqlx::Result<Vec<T>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+        F: FnMut(Self) -> T,
+    {
+        let start_entry = u32::from(page.saturating_sub(1)) * u32::from(page_size);
+
+        let mut builder = QueryBuilder::<MySql>::new("SELECT `c`.*");
+        predicate.push_where(&mut builder, include_hidden);
+        builder
+            .push(" ORDER BY `c`.`id` ASC LIMIT ")
+            .push_bind(start_entry)
+            .push(", ")
+            .push_bind(page_size);
+
+        builder
+            .build_query_as()
+            .fetch(executor)
+            .map_ok(map)
+            .try_collect()
+            .await
+    }
+
+    /// Counts the comics matching `predicate`; see
+    /// [`Self::matching_with_mapping`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn count_matching<'e, 'c: 'e, E>(
+        executor: E,
+        predicate: &ComicPredicate,
+        include_hidden: bool,
+    ) -> sqlx::Result<i64>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let mut builder = QueryBuilder::<MySql>::new("SELECT COUNT(*)");
+        predicate.push_where(&mut builder, include_hidden);
+
+        builder.build_query_scalar().fetch_one(executor).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(predicate: &ComicPredicate) -> String {
+        let mut builder = QueryBuilder::<MySql>::new("");
+        predicate.push_sql(&mut builder);
+        builder.into_sql()
+    }
+
+    #[test]
+    fn compiles_nested_expression() {
+        let predicate = ComicPredicate::And(vec![
+            ComicPredicate::Item(1),
+            ComicPredicate::Not(Box::new(ComicPredicate::Or(vec![
+                ComicPredicate::ItemType(ItemType::Location),
+                ComicPredicate::GuestComic,
+            ]))),
+        ]);
+
+        assert_eq!(
+            compile(&predicate),
+            "(EXISTS (SELECT 1 FROM `Occurrence` `o` WHERE `o`.`comic_id` = `c`.`id` \
+             AND `o`.`item_id` = ?) AND NOT ((EXISTS (SELECT 1 FROM `Occurrence` `o` \
+             JOIN `Item` `i` ON `i`.`id` = `o`.`item_id` WHERE `o`.`comic_id` = `c`.`id` \
+             AND `i`.`type` = ?) OR `c`.`is_guest_comic` = 1)))"
+        );
+    }
+
+    #[test]
+    fn empty_junctions_compile_to_identities() {
+        assert_eq!(compile(&ComicPredicate::And(Vec::new())), "TRUE");
+        assert_eq!(compile(&ComicPredicate::Or(Vec::new())), "FALSE");
+    }
+}
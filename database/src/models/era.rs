@@ -0,0 +1,251 @@
+use sqlx::{MySql, QueryBuilder};
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Era {
+    pub id: u16,
+    pub name: String,
+    pub start_comic_id: u16,
+    pub last_comic_id: u16,
+}
+
+impl Era {
+    /// Eras in story order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT * FROM `Era`
+                ORDER BY `start_comic_id`
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn by_id<'e, 'c: 'e, E>(executor: E, id: u16) -> sqlx::Result<Option<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT * FROM `Era` WHERE `id` = ?
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn by_ids<'e, 'c: 'e, E>(executor: E, ids: &[u16]) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new("SELECT * FROM `Era` WHERE `id` IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        builder.push(") ORDER BY `start_comic_id`");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
+    /// The first era other than `except_id` that shares a comic with
+    /// `start_comic_id..=last_comic_id`. Eras must not overlap, so this is
+    /// checked before an era is created or moved.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn first_overlapping<'e, 'c: 'e, E>(
+        executor: E,
+        start_comic_id: u16,
+        last_comic_id: u16,
+        except_id: Option<u16>,
+    ) -> sqlx::Result<Option<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT * FROM `Era`
+                WHERE `start_comic_id` <= ? AND `last_comic_id` >= ?
+                  AND (? IS NULL OR `id` <> ?)
+                ORDER BY `start_comic_id`
+                LIMIT 1
+                FOR UPDATE
+            "#,
+            last_comic_id,
+            start_comic_id,
+            except_id,
+            except_id,
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'e, 'c: 'e, E>(
+        executor: E,
+        name: &str,
+        start_comic_id: u16,
+        last_comic_id: u16,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT INTO `Era`
+                    (`name`, `start_comic_id`, `last_comic_id`)
+                VALUES
+                    (?, ?, ?)
+            "#,
+            name,
+            start_comic_id,
+            last_comic_id,
+        )
+        .execute(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn update_by_id<'e, 'c: 'e, E>(
+        executor: E,
+        id: u16,
+        name: &str,
+        start_comic_id: u16,
+        last_comic_id: u16,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                UPDATE `Era`
+                SET `name` = ?, `start_comic_id` = ?, `last_comic_id` = ?
+                WHERE `id` = ?
+            "#,
+            name,
+            start_comic_id,
+            last_comic_id,
+            id,
+        )
+        .execute(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete_by_id<'e, 'c: 'e, E>(
+        executor: E,
+        id: u16,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                DELETE FROM `Era` WHERE `id` = ?
+            "#,
+            id
+        )
+        .execute(executor)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::MySqlPool;
+
+    async fn create(
+        pool: &MySqlPool,
+        start_comic_id: u16,
+        last_comic_id: u16,
+    ) -> sqlx::Result<u16> {
+        let created = Era::create(pool, "era", start_comic_id, last_comic_id).await?;
+        Ok(u16::try_from(created.last_insert_id()).expect("era ids fit in u16"))
+    }
+
+    async fn overlapping(
+        pool: &MySqlPool,
+        start_comic_id: u16,
+        last_comic_id: u16,
+        except_id: Option<u16>,
+    ) -> sqlx::Result<Option<u16>> {
+        Ok(
+            Era::first_overlapping(pool, start_comic_id, last_comic_id, except_id)
+                .await?
+                .map(|era| era.id),
+        )
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn eras_overlap_when_they_share_a_comic(pool: MySqlPool) -> sqlx::Result<()> {
+        let later = create(&pool, 20, 30).await?;
+        let earlier = create(&pool, 1, 10).await?;
+
+        assert_eq!(overlapping(&pool, 10, 15, None).await?, Some(earlier));
+        assert_eq!(overlapping(&pool, 15, 20, None).await?, Some(later));
+        assert_eq!(overlapping(&pool, 11, 19, None).await?, None);
+        assert_eq!(overlapping(&pool, 25, 25, None).await?, Some(later));
+        // Spanning both reports the one that comes first in the story.
+        assert_eq!(overlapping(&pool, 5, 25, None).await?, Some(earlier));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn an_era_does_not_overlap_itself(pool: MySqlPool) -> sqlx::Result<()> {
+        let era = create(&pool, 1, 10).await?;
+
+        assert_eq!(overlapping(&pool, 1, 12, Some(era)).await?, None);
+        assert_eq!(overlapping(&pool, 1, 12, None).await?, Some(era));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn eras_are_listed_in_story_order(pool: MySqlPool) -> sqlx::Result<()> {
+        let later = create(&pool, 20, 30).await?;
+        let earlier = create(&pool, 1, 10).await?;
+
+        let ids: Vec<u16> = Era::all(&pool).await?.iter().map(|era| era.id).collect();
+        assert_eq!(ids, [earlier, later]);
+        Ok(())
+    }
+}
@@ -1,10 +1,12 @@
+use chrono::NaiveDateTime;
 use futures::TryStreamExt;
+use sqlx::{MySql, QueryBuilder};
 
 use std::collections::{BTreeMap, HashSet};
 
 use crate::models::{ComicId, ItemId, ItemType};
 
-#[derive(Debug)]
+#[derive(Debug, sqlx::FromRow)]
 pub struct Item {
     pub id: u16,
     pub short_name: String,
@@ -19,6 +21,8 @@ pub struct Item {
     /// Only meaningful for `type == "storyline"`; `None` means ongoing (or
     /// not a storyline).
     pub end_comic_id: Option<u16>,
+    /// Bumped on every update to the row; see `version_by_id_for_update`.
+    pub version: u32,
 }
 
 impl Item {
@@ -167,6 +171,125 @@ impl Item {
         .await
     }
 
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn version_by_id<'e, 'c: 'e, E>(executor: E, id: u16) -> sqlx::Result<Option<u32>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT `version` FROM `Item` WHERE `id` = ?
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Like [`Self::version_by_id`], but also locks the item row until the
+    /// end of the transaction, so that the version cannot change between
+    /// checking it and writing to the row.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn version_by_id_for_update<'e, 'c: 'e, E>(
+        executor: E,
+        id: u16,
+    ) -> sqlx::Result<Option<u32>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT `version` FROM `Item` WHERE `id` = ? FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn by_ids<'e, 'c: 'e, E>(executor: E, ids: &[u16]) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new("SELECT * FROM `Item` WHERE `id` IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(id);
+        }
+        builder.push(") ORDER BY `id` ASC");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
+    /// Inserts `item`, or overwrites the existing item with the same id.
+    /// `primary_image` is left alone, since the image may not exist yet;
+    /// set it afterwards with [`Self::set_primary_image`]. Used when
+    /// restoring a dump.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn upsert_without_primary_image<'e, 'c: 'e, E>(
+        executor: E,
+        item: &Self,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT INTO `Item`
+                    (`id`, `short_name`, `name`, `type`, `color_blue`, `color_green`, `color_red`, `start_comic_id`, `end_comic_id`)
+                VALUES
+                    (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE
+                    `short_name` = ?,
+                    `name` = ?,
+                    `type` = ?,
+                    `color_blue` = ?,
+                    `color_green` = ?,
+                    `color_red` = ?,
+                    `start_comic_id` = ?,
+                    `end_comic_id` = ?
+            "#,
+            item.id,
+            item.short_name,
+            item.name,
+            item.r#type,
+            item.color_blue,
+            item.color_green,
+            item.color_red,
+            item.start_comic_id,
+            item.end_comic_id,
+            item.short_name,
+            item.name,
+            item.r#type,
+            item.color_blue,
+            item.color_green,
+            item.color_red,
+            item.start_comic_id,
+            item.end_comic_id,
+        )
+        .execute(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -328,6 +451,49 @@ impl Item {
         .await
     }
 
+    /// Every visible comic from `from` up to but excluding `until`, in order,
+    /// with whether the item appears in it. `None` bounds are open, so a
+    /// storyline's lifecycle can be passed as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn timeline_by_id<'e, 'c: 'e, E>(
+        executor: E,
+        id: u16,
+        from: Option<u16>,
+        until: Option<u16>,
+    ) -> sqlx::Result<Vec<ItemTimelineComic>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ItemTimelineComic,
+            r#"
+                SELECT
+                    `c`.`id` AS `comic_id`,
+                    `c`.`publish_date`,
+                    EXISTS(
+                        SELECT 1 FROM `Occurrence` `o`
+                        WHERE `o`.`comic_id` = `c`.`id` AND `o`.`item_id` = ?
+                    ) AS `appears`
+                FROM `Comic` `c`
+                WHERE NOT `c`.`hidden`
+                    AND (? IS NULL OR `c`.`id` >= ?)
+                    AND (? IS NULL OR `c`.`id` < ?)
+                ORDER BY `c`.`id`
+            "#,
+            id,
+            from,
+            from,
+            until,
+            until,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -611,6 +777,82 @@ impl Item {
         .await
     }
 
+    /// Navigation data for the comics in which *all* of `item_ids` appear,
+    /// relative to `comic_id`. Duplicates in `item_ids` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn co_appearance_navigation_by_ids<'e, 'c: 'e, E>(
+        executor: E,
+        item_ids: &[u16],
+        comic_id: u16,
+        include_guest_comics: Option<bool>,
+        include_non_canon_comics: Option<bool>,
+    ) -> sqlx::Result<ItemSetNavigation>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let mut builder = QueryBuilder::<MySql>::new(
+            r"
+                SELECT
+                    CAST(MIN(`s`.`comic_id`) AS UNSIGNED) as `first`,
+                    CAST(MAX(CASE WHEN `s`.`comic_id` < ",
+        );
+        builder
+            .push_bind(comic_id)
+            .push(
+                r" THEN `s`.`comic_id` END) AS UNSIGNED) as `previous`,
+                    CAST(MIN(CASE WHEN `s`.`comic_id` > ",
+            )
+            .push_bind(comic_id)
+            .push(
+                r" THEN `s`.`comic_id` END) AS UNSIGNED) as `next`,
+                    CAST(MAX(`s`.`comic_id`) AS UNSIGNED) as `last`,
+                    COUNT(*) as `count`
+                FROM (
+                    SELECT `o`.`comic_id`
+                    FROM `Occurrence` `o`
+                    JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                    WHERE NOT `c`.`hidden`
+                        AND (",
+            )
+            .push_bind(include_guest_comics)
+            .push(" is NULL OR `c`.`is_guest_comic` = ")
+            .push_bind(include_guest_comics)
+            .push(
+                r")
+                        AND (",
+            )
+            .push_bind(include_non_canon_comics)
+            .push(" is NULL OR `c`.`is_non_canon` = ")
+            .push_bind(include_non_canon_comics)
+            .push(
+                r")
+                        AND `o`.`item_id` IN (",
+            );
+        let mut separated = builder.separated(", ");
+        for item_id in item_ids {
+            separated.push_bind(item_id);
+        }
+        let distinct_item_count = item_ids.iter().collect::<HashSet<_>>().len();
+        builder
+            .push(
+                r")
+                    GROUP BY `o`.`comic_id`
+                    HAVING COUNT(DISTINCT `o`.`item_id`) = ",
+            )
+            .push_bind(i64::try_from(distinct_item_count).unwrap_or(i64::MAX))
+            .push(
+                r"
+                ) `s`
+            ",
+            );
+
+        builder.build_query_as().fetch_one(executor).await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -688,6 +930,143 @@ impl Item {
         .await
     }
 
+    /// Metadata (including the owning item) of every item image, without the
+    /// image data itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all_image_records<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<ItemImageRecord>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ItemImageRecord,
+            r#"
+                SELECT `id`, `item_id`, `crc32c_hash`
+                FROM `ItemImage`
+                ORDER BY `id` ASC
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Every item image, including the image data, in a single query.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all_images<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<ItemImageData>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ItemImageData,
+            r#"
+                SELECT `id`, `item_id`, `crc32c_hash`, `image`
+                FROM `ItemImage`
+                ORDER BY `id` ASC
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Metadata (including the owning item) of each of the images in
+    /// `image_ids` that still exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn image_records_by_image_ids<'e, 'c: 'e, E>(
+        executor: E,
+        image_ids: &[u32],
+    ) -> sqlx::Result<Vec<ItemImageRecord>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if image_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new(
+            "SELECT `id`, `item_id`, `crc32c_hash` FROM `ItemImage` WHERE `id` IN (",
+        );
+        let mut separated = builder.separated(", ");
+        for image_id in image_ids {
+            separated.push_bind(image_id);
+        }
+        builder.push(") ORDER BY `id` ASC");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
+    /// Which of the given `(comic_id, item_id)` pairs currently exist in
+    /// `Occurrence`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn existing_occurrences<'e, 'c: 'e, E>(
+        executor: E,
+        occurrences: &[ItemOccurrence],
+    ) -> sqlx::Result<Vec<ItemOccurrence>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if occurrences.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new(
+            "SELECT `item_id`, `comic_id` FROM `Occurrence` WHERE (`comic_id`, `item_id`) IN (",
+        );
+        let mut separated = builder.separated(", ");
+        for occurrence in occurrences {
+            separated
+                .push("(")
+                .push_bind_unseparated(occurrence.comic_id)
+                .push_unseparated(", ")
+                .push_bind_unseparated(occurrence.item_id)
+                .push_unseparated(")");
+        }
+        builder.push(")");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn occurrences_in_comics<'e, 'c: 'e, E>(
+        executor: E,
+        comic_ids: &[u16],
+    ) -> sqlx::Result<Vec<ItemOccurrence>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if comic_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new(
+            "SELECT `item_id`, `comic_id` FROM `Occurrence` WHERE `comic_id` IN (",
+        );
+        let mut separated = builder.separated(", ");
+        for comic_id in comic_ids {
+            separated.push_bind(comic_id);
+        }
+        builder.push(") ORDER BY `comic_id`, `item_id`");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -762,6 +1141,46 @@ impl Item {
         .await
     }
 
+    /// Inserts an item image with a known id, or overwrites the existing
+    /// image with that id. Used when restoring a dump.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor, image))]
+    pub async fn upsert_image<'e, 'c: 'e, E>(
+        executor: E,
+        image_id: u32,
+        item_id: u16,
+        image: &[u8],
+        crc32c_hash: u32,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT INTO `ItemImage`
+                    (`id`, `item_id`, `image`, `crc32c_hash`)
+                VALUES
+                    (?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE
+                    `item_id` = ?,
+                    `image` = ?,
+                    `crc32c_hash` = ?
+            "#,
+            image_id,
+            item_id,
+            image,
+            crc32c_hash,
+            item_id,
+            image,
+            crc32c_hash,
+        )
+        .execute(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -1008,6 +1427,153 @@ impl Item {
         .await
     }
 
+    /// Like [`Self::active_storylines_by_comic_id`], but for every storyline
+    /// active at any comic in `from..=to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn active_storylines_in_comic_range<'e, 'c: 'e, E>(
+        executor: E,
+        from: u16,
+        to: u16,
+    ) -> sqlx::Result<Vec<ActiveStorylineOccurrence>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ActiveStorylineOccurrence,
+            r#"
+                SELECT
+                    `i`.`id` AS `item_id`,
+                    `i`.`start_comic_id` AS `start_comic_id!: u16`,
+                    `i`.`end_comic_id` AS `end_comic_id`,
+                    `o`.`comic_id` AS `occurrence_comic_id`
+                FROM `Item` `i`
+                LEFT JOIN `Occurrence` `o`
+                    ON `o`.`item_id` = `i`.`id`
+                    AND `o`.`comic_id` >= `i`.`start_comic_id`
+                    AND (`i`.`end_comic_id` IS NULL OR `o`.`comic_id` < `i`.`end_comic_id`)
+                WHERE `i`.`type` = 'storyline'
+                    AND `i`.`start_comic_id` <= ?
+                    AND (`i`.`end_comic_id` IS NULL OR ? < `i`.`end_comic_id`)
+                ORDER BY `i`.`id`, `o`.`comic_id`
+            "#,
+            to,
+            from,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Navigation data for every occurrence in a comic in `from..=to`, relative
+    /// to that comic and counting only the item's non-hidden, non-excluded
+    /// appearances. Items without any such appearance are left out. Rows are
+    /// ordered by comic, then by count, as in
+    /// [`Self::first_and_last_apperances_and_count_of_items_in_comic_by_comic_id`].
+    ///
+    /// Previous and next come from windows over all of the item's occurrences
+    /// rather than `LAG`/`LEAD` over the included ones, so that they're also
+    /// right for an occurrence in a comic that is itself excluded.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn navigation_of_items_in_comic_range<'e, 'c: 'e, E>(
+        executor: E,
+        from: u16,
+        to: u16,
+        include_guest_comics: Option<bool>,
+        include_non_canon_comics: Option<bool>,
+    ) -> sqlx::Result<Vec<ItemRangeNavigation>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ItemRangeNavigation,
+            r#"
+                WITH `item_occurrences` AS (
+                    SELECT
+                        `o`.`item_id`,
+                        `o`.`comic_id`,
+                        IF(
+                            (? is NULL OR `c`.`is_guest_comic` = ?)
+                                AND (? is NULL OR `c`.`is_non_canon` = ?)
+                                AND NOT `c`.`hidden`,
+                            `o`.`comic_id`,
+                            NULL
+                        ) AS `included_comic_id`
+                    FROM `Occurrence` `o`
+                    JOIN `Comic` `c` ON `c`.`id` = `o`.`comic_id`
+                    WHERE `o`.`item_id` IN (
+                        SELECT `item_id` FROM `Occurrence` WHERE `comic_id` BETWEEN ? AND ?
+                    )
+                ),
+                `navigation` AS (
+                    SELECT
+                        `comic_id`,
+                        `item_id`,
+                        MIN(`included_comic_id`) OVER `item` AS `first`,
+                        MAX(`included_comic_id`) OVER (
+                            `by_comic` ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+                        ) AS `previous`,
+                        MIN(`included_comic_id`) OVER (
+                            `by_comic` ROWS BETWEEN 1 FOLLOWING AND UNBOUNDED FOLLOWING
+                        ) AS `next`,
+                        MAX(`included_comic_id`) OVER `item` AS `last`,
+                        COUNT(`included_comic_id`) OVER `item` AS `count`
+                    FROM `item_occurrences`
+                    WINDOW
+                        `item` AS (PARTITION BY `item_id`),
+                        `by_comic` AS (PARTITION BY `item_id` ORDER BY `comic_id`)
+                )
+                SELECT
+                    `comic_id`,
+                    `item_id`,
+                    `first` AS `first?: u16`,
+                    `previous` AS `previous?: u16`,
+                    `next` AS `next?: u16`,
+                    `last` AS `last?: u16`,
+                    `count` AS `count!: i64`
+                FROM `navigation`
+                WHERE `comic_id` BETWEEN ? AND ?
+                    AND `count` > 0
+                ORDER BY `comic_id`, `count` DESC, `item_id`
+            "#,
+            include_guest_comics,
+            include_guest_comics,
+            include_non_canon_comics,
+            include_non_canon_comics,
+            from,
+            to,
+            from,
+            to,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all_occurrences<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<ItemOccurrence>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            ItemOccurrence,
+            r#"
+                SELECT `item_id`, `comic_id` FROM `Occurrence`
+                ORDER BY `comic_id`, `item_id`
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
     // Until v1 of API is gone
     /// # Errors
     ///
@@ -1063,6 +1629,21 @@ pub struct ItemImageMetadata {
     pub crc32c_hash: u32,
 }
 
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct ItemImageRecord {
+    pub id: u32,
+    pub item_id: u16,
+    pub crc32c_hash: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemImageData {
+    pub id: u32,
+    pub item_id: u16,
+    pub crc32c_hash: u32,
+    pub image: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct ItemWithCount {
     pub id: u16,
@@ -1095,6 +1676,24 @@ pub struct ActiveStorylineOccurrence {
     pub occurrence_comic_id: Option<u16>,
 }
 
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq, sqlx::FromRow)]
+pub struct ItemOccurrence {
+    pub item_id: u16,
+    pub comic_id: u16,
+}
+
+/// One row of [`Item::navigation_of_items_in_comic_range`].
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct ItemRangeNavigation {
+    pub comic_id: u16,
+    pub item_id: u16,
+    pub first: Option<u16>,
+    pub previous: Option<u16>,
+    pub next: Option<u16>,
+    pub last: Option<u16>,
+    pub count: i64,
+}
+
 #[derive(Debug, Copy, Clone, sqlx::FromRow)]
 pub struct ItemFirstLastCount {
     pub id: u16,
@@ -1134,6 +1733,25 @@ pub struct ItemWithStats {
     pub first_image_id: Option<u32>,
 }
 
+/// One comic of [`Item::timeline_by_id`].
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct ItemTimelineComic {
+    pub comic_id: u16,
+    pub publish_date: Option<NaiveDateTime>,
+    /// `1` if the item appears in the comic, `0` otherwise.
+    pub appears: i64,
+}
+
+/// Result of [`Item::co_appearance_navigation_by_ids`].
+#[derive(Debug, Copy, Clone, sqlx::FromRow)]
+pub struct ItemSetNavigation {
+    pub first: Option<u16>,
+    pub previous: Option<u16>,
+    pub next: Option<u16>,
+    pub last: Option<u16>,
+    pub count: i64,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 struct PrevNext {
     id: u16,
@@ -1165,3 +1783,99 @@ pub struct RelatedItem {
     pub color_blue: u8,
     pub count: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Comic, Occurrence};
+    use sqlx::MySqlPool;
+
+    async fn create_item(pool: &MySqlPool, name: &str) -> sqlx::Result<u16> {
+        let created = Item::create(pool, name, name, ItemType::Cast).await?;
+        Ok(u16::try_from(created.last_insert_id()).expect("item ids fit in u16"))
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn range_navigation_skips_excluded_comics_but_keeps_their_occurrences(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        for comic_id in 1..=5 {
+            Comic::ensure_exists_by_id(&pool, comic_id).await?;
+        }
+        Comic::update_is_guest_comic_by_id(&pool, 3, true).await?;
+        let a = create_item(&pool, "a").await?;
+        let b = create_item(&pool, "b").await?;
+        for comic_id in [1, 3, 5] {
+            Occurrence::create(&pool, a, comic_id).await?;
+        }
+        Occurrence::create(&pool, b, 3).await?;
+
+        let navigation = |rows: Vec<ItemRangeNavigation>| {
+            rows.into_iter()
+                .map(|r| {
+                    (
+                        r.comic_id, r.item_id, r.first, r.previous, r.next, r.last, r.count,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            navigation(Item::navigation_of_items_in_comic_range(&pool, 2, 4, None, None).await?),
+            [
+                (3, a, Some(1), Some(1), Some(5), Some(5), 3),
+                (3, b, Some(3), None, None, Some(3), 1)
+            ]
+        );
+        // Comic 3 is a guest comic, so `b` has no appearances left.
+        assert_eq!(
+            navigation(
+                Item::navigation_of_items_in_comic_range(&pool, 2, 4, Some(false), None).await?
+            ),
+            [(3, a, Some(1), Some(1), Some(5), Some(5), 2)]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn co_appearance_navigation_only_counts_comics_with_every_item(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        for comic_id in 1..=5 {
+            Comic::ensure_exists_by_id(&pool, comic_id).await?;
+        }
+        Comic::update_is_guest_comic_by_id(&pool, 3, true).await?;
+        let a = create_item(&pool, "a").await?;
+        let b = create_item(&pool, "b").await?;
+        for comic_id in [1, 2, 3, 4] {
+            Occurrence::create(&pool, a, comic_id).await?;
+        }
+        for comic_id in [2, 3, 4, 5] {
+            Occurrence::create(&pool, b, comic_id).await?;
+        }
+
+        let navigation = |n: ItemSetNavigation| (n.first, n.previous, n.next, n.last, n.count);
+
+        // A repeated id doesn't ask for a third item.
+        assert_eq!(
+            navigation(
+                Item::co_appearance_navigation_by_ids(&pool, &[a, b, a], 3, None, None).await?
+            ),
+            (Some(2), Some(2), Some(4), Some(4), 3)
+        );
+        assert_eq!(
+            navigation(Item::co_appearance_navigation_by_ids(&pool, &[a, a], 3, None, None).await?),
+            (Some(1), Some(2), Some(4), Some(4), 4)
+        );
+        // Comic 3 is a guest comic.
+        assert_eq!(
+            navigation(
+                Item::co_appearance_navigation_by_ids(&pool, &[a, b], 3, Some(false), None).await?
+            ),
+            (Some(2), Some(2), Some(4), Some(4), 2)
+        );
+        Ok(())
+    }
+}
@@ -9,6 +9,8 @@ pub enum ItemType {
 }
 
 impl ItemType {
+    pub const ALL: [Self; 3] = [Self::Cast, Self::Location, Self::Storyline];
+
     #[inline]
     #[must_use]
     pub const fn as_str(self) -> &'static str {
@@ -15,6 +15,32 @@ pub struct LogEntry {
 }
 
 impl LogEntry {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT
+                    `id`,
+                    `user_token`,
+                    `date_time`,
+                    `action`,
+                    `comic_involved` AS `involved_comic`,
+                    `item_involved` AS `involved_item`
+                FROM `LogEntry`
+                ORDER BY `id` ASC
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -216,6 +242,38 @@ impl LogEntry {
         .await
     }
 
+    /// Inserts `entry` with its own id, unless an entry with that id already
+    /// exists. Used when restoring a dump.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor, entry), fields(id = entry.id))]
+    pub async fn create_with_id_if_missing<'e, 'c: 'e, E>(
+        executor: E,
+        entry: &Self,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT IGNORE INTO `LogEntry`
+                    (`id`, `user_token`, `date_time`, `action`, `comic_involved`, `item_involved`)
+                VALUES
+                    (?, ?, ?, ?, ?, ?)
+            "#,
+            entry.id,
+            entry.user_token,
+            entry.date_time,
+            entry.action,
+            entry.involved_comic,
+            entry.involved_item,
+        )
+        .execute(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -1,11 +1,12 @@
 use chrono::{NaiveDate, Utc};
+use sqlx::{MySql, QueryBuilder};
 use tracing::debug;
 
 #[expect(
     clippy::struct_field_names,
     reason = "field name matches the database column name"
 )]
-#[derive(Debug)]
+#[derive(Debug, sqlx::FromRow)]
 pub struct News {
     pub comic_id: u16,
     pub last_updated: NaiveDate,
@@ -38,6 +39,90 @@ impl News {
         .await
     }
 
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+                SELECT * FROM `News`
+                ORDER BY `comic_id` ASC
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn by_comic_ids<'e, 'c: 'e, E>(
+        executor: E,
+        comic_ids: &[u16],
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        if comic_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder = QueryBuilder::<MySql>::new("SELECT * FROM `News` WHERE `comic_id` IN (");
+        let mut separated = builder.separated(", ");
+        for comic_id in comic_ids {
+            separated.push_bind(comic_id);
+        }
+        builder.push(") ORDER BY `comic_id` ASC");
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
+    /// Inserts `news`, or overwrites the existing news for the same comic.
+    /// Used when restoring a dump.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor, news), fields(comic_id = news.comic_id))]
+    pub async fn upsert<'e, 'c: 'e, E>(
+        executor: E,
+        news: &Self,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT INTO `News`
+                    (`comic_id`, `last_updated`, `news`, `update_factor`, `is_locked`)
+                VALUES
+                    (?, ?, ?, ?, ?)
+                ON DUPLICATE KEY UPDATE
+                    `last_updated` = ?,
+                    `news` = ?,
+                    `update_factor` = ?,
+                    `is_locked` = ?
+            "#,
+            news.comic_id,
+            news.last_updated,
+            news.news,
+            news.update_factor,
+            news.is_locked,
+            news.last_updated,
+            news.news,
+            news.update_factor,
+            news.is_locked,
+        )
+        .execute(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
@@ -82,6 +82,35 @@ impl Occurrence {
         .await
     }
 
+    /// Like [`Self::create`], but does nothing if the occurrence already
+    /// exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn create_if_missing<'e, 'c: 'e, E>(
+        executor: E,
+        item_id: u16,
+        comic_id: u16,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT IGNORE INTO `Occurrence`
+                    (`comic_id`, `item_id`)
+                VALUES
+                    (?, ?)
+            "#,
+            comic_id,
+            item_id
+        )
+        .execute(executor)
+        .await
+    }
+
     /// # Errors
     ///
     /// Returns a database error if the query fails.
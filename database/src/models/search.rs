@@ -0,0 +1,162 @@
+use sqlx::{MySql, QueryBuilder};
+
+/// Restricts a full-text search to a subset of comics. `None` fields leave
+/// the corresponding dimension unfiltered.
+#[derive(Debug, Default, Clone)]
+pub struct ComicSearchFilter {
+    /// Comics must feature every one of these items. Must not contain
+    /// duplicates.
+    pub item_ids: Vec<u16>,
+    pub is_guest_comic: Option<bool>,
+    pub is_non_canon: Option<bool>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ComicSearchHit {
+    pub id: u16,
+    pub title: String,
+    pub tagline: Option<String>,
+    pub is_guest_comic: u8,
+    pub is_non_canon: u8,
+    pub news: Option<String>,
+    pub relevance: f64,
+}
+
+impl ComicSearchHit {
+    /// Runs a natural-language full-text search over comic titles, taglines
+    /// and news, ordered by relevance. Title and tagline matches are weighted
+    /// above news matches, since news posts are long and rarely on-topic.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn search<'e, 'c: 'e, E>(
+        executor: E,
+        query: &str,
+        filter: &ComicSearchFilter,
+        page: u16,
+        page_size: u16,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let start_entry = u32::from(page.saturating_sub(1)) * u32::from(page_size);
+
+        let mut builder = QueryBuilder::<MySql>::new(
+            r"
+                SELECT
+                    `c`.`id`,
+                    `c`.`title`,
+                    `c`.`tagline`,
+                    `c`.`is_guest_comic`,
+                    `c`.`is_non_canon`,
+                    `n`.`news`,
+                    (
+                        2 * MATCH (`c`.`title`, `c`.`tagline`) AGAINST (",
+        );
+        builder
+            .push_bind(query)
+            .push(
+                r" IN NATURAL LANGUAGE MODE)
+                        + COALESCE(MATCH (`n`.`news`) AGAINST (",
+            )
+            .push_bind(query)
+            .push(
+                r" IN NATURAL LANGUAGE MODE), 0)
+                    ) AS `relevance`",
+            );
+        push_from_and_conditions(&mut builder, query, filter);
+        builder
+            .push(
+                r"
+                ORDER BY `relevance` DESC, `c`.`id` ASC
+                LIMIT ",
+            )
+            .push_bind(start_entry)
+            .push(", ")
+            .push_bind(page_size);
+
+        builder.build_query_as().fetch_all(executor).await
+    }
+
+    /// Counts the total number of hits for [`Self::search`], ignoring
+    /// pagination.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn count<'e, 'c: 'e, E>(
+        executor: E,
+        query: &str,
+        filter: &ComicSearchFilter,
+    ) -> sqlx::Result<i64>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let mut builder = QueryBuilder::<MySql>::new("SELECT COUNT(*)");
+        push_from_and_conditions(&mut builder, query, filter);
+
+        builder.build_query_scalar().fetch_one(executor).await
+    }
+}
+
+fn push_from_and_conditions<'a>(
+    builder: &mut QueryBuilder<'a, MySql>,
+    query: &'a str,
+    filter: &'a ComicSearchFilter,
+) {
+    builder
+        .push(
+            r"
+                FROM `Comic` `c`
+                LEFT JOIN `News` `n` ON `n`.`comic_id` = `c`.`id`
+                WHERE NOT `c`.`hidden`
+                    AND (
+                        MATCH (`c`.`title`, `c`.`tagline`) AGAINST (",
+        )
+        .push_bind(query)
+        .push(
+            r" IN NATURAL LANGUAGE MODE)
+                        OR MATCH (`n`.`news`) AGAINST (",
+        )
+        .push_bind(query)
+        .push(
+            r" IN NATURAL LANGUAGE MODE)
+                    )",
+        );
+
+    if let Some(is_guest_comic) = filter.is_guest_comic {
+        builder
+            .push(" AND `c`.`is_guest_comic` = ")
+            .push_bind(is_guest_comic);
+    }
+    if let Some(is_non_canon) = filter.is_non_canon {
+        builder
+            .push(" AND `c`.`is_non_canon` = ")
+            .push_bind(is_non_canon);
+    }
+
+    if !filter.item_ids.is_empty() {
+        builder.push(
+            r"
+                    AND `c`.`id` IN (
+                        SELECT `o`.`comic_id`
+                        FROM `Occurrence` `o`
+                        WHERE `o`.`item_id` IN (",
+        );
+        let mut separated = builder.separated(", ");
+        for item_id in &filter.item_ids {
+            separated.push_bind(item_id);
+        }
+        builder
+            .push(
+                r")
+                        GROUP BY `o`.`comic_id`
+                        HAVING COUNT(DISTINCT `o`.`item_id`) = ",
+            )
+            .push_bind(i64::try_from(filter.item_ids.len()).unwrap_or(i64::MAX))
+            .push(")");
+    }
+}
@@ -1,3 +1,106 @@
+use crate::models::ItemType;
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Restricts the comics and occurrences the stats are computed over.
+///
+/// The default filter includes everything. Bounds are inclusive.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct StatsFilter {
+    pub exclude_guest_comics: bool,
+    pub exclude_non_canon_comics: bool,
+    pub from_comic: Option<u16>,
+    pub to_comic: Option<u16>,
+    pub from_date: Option<NaiveDate>,
+    pub to_date: Option<NaiveDate>,
+    /// Only count occurrences of items of this type.
+    pub item_type: Option<ItemType>,
+}
+
+/// What the year-based stats are grouped by.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum StatsGrouping {
+    /// Calendar years of the publish dates; the period is the year.
+    #[default]
+    Year,
+    /// Editor-defined eras; the period is the 1-based position of the era in
+    /// story order. Comics outside every era are left out.
+    Era,
+}
+
+/// `sqlx::query_as!` for a stats query over the comics and occurrences
+/// matched by a [`StatsFilter`].
+///
+/// The query must read `Comic` and `Occurrence` through the
+/// `filtered_comics` and `filtered_occurrences` CTEs defined here. Queries
+/// with CTEs of their own start with a comma instead of `WITH`.
+///
+/// With `grouping = ...`, the query can also join `comic_periods`, which maps
+/// the id of each filtered comic to its `period` under that grouping (`NULL`
+/// if it has none).
+macro_rules! filtered_query_as {
+    ($out:path, $filter:expr, grouping = $grouping:expr, $sql:literal $(, $arg:expr)* $(,)?) => {{
+        let grouping: StatsGrouping = $grouping;
+        filtered_query_as!(
+            $out,
+            $filter,
+            r#",
+                `comic_periods` AS (
+                    SELECT
+                        `pc`.`id` AS `comic_id`,
+                        CAST(IF(?, `pe`.`position`, YEAR(`pc`.`publish_date`)) AS SIGNED) AS `period`
+                    FROM `filtered_comics` `pc`
+                    LEFT JOIN (
+                        SELECT
+                            `e`.`start_comic_id`,
+                            `e`.`last_comic_id`,
+                            ROW_NUMBER() OVER (ORDER BY `e`.`start_comic_id`) AS `position`
+                        FROM `Era` `e`
+                    ) `pe` ON `pc`.`id` BETWEEN `pe`.`start_comic_id` AND `pe`.`last_comic_id`
+                )"# + $sql,
+            grouping == StatsGrouping::Era,
+            $($arg,)*
+        )
+    }};
+    ($out:path, $filter:expr, $sql:literal $(+ $more:literal)* $(, $arg:expr)* $(,)?) => {{
+        let filter: &StatsFilter = $filter;
+        let item_type = filter.item_type.map(ItemType::as_str);
+        sqlx::query_as!(
+            $out,
+            r#"
+                WITH `filtered_comics` AS (
+                    SELECT `fc`.*
+                    FROM `Comic` `fc`
+                    WHERE (? IS NULL OR `fc`.`id` >= ?)
+                      AND (? IS NULL OR `fc`.`id` <= ?)
+                      AND (? IS NULL OR `fc`.`publish_date` >= ?)
+                      AND (? IS NULL OR `fc`.`publish_date` < ? + INTERVAL 1 DAY)
+                      AND (NOT ? OR NOT `fc`.`is_guest_comic`)
+                      AND (NOT ? OR NOT `fc`.`is_non_canon`)
+                ),
+                `filtered_occurrences` AS (
+                    SELECT `fo`.*
+                    FROM `Occurrence` `fo`
+                    JOIN `filtered_comics` `foc` ON `foc`.`id` = `fo`.`comic_id`
+                    JOIN `Item` `fi` ON `fi`.`id` = `fo`.`item_id`
+                    WHERE (? IS NULL OR `fi`.`type` = ?)
+                )"# + $sql $(+ $more)*,
+            filter.from_comic,
+            filter.from_comic,
+            filter.to_comic,
+            filter.to_comic,
+            filter.from_date,
+            filter.from_date,
+            filter.to_date,
+            filter.to_date,
+            filter.exclude_guest_comics,
+            filter.exclude_non_canon_comics,
+            item_type,
+            item_type,
+            $($arg,)*
+        )
+    }};
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct ItemStats {
     pub id: u16,
@@ -12,12 +115,13 @@ impl ItemStats {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn cast<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn cast<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -26,7 +130,7 @@ impl ItemStats {
                     MAX(`o`.`comic_id`) AS `last_comic`,
                     COUNT(*) AS `appearances`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `i`.`id` = `o`.`item_id`
+                JOIN `filtered_occurrences` `o` ON `i`.`id` = `o`.`item_id`
                 WHERE `i`.`type` = 'cast'
                 GROUP BY `i`.`id`
                 ORDER BY `appearances` DESC
@@ -40,12 +144,16 @@ impl ItemStats {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn locations<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn locations<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -54,7 +162,7 @@ impl ItemStats {
                     MAX(`o`.`comic_id`) AS `last_comic`,
                     COUNT(*) AS `appearances`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `i`.`id` = `o`.`item_id`
+                JOIN `filtered_occurrences` `o` ON `i`.`id` = `o`.`item_id`
                 WHERE `i`.`type` = 'location'
                 GROUP BY `i`.`id`
                 ORDER BY `appearances` DESC
@@ -81,12 +189,13 @@ impl CoAppearance {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i1`.`id` AS `character1_id`,
@@ -96,20 +205,20 @@ impl CoAppearance {
                     `i2`.`name` AS `character2_name`,
                     `app2`.`total` AS `character2_appearances`,
                     COUNT(*) AS `comics_together`
-                FROM `Occurrence` `o1`
-                JOIN `Occurrence` `o2`
+                FROM `filtered_occurrences` `o1`
+                JOIN `filtered_occurrences` `o2`
                     ON `o1`.`comic_id` = `o2`.`comic_id`
                     AND `o1`.`item_id` < `o2`.`item_id`
                 JOIN `Item` `i1` ON `o1`.`item_id` = `i1`.`id`
                 JOIN `Item` `i2` ON `o2`.`item_id` = `i2`.`id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app1` ON `o1`.`item_id` = `app1`.`item_id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app2` ON `o2`.`item_id` = `app2`.`item_id`
                 WHERE `i1`.`type` = 'cast' AND `i2`.`type` = 'cast'
@@ -125,7 +234,8 @@ impl CoAppearance {
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct YearlyAppearanceRow {
-    pub year: Option<i32>,
+    /// The period, see [`StatsGrouping`].
+    pub year: Option<i64>,
     pub id: u16,
     pub name: String,
     pub color_red: u8,
@@ -139,25 +249,32 @@ impl YearlyAppearanceRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        grouping: StatsGrouping,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
+            grouping = grouping,
             r#"
                 SELECT
-                    YEAR(`c`.`publish_date`) AS `year`,
+                    `p`.`period` AS `year`,
                     `i`.`id`,
                     `i`.`name`,
                     `i`.`color_red`,
                     `i`.`color_green`,
                     `i`.`color_blue`,
                     COUNT(*) AS `appearances`
-                FROM `Comic` `c`
-                JOIN `Occurrence` `o` ON `c`.`id` = `o`.`comic_id`
+                FROM `filtered_comics` `c`
+                JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
+                JOIN `filtered_occurrences` `o` ON `c`.`id` = `o`.`comic_id`
                 JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
-                WHERE `c`.`publish_date` IS NOT NULL AND `i`.`type` = 'cast'
+                WHERE `p`.`period` IS NOT NULL AND `i`.`type` = 'cast'
                 GROUP BY `year`, `i`.`id`
                 ORDER BY `year`, `appearances` DESC
             "#,
@@ -179,12 +296,13 @@ impl DebutsPerYearRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `first_app`.`year`,
@@ -192,8 +310,8 @@ impl DebutsPerYearRow {
                     COUNT(CASE WHEN `i`.`type` = 'location' THEN 1 END) AS `location_debuts`
                 FROM (
                     SELECT `o`.`item_id`, YEAR(MIN(`c`.`publish_date`)) AS `year`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                     JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                     WHERE `c`.`publish_date` IS NOT NULL
                       AND `i`.`type` IN ('cast', 'location')
@@ -221,12 +339,13 @@ impl YearlyOverviewRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `yc`.`year`,
@@ -234,16 +353,16 @@ impl YearlyOverviewRow {
                     COUNT(CASE WHEN `fd`.`debut_year` = `yc`.`year` THEN 1 END) AS `new_cast`
                 FROM (
                     SELECT YEAR(`c`.`publish_date`) AS `year`, `o`.`item_id`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                     JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                     WHERE `c`.`publish_date` IS NOT NULL AND `i`.`type` = 'cast'
                     GROUP BY YEAR(`c`.`publish_date`), `o`.`item_id`
                 ) `yc`
                 JOIN (
                     SELECT `o`.`item_id`, YEAR(MIN(`c`.`publish_date`)) AS `debut_year`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                     JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                     WHERE `c`.`publish_date` IS NOT NULL AND `i`.`type` = 'cast'
                     GROUP BY `o`.`item_id`
@@ -268,15 +387,16 @@ impl PublicationMonthRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT MONTH(`publish_date`) AS `month`, COUNT(*) AS `comics`
-                FROM `Comic`
+                FROM `filtered_comics` `Comic`
                 WHERE `publish_date` IS NOT NULL
                 GROUP BY `month`
                 ORDER BY `month`
@@ -298,15 +418,16 @@ impl PublicationDowRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT DAYOFWEEK(`publish_date`) AS `dow`, COUNT(*) AS `comics`
-                FROM `Comic`
+                FROM `filtered_comics` `Comic`
                 WHERE `publish_date` IS NOT NULL
                 GROUP BY `dow`
                 ORDER BY `dow`
@@ -331,14 +452,15 @@ impl ComebackCharacterRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
-            r#"
-                WITH `gaps` AS (
+            filter,
+            r#",
+                `gaps` AS (
                     SELECT
                         `sub`.`item_id`,
                         `sub`.`prev_comic` AS `last_comic`,
@@ -357,8 +479,8 @@ impl ComebackCharacterRow {
                                 PARTITION BY `o`.`item_id`
                                 ORDER BY `o`.`comic_id`
                             ) AS `prev_date`
-                        FROM `Occurrence` `o`
-                        JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                        FROM `filtered_occurrences` `o`
+                        JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                         JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                         WHERE `i`.`type` = 'cast'
                           AND `c`.`publish_date` IS NOT NULL
@@ -410,14 +532,15 @@ impl ComebackLocationRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
-            r#"
-                WITH `gaps` AS (
+            filter,
+            r#",
+                `gaps` AS (
                     SELECT
                         `sub`.`item_id`,
                         `sub`.`prev_comic` AS `last_comic`,
@@ -436,8 +559,8 @@ impl ComebackLocationRow {
                                 PARTITION BY `o`.`item_id`
                                 ORDER BY `o`.`comic_id`
                             ) AS `prev_date`
-                        FROM `Occurrence` `o`
-                        JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                        FROM `filtered_occurrences` `o`
+                        JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                         JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                         WHERE `i`.`type` = 'location'
                           AND `c`.`publish_date` IS NOT NULL
@@ -489,12 +612,13 @@ impl LocationAffinityRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `l`.`id` AS `location_id`,
@@ -502,8 +626,8 @@ impl LocationAffinityRow {
                     `ch`.`id` AS `character_id`,
                     `ch`.`name` AS `character_name`,
                     COUNT(*) AS `comics_together`
-                FROM `Occurrence` `ol`
-                JOIN `Occurrence` `oc` ON `ol`.`comic_id` = `oc`.`comic_id`
+                FROM `filtered_occurrences` `ol`
+                JOIN `filtered_occurrences` `oc` ON `ol`.`comic_id` = `oc`.`comic_id`
                 JOIN `Item` `l` ON `ol`.`item_id` = `l`.`id`
                 JOIN `Item` `ch` ON `oc`.`item_id` = `ch`.`id`
                 WHERE `l`.`type` = 'location' AND `ch`.`type` = 'cast'
@@ -527,18 +651,19 @@ impl CrowdedComicRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `c`.`id` AS `comic_id`,
                     COUNT(`i`.`id`) AS `cast_count`
-                FROM `Comic` `c`
-                JOIN `Occurrence` `o` ON `c`.`id` = `o`.`comic_id`
+                FROM `filtered_comics` `c`
+                JOIN `filtered_occurrences` `o` ON `c`.`id` = `o`.`comic_id`
                 JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                 WHERE `i`.`type` = 'cast'
                 GROUP BY `c`.`id`
@@ -562,12 +687,13 @@ impl AvgCastPerYearRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `sub`.`pub_year` AS `year`,
@@ -576,8 +702,8 @@ impl AvgCastPerYearRow {
                     SELECT
                         YEAR(`c`.`publish_date`) AS `pub_year`,
                         COUNT(`i`.`id`) AS `cast_count`
-                    FROM `Comic` `c`
-                    LEFT JOIN `Occurrence` `o` ON `c`.`id` = `o`.`comic_id`
+                    FROM `filtered_comics` `c`
+                    LEFT JOIN `filtered_occurrences` `o` ON `c`.`id` = `o`.`comic_id`
                     LEFT JOIN `Item` `i` ON `o`.`item_id` = `i`.`id` AND `i`.`type` = 'cast'
                     WHERE `c`.`publish_date` IS NOT NULL
                     GROUP BY `c`.`id`, YEAR(`c`.`publish_date`)
@@ -594,7 +720,8 @@ impl AvgCastPerYearRow {
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct LocationYearlyAppearanceRow {
-    pub year: Option<i32>,
+    /// The period, see [`StatsGrouping`].
+    pub year: Option<i64>,
     pub id: u16,
     pub name: String,
     pub color_red: u8,
@@ -608,25 +735,32 @@ impl LocationYearlyAppearanceRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        grouping: StatsGrouping,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
+            grouping = grouping,
             r#"
                 SELECT
-                    YEAR(`c`.`publish_date`) AS `year`,
+                    `p`.`period` AS `year`,
                     `i`.`id`,
                     `i`.`name`,
                     `i`.`color_red`,
                     `i`.`color_green`,
                     `i`.`color_blue`,
                     COUNT(*) AS `appearances`
-                FROM `Comic` `c`
-                JOIN `Occurrence` `o` ON `c`.`id` = `o`.`comic_id`
+                FROM `filtered_comics` `c`
+                JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
+                JOIN `filtered_occurrences` `o` ON `c`.`id` = `o`.`comic_id`
                 JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
-                WHERE `c`.`publish_date` IS NOT NULL AND `i`.`type` = 'location'
+                WHERE `p`.`period` IS NOT NULL AND `i`.`type` = 'location'
                 GROUP BY `year`, `i`.`id`
                 ORDER BY `year`, `appearances` DESC
             "#,
@@ -648,12 +782,13 @@ impl PublicationGapRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `gaps`.`before_comic`,
@@ -667,7 +802,7 @@ impl PublicationGapRow {
                             LEAD(`c`.`publish_date`) OVER (ORDER BY `c`.`publish_date`, `c`.`id`),
                             `c`.`publish_date`
                         ) AS `gap_days`
-                    FROM `Comic` `c`
+                    FROM `filtered_comics` `c`
                     WHERE `c`.`publish_date` IS NOT NULL
                 ) `gaps`
                 WHERE `gaps`.`gap_days` IS NOT NULL AND `gaps`.`gap_days` > 6
@@ -692,19 +827,20 @@ impl DebutDetailRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     YEAR(MIN(`c`.`publish_date`)) AS `year`,
                     `i`.`id`,
                     `i`.`name`
-                FROM `Occurrence` `o`
-                JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                FROM `filtered_occurrences` `o`
+                JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                 JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
                 WHERE `i`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
                 GROUP BY `i`.`id`
@@ -731,12 +867,13 @@ impl EnsembleRatioRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `sub`.`pub_year` AS `year`,
@@ -749,8 +886,8 @@ impl EnsembleRatioRow {
                     SELECT
                         YEAR(`c`.`publish_date`) AS `pub_year`,
                         COUNT(`i`.`id`) AS `cast_count`
-                    FROM `Comic` `c`
-                    LEFT JOIN `Occurrence` `o` ON `c`.`id` = `o`.`comic_id`
+                    FROM `filtered_comics` `c`
+                    LEFT JOIN `filtered_occurrences` `o` ON `c`.`id` = `o`.`comic_id`
                     LEFT JOIN `Item` `i` ON `o`.`item_id` = `i`.`id` AND `i`.`type` = 'cast'
                     WHERE `c`.`publish_date` IS NOT NULL
                     GROUP BY `c`.`id`, YEAR(`c`.`publish_date`)
@@ -779,12 +916,13 @@ impl CharacterRegularityRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -799,8 +937,8 @@ impl CharacterRegularityRow {
                             `c`.`publish_date`,
                             LAG(`c`.`publish_date`) OVER (PARTITION BY `o`.`item_id` ORDER BY `o`.`comic_id`)
                         ) AS `gap_days`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                     JOIN `Item` `i2` ON `o`.`item_id` = `i2`.`id`
                     WHERE `i2`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
                 ) `g`
@@ -830,12 +968,13 @@ impl LocationRegularityRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -850,8 +989,8 @@ impl LocationRegularityRow {
                             `c`.`publish_date`,
                             LAG(`c`.`publish_date`) OVER (PARTITION BY `o`.`item_id` ORDER BY `o`.`comic_id`)
                         ) AS `gap_days`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                     JOIN `Item` `i2` ON `o`.`item_id` = `i2`.`id`
                     WHERE `i2`.`type` = 'location' AND `c`.`publish_date` IS NOT NULL
                 ) `g`
@@ -883,12 +1022,13 @@ impl LocationCoOccurrenceRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `l1`.`id` AS `location1_id`,
@@ -898,20 +1038,20 @@ impl LocationCoOccurrenceRow {
                     `l2`.`name` AS `location2_name`,
                     `app2`.`total` AS `location2_appearances`,
                     COUNT(*) AS `comics_together`
-                FROM `Occurrence` `ol1`
-                JOIN `Occurrence` `ol2`
+                FROM `filtered_occurrences` `ol1`
+                JOIN `filtered_occurrences` `ol2`
                     ON `ol1`.`comic_id` = `ol2`.`comic_id`
                     AND `ol1`.`item_id` < `ol2`.`item_id`
                 JOIN `Item` `l1` ON `ol1`.`item_id` = `l1`.`id`
                 JOIN `Item` `l2` ON `ol2`.`item_id` = `l2`.`id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app1` ON `ol1`.`item_id` = `app1`.`item_id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app2` ON `ol2`.`item_id` = `app2`.`item_id`
                 WHERE `l1`.`type` = 'location' AND `l2`.`type` = 'location'
@@ -933,12 +1073,16 @@ impl CoAppearance {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn top_normalized<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn top_normalized<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i1`.`id` AS `character1_id`,
@@ -948,20 +1092,20 @@ impl CoAppearance {
                     `i2`.`name` AS `character2_name`,
                     `app2`.`total` AS `character2_appearances`,
                     COUNT(*) AS `comics_together`
-                FROM `Occurrence` `o1`
-                JOIN `Occurrence` `o2`
+                FROM `filtered_occurrences` `o1`
+                JOIN `filtered_occurrences` `o2`
                     ON `o1`.`comic_id` = `o2`.`comic_id`
                     AND `o1`.`item_id` < `o2`.`item_id`
                 JOIN `Item` `i1` ON `o1`.`item_id` = `i1`.`id`
                 JOIN `Item` `i2` ON `o2`.`item_id` = `i2`.`id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app1` ON `o1`.`item_id` = `app1`.`item_id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app2` ON `o2`.`item_id` = `app2`.`item_id`
                 WHERE `i1`.`type` = 'cast' AND `i2`.`type` = 'cast'
@@ -976,6 +1120,115 @@ impl CoAppearance {
     }
 }
 
+#[derive(Debug, sqlx::FromRow)]
+pub struct GraphNodeRow {
+    pub id: u16,
+    pub short_name: String,
+    pub name: String,
+    pub r#type: String,
+    pub color_red: u8,
+    pub color_green: u8,
+    pub color_blue: u8,
+    pub appearances: i64,
+}
+
+impl GraphNodeRow {
+    /// Every item of one of `types` (a comma separated list of item types)
+    /// that appears in the filtered comics.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        types: &str,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#"
+                SELECT
+                    `i`.`id`,
+                    `i`.`short_name`,
+                    `i`.`name`,
+                    `i`.`type`,
+                    `i`.`color_red`,
+                    `i`.`color_green`,
+                    `i`.`color_blue`,
+                    COUNT(*) AS `appearances`
+                FROM `Item` `i`
+                JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
+                WHERE FIND_IN_SET(`i`.`type`, ?)
+                GROUP BY `i`.`id`
+                ORDER BY `i`.`id`
+            "#,
+            types,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+#[derive(Copy, Clone, Debug, sqlx::FromRow)]
+pub struct GraphEdgeRow {
+    pub source: u16,
+    pub target: u16,
+    pub weight: i64,
+}
+
+impl GraphEdgeRow {
+    /// Every pair of items of one of `types` (a comma separated list of item
+    /// types) that appear together in at least `min_weight` of the filtered
+    /// comics, with the lower item id as the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        types: &str,
+        min_weight: u32,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#",
+                `graph_occurrences` AS (
+                    SELECT `go`.`comic_id`, `go`.`item_id`
+                    FROM `filtered_occurrences` `go`
+                    JOIN `Item` `gi` ON `gi`.`id` = `go`.`item_id`
+                    WHERE FIND_IN_SET(`gi`.`type`, ?)
+                )
+                SELECT
+                    `o1`.`item_id` AS `source`,
+                    `o2`.`item_id` AS `target`,
+                    COUNT(*) AS `weight`
+                FROM `graph_occurrences` `o1`
+                JOIN `graph_occurrences` `o2`
+                    ON `o1`.`comic_id` = `o2`.`comic_id`
+                    AND `o1`.`item_id` < `o2`.`item_id`
+                GROUP BY `o1`.`item_id`, `o2`.`item_id`
+                HAVING COUNT(*) >= ?
+                ORDER BY `source`, `target`
+            "#,
+            types,
+            min_weight,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct SocialHubRow {
     pub id: u16,
@@ -989,12 +1242,13 @@ impl SocialHubRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -1004,11 +1258,11 @@ impl SocialHubRow {
                 FROM `Item` `i`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app` ON `app`.`item_id` = `i`.`id`
-                JOIN `Occurrence` `o1` ON `o1`.`item_id` = `i`.`id`
-                JOIN `Occurrence` `o2`
+                JOIN `filtered_occurrences` `o1` ON `o1`.`item_id` = `i`.`id`
+                JOIN `filtered_occurrences` `o2`
                     ON `o2`.`comic_id` = `o1`.`comic_id`
                     AND `o2`.`item_id` != `o1`.`item_id`
                 JOIN `Item` `i2` ON `i2`.`id` = `o2`.`item_id` AND `i2`.`type` = 'cast'
@@ -1037,15 +1291,19 @@ impl TrendingItemRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn cast_trending<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn cast_trending<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
-            r#"
-                WITH `cutoff` AS (
-                    SELECT DATE_SUB(MAX(`publish_date`), INTERVAL 365 DAY) AS `d` FROM `Comic`
+            filter,
+            r#",
+                `cutoff` AS (
+                    SELECT DATE_SUB(MAX(`publish_date`), INTERVAL 365 DAY) AS `d` FROM `filtered_comics` `Comic`
                 )
                 SELECT
                     `i`.`id`,
@@ -1056,8 +1314,8 @@ impl TrendingItemRow {
                         DATEDIFF(MAX(`c`.`publish_date`), MIN(`c`.`publish_date`)) / 365.25
                     AS DOUBLE) AS `career_years`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
-                JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
+                JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                 CROSS JOIN `cutoff`
                 WHERE `i`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
                 GROUP BY `i`.`id`
@@ -1073,15 +1331,19 @@ impl TrendingItemRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn location_trending<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn location_trending<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
-            r#"
-                WITH `cutoff` AS (
-                    SELECT DATE_SUB(MAX(`publish_date`), INTERVAL 365 DAY) AS `d` FROM `Comic`
+            filter,
+            r#",
+                `cutoff` AS (
+                    SELECT DATE_SUB(MAX(`publish_date`), INTERVAL 365 DAY) AS `d` FROM `filtered_comics` `Comic`
                 )
                 SELECT
                     `i`.`id`,
@@ -1092,8 +1354,8 @@ impl TrendingItemRow {
                         DATEDIFF(MAX(`c`.`publish_date`), MIN(`c`.`publish_date`)) / 365.25
                     AS DOUBLE) AS `career_years`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
-                JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
+                JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                 CROSS JOIN `cutoff`
                 WHERE `i`.`type` = 'location' AND `c`.`publish_date` IS NOT NULL
                 GROUP BY `i`.`id`
@@ -1108,7 +1370,8 @@ impl TrendingItemRow {
 
 #[derive(Copy, Clone, Debug, sqlx::FromRow)]
 pub struct CastTurnoverRow {
-    pub year: Option<i32>,
+    /// The period, see [`StatsGrouping`].
+    pub year: Option<i64>,
     pub new_chars: i64,
     pub continuing_chars: i64,
     pub returning_chars: i64,
@@ -1120,19 +1383,26 @@ impl CastTurnoverRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        grouping: StatsGrouping,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
-            r#"
-                WITH `char_yr` AS (
-                    SELECT DISTINCT YEAR(`c`.`publish_date`) AS `yr`, `o`.`item_id`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+            filter,
+            grouping = grouping,
+            r#",
+                `char_yr` AS (
+                    SELECT DISTINCT `p`.`period` AS `yr`, `o`.`item_id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
+                    JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
                     JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
-                    WHERE `i`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
+                    WHERE `i`.`type` = 'cast' AND `p`.`period` IS NOT NULL
                 ),
                 `char_first` AS (
                     SELECT `item_id`, MIN(`yr`) AS `first_yr`
@@ -1179,12 +1449,13 @@ impl CharacterSeasonRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -1192,11 +1463,11 @@ impl CharacterSeasonRow {
                     MONTH(`c`.`publish_date`) AS `month`,
                     COUNT(*) AS `appearances`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
-                JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
+                JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                 WHERE `i`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
                   AND `i`.`id` IN (
-                    SELECT `item_id` FROM `Occurrence`
+                    SELECT `item_id` FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                     HAVING COUNT(*) >= 50
                   )
@@ -1223,12 +1494,18 @@ impl BreakoutYearRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        grouping: StatsGrouping,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
+            grouping = grouping,
             r#"
                 SELECT
                     `ranked`.`item_id` AS `id`,
@@ -1239,25 +1516,27 @@ impl BreakoutYearRow {
                 FROM (
                     SELECT
                         `o`.`item_id`,
-                        YEAR(`c`.`publish_date`) AS `yr`,
+                        `p`.`period` AS `yr`,
                         COUNT(*) AS `cnt`,
                         RANK() OVER (PARTITION BY `o`.`item_id` ORDER BY COUNT(*) DESC) AS `rnk`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
+                    JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
                     JOIN `Item` `i2` ON `o`.`item_id` = `i2`.`id`
-                    WHERE `i2`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
-                    GROUP BY `o`.`item_id`, YEAR(`c`.`publish_date`)
+                    WHERE `i2`.`type` = 'cast' AND `p`.`period` IS NOT NULL
+                    GROUP BY `o`.`item_id`, `p`.`period`
                 ) `ranked`
                 JOIN `Item` `i` ON `i`.`id` = `ranked`.`item_id`
                 JOIN (
                     SELECT
                         `o`.`item_id`,
                         COUNT(*) AS `total_appearances`,
-                        COUNT(DISTINCT YEAR(`c`.`publish_date`)) AS `active_years`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                        COUNT(DISTINCT `p`.`period`) AS `active_years`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
+                    JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
                     JOIN `Item` `i2` ON `o`.`item_id` = `i2`.`id`
-                    WHERE `i2`.`type` = 'cast' AND `c`.`publish_date` IS NOT NULL
+                    WHERE `i2`.`type` = 'cast' AND `p`.`period` IS NOT NULL
                     GROUP BY `o`.`item_id`
                 ) `item_totals` ON `item_totals`.`item_id` = `ranked`.`item_id`
                 WHERE `ranked`.`rnk` = 1 AND `item_totals`.`active_years` >= 2
@@ -1285,12 +1564,13 @@ impl CharacterHomeTurfRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `ranked`.`item_id` AS `character_id`,
@@ -1305,9 +1585,9 @@ impl CharacterHomeTurfRow {
                         `ol`.`item_id` AS `loc_id`,
                         COUNT(*) AS `cnt`,
                         RANK() OVER (PARTITION BY `oc`.`item_id` ORDER BY COUNT(*) DESC) AS `rnk`
-                    FROM `Occurrence` `oc`
+                    FROM `filtered_occurrences` `oc`
                     JOIN `Item` `ic2` ON `oc`.`item_id` = `ic2`.`id` AND `ic2`.`type` = 'cast'
-                    JOIN `Occurrence` `ol` ON `ol`.`comic_id` = `oc`.`comic_id`
+                    JOIN `filtered_occurrences` `ol` ON `ol`.`comic_id` = `oc`.`comic_id`
                     JOIN `Item` `il2` ON `ol`.`item_id` = `il2`.`id` AND `il2`.`type` = 'location'
                     GROUP BY `oc`.`item_id`, `ol`.`item_id`
                 ) `ranked`
@@ -1315,7 +1595,7 @@ impl CharacterHomeTurfRow {
                 JOIN `Item` `il` ON `il`.`id` = `ranked`.`loc_id`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     JOIN `Item` ON `Occurrence`.`item_id` = `Item`.`id` AND `Item`.`type` = 'cast'
                     GROUP BY `item_id`
                 ) `char_app` ON `char_app`.`item_id` = `ranked`.`item_id`
@@ -1341,23 +1621,25 @@ impl PairEvolutionRow {
     #[tracing::instrument(skip(executor))]
     pub async fn for_pair<'e, 'c: 'e, E>(
         executor: E,
+        filter: &StatsFilter,
         char1: u16,
         char2: u16,
     ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     YEAR(`c`.`publish_date`) AS `year`,
                     COUNT(*) AS `comics_together`
-                FROM `Occurrence` `o1`
-                JOIN `Occurrence` `o2`
+                FROM `filtered_occurrences` `o1`
+                JOIN `filtered_occurrences` `o2`
                     ON `o1`.`comic_id` = `o2`.`comic_id`
                     AND `o2`.`item_id` = ?
-                JOIN `Comic` `c` ON `o1`.`comic_id` = `c`.`id`
+                JOIN `filtered_comics` `c` ON `o1`.`comic_id` = `c`.`id`
                 WHERE `o1`.`item_id` = ? AND `c`.`publish_date` IS NOT NULL
                 GROUP BY YEAR(`c`.`publish_date`)
                 ORDER BY `year`
@@ -1370,6 +1652,123 @@ impl PairEvolutionRow {
     }
 }
 
+/// A comic in which at least one of two compared items appears.
+#[derive(Copy, Clone, Debug, sqlx::FromRow)]
+pub struct PairComicRow {
+    pub comic_id: u16,
+    pub year: Option<i32>,
+    /// The 1-based position of the comic among the filtered comics.
+    pub position: i64,
+    /// The number of filtered comics.
+    pub total: i64,
+    /// `1` if the first item appears in the comic, `0` otherwise.
+    pub a_appears: i64,
+    /// `1` if the second item appears in the comic, `0` otherwise.
+    pub b_appears: i64,
+}
+
+impl PairComicRow {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn for_pair<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        a: u16,
+        b: u16,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#"
+                SELECT
+                    `c`.`id` AS `comic_id`,
+                    YEAR(`c`.`publish_date`) AS `year`,
+                    `c`.`position` AS `position!`,
+                    `c`.`total`,
+                    MAX(`o`.`item_id` = ?) AS `a_appears!`,
+                    MAX(`o`.`item_id` = ?) AS `b_appears!`
+                FROM (
+                    SELECT
+                        `pc`.`id`,
+                        `pc`.`publish_date`,
+                        CAST(ROW_NUMBER() OVER (ORDER BY `pc`.`id`) AS SIGNED) AS `position`,
+                        COUNT(*) OVER () AS `total`
+                    FROM `filtered_comics` `pc`
+                ) `c`
+                JOIN `filtered_occurrences` `o`
+                    ON `o`.`comic_id` = `c`.`id`
+                    AND `o`.`item_id` IN (?, ?)
+                GROUP BY `c`.`id`, `c`.`publish_date`, `c`.`position`, `c`.`total`
+                ORDER BY `c`.`id`
+            "#,
+            a,
+            b,
+            a,
+            b,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct MeetingLocationRow {
+    pub id: u16,
+    pub name: String,
+    pub comics_together: i64,
+}
+
+impl MeetingLocationRow {
+    /// The locations of the comics two items appear in together, most shared
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn top_for_pair<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        a: u16,
+        b: u16,
+    ) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#"
+                SELECT
+                    `l`.`id`,
+                    `l`.`name`,
+                    COUNT(*) AS `comics_together`
+                FROM `filtered_occurrences` `oa`
+                JOIN `filtered_occurrences` `ob`
+                    ON `ob`.`comic_id` = `oa`.`comic_id`
+                    AND `ob`.`item_id` = ?
+                JOIN `filtered_occurrences` `ol` ON `ol`.`comic_id` = `oa`.`comic_id`
+                JOIN `Item` `l` ON `l`.`id` = `ol`.`item_id` AND `l`.`type` = 'location'
+                WHERE `oa`.`item_id` = ? AND `l`.`id` <> ? AND `l`.`id` <> ?
+                GROUP BY `l`.`id`, `l`.`name`
+                ORDER BY `comics_together` DESC, `l`.`id`
+                LIMIT 10
+            "#,
+            b,
+            a,
+            a,
+            b,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct LonerIndexRow {
     pub id: u16,
@@ -1383,12 +1782,13 @@ impl LonerIndexRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -1396,10 +1796,10 @@ impl LonerIndexRow {
                     COUNT(*) AS `appearances`,
                     CAST(AVG(GREATEST(0, `cast_count`.`cnt` - 1)) AS DOUBLE) AS `avg_co_cast`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
+                JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
                 JOIN (
                     SELECT `o2`.`comic_id`, COUNT(*) AS `cnt`
-                    FROM `Occurrence` `o2`
+                    FROM `filtered_occurrences` `o2`
                     JOIN `Item` `i2` ON `o2`.`item_id` = `i2`.`id` AND `i2`.`type` = 'cast'
                     GROUP BY `o2`.`comic_id`
                 ) `cast_count` ON `cast_count`.`comic_id` = `o`.`comic_id`
@@ -1430,12 +1830,13 @@ impl NeverMetRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `c1`.`id` AS `character1_id`,
@@ -1448,7 +1849,7 @@ impl NeverMetRow {
                 FROM (
                     SELECT `i`.`id`, `i`.`name`, COUNT(*) AS `appearances`
                     FROM `Item` `i`
-                    JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
+                    JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
                     WHERE `i`.`type` = 'cast'
                     GROUP BY `i`.`id`
                     ORDER BY `appearances` DESC
@@ -1457,7 +1858,7 @@ impl NeverMetRow {
                 JOIN (
                     SELECT `i`.`id`, `i`.`name`, COUNT(*) AS `appearances`
                     FROM `Item` `i`
-                    JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
+                    JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
                     WHERE `i`.`type` = 'cast'
                     GROUP BY `i`.`id`
                     ORDER BY `appearances` DESC
@@ -1468,8 +1869,8 @@ impl NeverMetRow {
                         `o1`.`item_id` AS `char1_id`,
                         `o2`.`item_id` AS `char2_id`,
                         COUNT(*) AS `comics_together`
-                    FROM `Occurrence` `o1`
-                    JOIN `Occurrence` `o2`
+                    FROM `filtered_occurrences` `o1`
+                    JOIN `filtered_occurrences` `o2`
                         ON `o1`.`comic_id` = `o2`.`comic_id`
                         AND `o1`.`item_id` < `o2`.`item_id`
                     GROUP BY `o1`.`item_id`, `o2`.`item_id`
@@ -1496,18 +1897,19 @@ impl PublishTimeRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     YEAR(`publish_date`) AS `year`,
                     HOUR(`publish_date`) AS `hour`,
                     COUNT(*) AS `comics`
-                FROM `Comic`
+                FROM `filtered_comics` `Comic`
                 WHERE `publish_date` IS NOT NULL
                 GROUP BY YEAR(`publish_date`), HOUR(`publish_date`)
                 ORDER BY `year`, `hour`
@@ -1530,18 +1932,19 @@ impl ScheduleEvolutionRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     YEAR(`publish_date`) AS `year`,
                     DAYOFWEEK(`publish_date`) AS `dow`,
                     COUNT(*) AS `comics`
-                FROM `Comic`
+                FROM `filtered_comics` `Comic`
                 WHERE `publish_date` IS NOT NULL
                 GROUP BY YEAR(`publish_date`), DAYOFWEEK(`publish_date`)
                 ORDER BY `year`, `dow`
@@ -1562,17 +1965,21 @@ impl PublishedDateRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all_distinct<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all_distinct<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT `pub_date` FROM (
                     -- Weekday comics: count on their actual date
                     SELECT DATE_FORMAT(DATE(`publish_date`), '%Y-%m-%d') AS `pub_date`
-                    FROM `Comic`
+                    FROM `filtered_comics` `Comic`
                     WHERE `publish_date` IS NOT NULL
                       AND DAYOFWEEK(`publish_date`) BETWEEN 2 AND 6
                     UNION
@@ -1581,7 +1988,7 @@ impl PublishedDateRow {
                     SELECT DATE_FORMAT(DATE(DATE_ADD(`publish_date`, INTERVAL
                         CASE DAYOFWEEK(`publish_date`) WHEN 1 THEN 1 ELSE 2 END DAY
                     )), '%Y-%m-%d') AS `pub_date`
-                    FROM `Comic`
+                    FROM `filtered_comics` `Comic`
                     WHERE `publish_date` IS NOT NULL
                       AND DAYOFWEEK(`publish_date`) IN (1, 7)
                     UNION
@@ -1589,7 +1996,7 @@ impl PublishedDateRow {
                     --   Friday is excluded: an evening Fri post is still Friday's comic,
                     --   and bridging to Monday three days later would be too aggressive.
                     SELECT DATE_FORMAT(DATE(DATE_ADD(`publish_date`, INTERVAL 1 DAY)), '%Y-%m-%d') AS `pub_date`
-                    FROM `Comic`
+                    FROM `filtered_comics` `Comic`
                     WHERE `publish_date` IS NOT NULL
                       AND DAYOFWEEK(`publish_date`) BETWEEN 2 AND 5
                       AND TIME(`publish_date`) >= '20:00:00'
@@ -1614,18 +2021,19 @@ impl MonthlyHeatmapRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     YEAR(`publish_date`) AS `year`,
                     MONTH(`publish_date`) AS `month`,
                     COUNT(*) AS `comics`
-                FROM `Comic`
+                FROM `filtered_comics` `Comic`
                 WHERE `publish_date` IS NOT NULL
                 GROUP BY YEAR(`publish_date`), MONTH(`publish_date`)
                 ORDER BY `year`, `month`
@@ -1650,12 +2058,13 @@ impl MilestoneComicRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `id` AS `comic_id`,
@@ -1663,7 +2072,7 @@ impl MilestoneComicRow {
                     DATE_FORMAT(`publish_date`, '%Y-%m-%d') AS `pub_date`,
                     `is_guest_comic`,
                     `is_non_canon`
-                FROM `Comic`
+                FROM `filtered_comics` `Comic`
                 WHERE `id` = 1
                    OR (`id` >= 100 AND `id` <= 1000 AND `id` % 100 = 0)
                    OR (`id` > 1000 AND `id` % 500 = 0)
@@ -1688,12 +2097,13 @@ impl LocationSeasonRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -1701,11 +2111,11 @@ impl LocationSeasonRow {
                     MONTH(`c`.`publish_date`) AS `month`,
                     COUNT(*) AS `appearances`
                 FROM `Item` `i`
-                JOIN `Occurrence` `o` ON `o`.`item_id` = `i`.`id`
-                JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                JOIN `filtered_occurrences` `o` ON `o`.`item_id` = `i`.`id`
+                JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
                 WHERE `i`.`type` = 'location' AND `c`.`publish_date` IS NOT NULL
                   AND `i`.`id` IN (
-                    SELECT `item_id` FROM `Occurrence`
+                    SELECT `item_id` FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                     HAVING COUNT(*) >= 50
                   )
@@ -1732,12 +2142,18 @@ impl LocationBreakoutYearRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        grouping: StatsGrouping,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
+            grouping = grouping,
             r#"
                 SELECT
                     `ranked`.`item_id` AS `id`,
@@ -1748,25 +2164,27 @@ impl LocationBreakoutYearRow {
                 FROM (
                     SELECT
                         `o`.`item_id`,
-                        YEAR(`c`.`publish_date`) AS `yr`,
+                        `p`.`period` AS `yr`,
                         COUNT(*) AS `cnt`,
                         RANK() OVER (PARTITION BY `o`.`item_id` ORDER BY COUNT(*) DESC) AS `rnk`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
+                    JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
                     JOIN `Item` `i2` ON `o`.`item_id` = `i2`.`id`
-                    WHERE `i2`.`type` = 'location' AND `c`.`publish_date` IS NOT NULL
-                    GROUP BY `o`.`item_id`, YEAR(`c`.`publish_date`)
+                    WHERE `i2`.`type` = 'location' AND `p`.`period` IS NOT NULL
+                    GROUP BY `o`.`item_id`, `p`.`period`
                 ) `ranked`
                 JOIN `Item` `i` ON `i`.`id` = `ranked`.`item_id`
                 JOIN (
                     SELECT
                         `o`.`item_id`,
                         COUNT(*) AS `total_appearances`,
-                        COUNT(DISTINCT YEAR(`c`.`publish_date`)) AS `active_years`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+                        COUNT(DISTINCT `p`.`period`) AS `active_years`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
+                    JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
                     JOIN `Item` `i2` ON `o`.`item_id` = `i2`.`id`
-                    WHERE `i2`.`type` = 'location' AND `c`.`publish_date` IS NOT NULL
+                    WHERE `i2`.`type` = 'location' AND `p`.`period` IS NOT NULL
                     GROUP BY `o`.`item_id`
                 ) `item_totals` ON `item_totals`.`item_id` = `ranked`.`item_id`
                 WHERE `ranked`.`rnk` = 1 AND `item_totals`.`active_years` >= 2
@@ -1792,12 +2210,13 @@ impl LocationSocialHubRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
+            filter,
             r#"
                 SELECT
                     `i`.`id`,
@@ -1807,11 +2226,11 @@ impl LocationSocialHubRow {
                 FROM `Item` `i`
                 JOIN (
                     SELECT `item_id`, COUNT(*) AS `total`
-                    FROM `Occurrence`
+                    FROM `filtered_occurrences` `Occurrence`
                     GROUP BY `item_id`
                 ) `app` ON `app`.`item_id` = `i`.`id`
-                JOIN `Occurrence` `o1` ON `o1`.`item_id` = `i`.`id`
-                JOIN `Occurrence` `o2`
+                JOIN `filtered_occurrences` `o1` ON `o1`.`item_id` = `i`.`id`
+                JOIN `filtered_occurrences` `o2`
                     ON `o2`.`comic_id` = `o1`.`comic_id`
                     AND `o2`.`item_id` != `o1`.`item_id`
                 JOIN `Item` `i2` ON `i2`.`id` = `o2`.`item_id` AND `i2`.`type` = 'cast'
@@ -1828,7 +2247,8 @@ impl LocationSocialHubRow {
 
 #[derive(Copy, Clone, Debug, sqlx::FromRow)]
 pub struct LocationTurnoverRow {
-    pub year: Option<i32>,
+    /// The period, see [`StatsGrouping`].
+    pub year: Option<i64>,
     pub new_locations: i64,
     pub continuing_locations: i64,
     pub returning_locations: i64,
@@ -1840,19 +2260,26 @@ impl LocationTurnoverRow {
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(
+        executor: E,
+        filter: &StatsFilter,
+        grouping: StatsGrouping,
+    ) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query_as!(
+        filtered_query_as!(
             Self,
-            r#"
-                WITH `loc_yr` AS (
-                    SELECT DISTINCT YEAR(`c`.`publish_date`) AS `yr`, `o`.`item_id`
-                    FROM `Occurrence` `o`
-                    JOIN `Comic` `c` ON `o`.`comic_id` = `c`.`id`
+            filter,
+            grouping = grouping,
+            r#",
+                `loc_yr` AS (
+                    SELECT DISTINCT `p`.`period` AS `yr`, `o`.`item_id`
+                    FROM `filtered_occurrences` `o`
+                    JOIN `filtered_comics` `c` ON `o`.`comic_id` = `c`.`id`
+                    JOIN `comic_periods` `p` ON `p`.`comic_id` = `c`.`id`
                     JOIN `Item` `i` ON `o`.`item_id` = `i`.`id`
-                    WHERE `i`.`type` = 'location' AND `c`.`publish_date` IS NOT NULL
+                    WHERE `i`.`type` = 'location' AND `p`.`period` IS NOT NULL
                 ),
                 `loc_first` AS (
                     SELECT `item_id`, MIN(`yr`) AS `first_yr`
@@ -1886,6 +2313,178 @@ impl LocationTurnoverRow {
     }
 }
 
+/// A storyline's lifecycle, clipped to the filtered comics.
+#[derive(Debug, sqlx::FromRow)]
+pub struct StorylineLifecycleRow {
+    pub id: u16,
+    pub name: String,
+    pub start_comic_id: u16,
+    /// Exclusive; `None` while the storyline is ongoing.
+    pub end_comic_id: Option<u16>,
+    pub first_comic_id: u16,
+    pub last_comic_id: u16,
+    /// The position of `last_comic_id` among the filtered comics.
+    pub last_position: i64,
+    pub span_comics: i64,
+    pub first_publish_date: Option<NaiveDateTime>,
+    pub last_publish_date: Option<NaiveDateTime>,
+    pub featured_comics: i64,
+}
+
+impl StorylineLifecycleRow {
+    /// Every storyline with a lifecycle that covers at least one of the
+    /// filtered comics, in story order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#",
+                `positioned_comics` AS (
+                    SELECT
+                        `pc`.`id`,
+                        `pc`.`publish_date`,
+                        CAST(ROW_NUMBER() OVER (ORDER BY `pc`.`id`) AS SIGNED) AS `position`
+                    FROM `filtered_comics` `pc`
+                )
+                SELECT
+                    `i`.`id`,
+                    `i`.`name`,
+                    `i`.`start_comic_id` AS `start_comic_id!`,
+                    `i`.`end_comic_id`,
+                    MIN(`c`.`id`) AS `first_comic_id!`,
+                    MAX(`c`.`id`) AS `last_comic_id!`,
+                    MAX(`c`.`position`) AS `last_position!`,
+                    COUNT(*) AS `span_comics`,
+                    MIN(`c`.`publish_date`) AS `first_publish_date`,
+                    MAX(`c`.`publish_date`) AS `last_publish_date`,
+                    COUNT(`o`.`comic_id`) AS `featured_comics`
+                FROM `Item` `i`
+                JOIN `positioned_comics` `c`
+                    ON `c`.`id` >= `i`.`start_comic_id`
+                    AND (`i`.`end_comic_id` IS NULL OR `c`.`id` < `i`.`end_comic_id`)
+                LEFT JOIN `filtered_occurrences` `o`
+                    ON `o`.`comic_id` = `c`.`id`
+                    AND `o`.`item_id` = `i`.`id`
+                WHERE `i`.`type` = 'storyline' AND `i`.`start_comic_id` IS NOT NULL
+                GROUP BY `i`.`id`
+                ORDER BY `i`.`start_comic_id`, `i`.`id`
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// A comic a storyline is featured in during its lifecycle.
+#[derive(Copy, Clone, Debug, sqlx::FromRow)]
+pub struct StorylineFeatureRow {
+    pub storyline_id: u16,
+    pub comic_id: u16,
+    /// The position of the comic among the filtered comics.
+    pub position: i64,
+}
+
+impl StorylineFeatureRow {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#",
+                `positioned_comics` AS (
+                    SELECT
+                        `pc`.`id`,
+                        `pc`.`publish_date`,
+                        CAST(ROW_NUMBER() OVER (ORDER BY `pc`.`id`) AS SIGNED) AS `position`
+                    FROM `filtered_comics` `pc`
+                )
+                SELECT
+                    `o`.`item_id` AS `storyline_id`,
+                    `o`.`comic_id`,
+                    `c`.`position` AS `position!`
+                FROM `filtered_occurrences` `o`
+                JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                JOIN `positioned_comics` `c` ON `c`.`id` = `o`.`comic_id`
+                WHERE `i`.`type` = 'storyline'
+                    AND `o`.`comic_id` >= `i`.`start_comic_id`
+                    AND (`i`.`end_comic_id` IS NULL OR `o`.`comic_id` < `i`.`end_comic_id`)
+                ORDER BY `o`.`item_id`, `o`.`comic_id`
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct StorylineCastRow {
+    pub storyline_id: u16,
+    pub character_id: u16,
+    pub character_name: String,
+    pub comics_together: i64,
+}
+
+impl StorylineCastRow {
+    /// How often every character appears in the comics a storyline is
+    /// featured in during its lifecycle, most often first per storyline.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'e, 'c: 'e, E>(executor: E, filter: &StatsFilter) -> sqlx::Result<Vec<Self>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        filtered_query_as!(
+            Self,
+            filter,
+            r#"
+                SELECT
+                    `s`.`id` AS `storyline_id`,
+                    `ch`.`id` AS `character_id`,
+                    `ch`.`name` AS `character_name`,
+                    COUNT(*) AS `comics_together`
+                FROM `Item` `s`
+                JOIN `filtered_occurrences` `os`
+                    ON `os`.`item_id` = `s`.`id`
+                    AND `os`.`comic_id` >= `s`.`start_comic_id`
+                    AND (`s`.`end_comic_id` IS NULL OR `os`.`comic_id` < `s`.`end_comic_id`)
+                JOIN `filtered_occurrences` `oc` ON `oc`.`comic_id` = `os`.`comic_id`
+                JOIN `Item` `ch` ON `ch`.`id` = `oc`.`item_id` AND `ch`.`type` = 'cast'
+                WHERE `s`.`type` = 'storyline'
+                GROUP BY `s`.`id`, `ch`.`id`, `ch`.`name`
+                ORDER BY `s`.`id`, `comics_together` DESC, `ch`.`id`
+            "#,
+        )
+        .fetch_all(executor)
+        .await
+    }
+}
+
+/// Where a cached stint starts and who leads it.
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+pub struct CachedStintStart {
+    pub from_comic: u16,
+    pub item_id: u16,
+}
+
+/// A leadership stint: the span of comics during which one item of a type had
+/// more appearances than any other item of that type.
 #[derive(Debug, sqlx::FromRow)]
 pub struct TopRankedStintRow {
     pub from_comic: u16,
@@ -1899,7 +2498,13 @@ pub struct TopRankedStintRow {
 }
 
 impl TopRankedStintRow {
-    /// Atomically claims the dirty flag, deletes stale rows, and recomputes the cache.
+    /// Atomically claims the dirty flag, deletes stale rows, and recomputes the cache
+    /// for items of type `r#type`.
+    ///
+    /// Only the stints from the lowest comic whose occurrences changed onward are
+    /// recomputed; earlier stints can't have changed. The stint that was current at
+    /// that comic is kept, but its end is moved to the start of the first recomputed
+    /// one.
     ///
     /// Everything runs inside a single transaction so readers never observe an empty
     /// table and a failed recompute rolls the delete back automatically.  Any trigger
@@ -1910,48 +2515,131 @@ impl TopRankedStintRow {
     ///
     /// Returns a database error if any step fails; the transaction is rolled back.
     #[tracing::instrument(skip(conn))]
-    pub async fn refresh_cache(conn: &mut sqlx::MySqlConnection) -> sqlx::Result<()> {
+    pub async fn refresh_cache(
+        conn: &mut sqlx::MySqlConnection,
+        r#type: ItemType,
+    ) -> sqlx::Result<()> {
         use sqlx::Acquire as _;
         let mut tx = conn.begin().await?;
-        Self::clear_needs_refresh(&mut *tx).await?;
-        Self::delete_cached(&mut *tx).await?;
-        Self::insert_from_cte(&mut *tx).await?;
+        let from_comic = Self::claim_dirty_from_comic(&mut *tx, r#type)
+            .await?
+            .unwrap_or(0);
+        Self::clear_needs_refresh(&mut *tx, r#type).await?;
+
+        let previous = Self::stint_before(&mut *tx, r#type, from_comic).await?;
+        Self::delete_cached_from(&mut *tx, r#type, from_comic).await?;
+        Self::insert_from_cte(
+            &mut *tx,
+            r#type,
+            from_comic,
+            previous.as_ref().map(|p| p.item_id),
+        )
+        .await?;
+        if let Some(previous) = previous {
+            let next = Self::first_cached_from(&mut *tx, r#type, from_comic).await?;
+            Self::set_stint_end(&mut *tx, r#type, previous.from_comic, next).await?;
+        }
+
         tx.commit().await?;
         Ok(())
     }
 
+    /// Locks the cache's metadata row and returns the lowest comic whose
+    /// occurrences changed since the last refresh, or `None` if the whole cache
+    /// has to be recomputed.
+    ///
     /// # Errors
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn needs_refresh<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<bool>
+    pub async fn claim_dirty_from_comic<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+    ) -> sqlx::Result<Option<u16>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let r#type = r#type.as_str();
+        sqlx::query_scalar!(
+            r#"
+                SELECT `dirty_from_comic`
+                FROM `stats_cache_meta`
+                WHERE `cache_key` = CONCAT(?, '_rank_stints')
+                FOR UPDATE
+            "#,
+            r#type
+        )
+        .fetch_optional(executor)
+        .await
+        .map(Option::flatten)
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn needs_refresh<'e, 'c: 'e, E>(executor: E, r#type: ItemType) -> sqlx::Result<bool>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
+        let r#type = r#type.as_str();
         sqlx::query_scalar!(
             r#"
                 SELECT `needs_refresh`
                 FROM `stats_cache_meta`
-                WHERE `cache_key` = 'cast_rank_stints'
+                WHERE `cache_key` = CONCAT(?, '_rank_stints')
             "#,
+            r#type
         )
         .fetch_optional(executor)
         .await
         .map(|opt| opt.is_some_and(|v: u8| v > 0))
     }
 
+    /// Whether the stints of any item type are waiting for a refresh.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn any_needs_refresh<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<bool>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*)
+                FROM `stats_cache_meta`
+                WHERE `cache_key` IN ('cast_rank_stints', 'location_rank_stints', 'storyline_rank_stints')
+                  AND `needs_refresh` <> 0
+            "#,
+        )
+        .fetch_one(executor)
+        .await
+        .map(|count| count > 0)
+    }
+
     /// Sets `needs_refresh = 0` to claim the refresh slot before rebuilding the cache.
     ///
     /// # Errors
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn clear_needs_refresh<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<()>
+    pub async fn clear_needs_refresh<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+    ) -> sqlx::Result<()>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
+        let r#type = r#type.as_str();
         sqlx::query!(
-            "UPDATE `stats_cache_meta` SET `needs_refresh` = 0 WHERE `cache_key` = 'cast_rank_stints'",
+            r#"
+                UPDATE `stats_cache_meta`
+                SET `needs_refresh` = 0, `dirty_from_comic` = NULL
+                WHERE `cache_key` = CONCAT(?, '_rank_stints')
+            "#,
+            r#type
         )
         .execute(executor)
         .await
@@ -1978,47 +2666,162 @@ impl TopRankedStintRow {
         .await
     }
 
+    /// The cached stint that started last before `comic_id`, if any.
+    ///
     /// # Errors
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn delete_cached<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<()>
+    pub async fn stint_before<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+        comic_id: u16,
+    ) -> sqlx::Result<Option<CachedStintStart>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
-        sqlx::query!("DELETE FROM `cast_rank_leadership_stints`")
-            .execute(executor)
-            .await
-            .map(|_| ())
+        let r#type = r#type.as_str();
+        sqlx::query_as!(
+            CachedStintStart,
+            r#"
+                SELECT `from_comic`, `item_id`
+                FROM `rank_leadership_stints`
+                WHERE `item_type` = ? AND `from_comic` < ?
+                ORDER BY `from_comic` DESC
+                LIMIT 1
+            "#,
+            r#type,
+            comic_id
+        )
+        .fetch_optional(executor)
+        .await
     }
 
+    /// The start of the first cached stint from `comic_id` onward, if any.
+    ///
     /// # Errors
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn insert_from_cte<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<()>
+    pub async fn first_cached_from<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+        comic_id: u16,
+    ) -> sqlx::Result<Option<u16>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
+        let r#type = r#type.as_str();
+        sqlx::query_scalar!(
+            r#"
+                SELECT MIN(`from_comic`)
+                FROM `rank_leadership_stints`
+                WHERE `item_type` = ? AND `from_comic` >= ?
+            "#,
+            r#type,
+            comic_id
+        )
+        .fetch_one(executor)
+        .await
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn set_stint_end<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+        from_comic: u16,
+        to_comic_exclusive: Option<u16>,
+    ) -> sqlx::Result<()>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let r#type = r#type.as_str();
         sqlx::query!(
             r#"
-                INSERT INTO `cast_rank_leadership_stints`
-                    (`from_comic`, `to_comic_exclusive`, `item_id`, `appearances_at_takeover`)
-                WITH `cast_occurrences` AS (
+                UPDATE `rank_leadership_stints`
+                SET `to_comic_exclusive` = ?
+                WHERE `item_type` = ? AND `from_comic` = ?
+            "#,
+            to_comic_exclusive,
+            r#type,
+            from_comic
+        )
+        .execute(executor)
+        .await
+        .map(|_| ())
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete_cached_from<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+        comic_id: u16,
+    ) -> sqlx::Result<()>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let r#type = r#type.as_str();
+        sqlx::query!(
+            "DELETE FROM `rank_leadership_stints` WHERE `item_type` = ? AND `from_comic` >= ?",
+            r#type,
+            comic_id
+        )
+        .execute(executor)
+        .await
+        .map(|_| ())
+    }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn insert_from_cte<'e, 'c: 'e, E>(
+        executor: E,
+        r#type: ItemType,
+        from_comic: u16,
+        previous_leader: Option<u16>,
+    ) -> sqlx::Result<()>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        // Appearance counts before `from_comic` seed the running counts, so that
+        // only the occurrences from there onward go through the window functions.
+        let r#type = r#type.as_str();
+        sqlx::query!(
+            r#"
+                INSERT INTO `rank_leadership_stints`
+                    (`item_type`, `from_comic`, `to_comic_exclusive`, `item_id`, `appearances_at_takeover`)
+                WITH `typed_occurrences` AS (
                     SELECT `o`.`comic_id`, `o`.`item_id`
                     FROM `Occurrence` `o`
                     JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
-                    WHERE `i`.`type` = 'cast'
+                    WHERE `i`.`type` = ?
+                      AND `o`.`comic_id` >= ?
+                ),
+                `base_counts` AS (
+                    SELECT `o`.`item_id`, COUNT(*) AS `cnt`
+                    FROM `Occurrence` `o`
+                    JOIN `Item` `i` ON `i`.`id` = `o`.`item_id`
+                    WHERE `i`.`type` = ?
+                      AND `o`.`comic_id` < ?
+                    GROUP BY `o`.`item_id`
                 ),
                 `running_counts` AS (
                     SELECT
-                        `comic_id`,
-                        `item_id`,
-                        COUNT(*) OVER (
-                            PARTITION BY `item_id`
-                            ORDER BY `comic_id`
+                        `c`.`comic_id`,
+                        `c`.`item_id`,
+                        COALESCE(`b`.`cnt`, 0) + COUNT(*) OVER (
+                            PARTITION BY `c`.`item_id`
+                            ORDER BY `c`.`comic_id`
                         ) AS `cnt`
-                    FROM `cast_occurrences`
+                    FROM `typed_occurrences` `c`
+                    LEFT JOIN `base_counts` `b` ON `b`.`item_id` = `c`.`item_id`
                 ),
                 `new_global_highs` AS (
                     SELECT `comic_id`, `item_id`, `cnt`
@@ -2033,14 +2836,17 @@ impl TopRankedStintRow {
                             ) AS `prev_global_max`
                         FROM `running_counts`
                     ) `t`
-                    WHERE `cnt` > COALESCE(`prev_global_max`, 0)
+                    WHERE `cnt` > GREATEST(
+                        COALESCE(`prev_global_max`, 0),
+                        (SELECT COALESCE(MAX(`cnt`), 0) FROM `base_counts`)
+                    )
                 ),
                 `leader_changes` AS (
                     SELECT
                         `comic_id`,
                         `item_id`,
                         `cnt`,
-                        LAG(`item_id`) OVER (ORDER BY `comic_id`) AS `prev_item_id`
+                        COALESCE(LAG(`item_id`) OVER (ORDER BY `comic_id`), ?) AS `prev_item_id`
                     FROM `new_global_highs`
                 ),
                 `stints` AS (
@@ -2053,28 +2859,38 @@ impl TopRankedStintRow {
                     WHERE `prev_item_id` IS NULL OR `item_id` != `prev_item_id`
                 )
                 SELECT
+                    ?,
                     CAST(`s`.`from_comic`         AS UNSIGNED) AS `from_comic`,
                     `s`.`to_comic_exclusive`,
                     CAST(`s`.`item_id`            AS UNSIGNED) AS `item_id`,
                     `s`.`appearances_at_takeover`
                 FROM `stints` `s`
             "#,
+            r#type,
+            from_comic,
+            r#type,
+            from_comic,
+            previous_leader,
+            r#type
         )
         .execute(executor)
         .await
         .map(|_| ())
     }
 
-    /// Reads all stints from the pre-computed cache table.
+    /// Reads all stints of items of type `r#type` from the pre-computed cache table.
+    ///
+    /// Stints of items that have changed type since the last refresh are left out.
     ///
     /// # Errors
     ///
     /// Returns a database error if the query fails.
     #[tracing::instrument(skip(executor))]
-    pub async fn all<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<Vec<Self>>
+    pub async fn all<'e, 'c: 'e, E>(executor: E, r#type: ItemType) -> sqlx::Result<Vec<Self>>
     where
         E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
     {
+        let r#type = r#type.as_str();
         sqlx::query_as!(
             Self,
             r#"
@@ -2087,12 +2903,299 @@ impl TopRankedStintRow {
                     `i`.`color_green`,
                     `i`.`color_blue`,
                     `s`.`appearances_at_takeover`
-                FROM `cast_rank_leadership_stints` `s`
+                FROM `rank_leadership_stints` `s`
                 JOIN `Item` `i` ON `i`.`id` = `s`.`item_id`
+                WHERE `s`.`item_type` = ?
+                  AND `i`.`type` = `s`.`item_type`
                 ORDER BY `s`.`from_comic`
             "#,
+            r#type
         )
         .fetch_all(executor)
         .await
     }
 }
+
+/// The dirty flag of the server's in-memory cache of computed stats responses,
+/// set by triggers on every write to `Comic`, `Item` and `Occurrence`.
+#[derive(Debug, Clone, Copy)]
+pub struct StatsResponseCacheMeta;
+
+impl StatsResponseCacheMeta {
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn needs_refresh<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<bool>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT `needs_refresh`
+                FROM `stats_cache_meta`
+                WHERE `cache_key` = 'stats_responses'
+            "#,
+        )
+        .fetch_optional(executor)
+        .await
+        .map(|opt| opt.is_some_and(|v: u8| v > 0))
+    }
+
+    /// Sets `needs_refresh = 0` to claim the refresh before recomputing the
+    /// responses. A write during the recompute sets it again, so the next
+    /// cycle picks that up.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn clear_needs_refresh<'e, 'c: 'e, E>(executor: E) -> sqlx::Result<()>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            "UPDATE `stats_cache_meta` SET `needs_refresh` = 0 WHERE `cache_key` = 'stats_responses'",
+        )
+        .execute(executor)
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Comic, Era, Item, Occurrence};
+    use sqlx::{MySqlConnection, MySqlPool};
+
+    /// `(from_comic, to_comic_exclusive, item_id, appearances_at_takeover)`
+    type Stint = (u16, Option<u16>, u16, u32);
+
+    async fn cached_stints(
+        conn: &mut MySqlConnection,
+        r#type: ItemType,
+    ) -> sqlx::Result<Vec<Stint>> {
+        Ok(TopRankedStintRow::all(&mut *conn, r#type)
+            .await?
+            .into_iter()
+            .map(|s| {
+                (
+                    s.from_comic,
+                    s.to_comic_exclusive,
+                    s.item_id,
+                    s.appearances_at_takeover,
+                )
+            })
+            .collect())
+    }
+
+    /// Three cast members over comics 1 to 14: `a` leads from comic 1, `b`
+    /// from comic 6 and `c` from comic 13.
+    async fn seed(conn: &mut MySqlConnection) -> sqlx::Result<[u16; 3]> {
+        for comic_id in 1..=14 {
+            Comic::ensure_exists_by_id(&mut *conn, comic_id).await?;
+        }
+        let mut ids = [0; 3];
+        for (id, name) in ids.iter_mut().zip(["a", "b", "c"]) {
+            let created = Item::create(&mut *conn, name, name, ItemType::Cast).await?;
+            *id = u16::try_from(created.last_insert_id()).expect("item ids fit in u16");
+        }
+        let [a, b, c] = ids;
+        for (item_id, comics) in [
+            (a, &[1, 2, 3][..]),
+            (b, &[2, 4, 5, 6, 7]),
+            (c, &[8, 9, 10, 11, 12, 13, 14]),
+        ] {
+            for &comic_id in comics {
+                Occurrence::create(&mut *conn, item_id, comic_id).await?;
+            }
+        }
+
+        TopRankedStintRow::refresh_cache(&mut *conn, ItemType::Cast).await?;
+        assert_eq!(
+            cached_stints(&mut *conn, ItemType::Cast).await?,
+            [(1, Some(6), a, 1), (6, Some(13), b, 4), (13, None, c, 6)]
+        );
+        Ok(ids)
+    }
+
+    /// Refreshes the stints from the comic the triggers marked dirty, and
+    /// checks that the result is what recomputing every stint gives.
+    async fn refresh_incrementally(
+        conn: &mut MySqlConnection,
+        dirty_from_comic: u16,
+    ) -> sqlx::Result<Vec<Stint>> {
+        assert_eq!(
+            TopRankedStintRow::claim_dirty_from_comic(&mut *conn, ItemType::Cast).await?,
+            Some(dirty_from_comic)
+        );
+        TopRankedStintRow::refresh_cache(&mut *conn, ItemType::Cast).await?;
+        let incremental = cached_stints(&mut *conn, ItemType::Cast).await?;
+
+        TopRankedStintRow::delete_cached_from(&mut *conn, ItemType::Cast, 0).await?;
+        TopRankedStintRow::insert_from_cte(&mut *conn, ItemType::Cast, 0, None).await?;
+        assert_eq!(
+            incremental,
+            cached_stints(&mut *conn, ItemType::Cast).await?
+        );
+
+        Ok(incremental)
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn refreshing_from_a_changed_lead_matches_a_full_recompute(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let [a, b, c] = seed(&mut conn).await?;
+
+        // `b` ties `a` at comic 6 now, so it only takes over at comic 7, and
+        // `c` overtakes it one comic earlier.
+        Occurrence::delete(&mut *conn, b, 5).await?;
+
+        assert_eq!(
+            refresh_incrementally(&mut conn, 5).await?,
+            [(1, Some(7), a, 1), (7, Some(12), b, 4), (12, None, c, 5)]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn refreshing_from_a_new_high_of_the_leader_matches_a_full_recompute(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let [a, b, c] = seed(&mut conn).await?;
+
+        // `b` already leads at comic 9, so its stint goes on; `c` needs one
+        // more comic to overtake it.
+        Occurrence::create(&mut *conn, b, 9).await?;
+
+        assert_eq!(
+            refresh_incrementally(&mut conn, 9).await?,
+            [(1, Some(6), a, 1), (6, Some(14), b, 4), (14, None, c, 7)]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn refreshing_from_before_the_first_stint_matches_a_full_recompute(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let [a, b, c] = seed(&mut conn).await?;
+
+        // Nobody appears in comic 1 any more, so the first stint starts later.
+        Occurrence::delete(&mut *conn, a, 1).await?;
+        assert_eq!(
+            refresh_incrementally(&mut conn, 1).await?,
+            [(2, Some(5), a, 1), (5, Some(13), b, 3), (13, None, c, 6)]
+        );
+
+        // And now `b` leads from comic 1 until `c` overtakes it.
+        Occurrence::create(&mut *conn, b, 1).await?;
+        assert_eq!(
+            refresh_incrementally(&mut conn, 1).await?,
+            [(1, Some(14), b, 1), (14, None, c, 7)]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn refreshing_when_the_previous_leader_takes_the_lead_back_matches_a_full_recompute(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let [a, b, _] = seed(&mut conn).await?;
+
+        // `a` overtakes `b` at comic 11 and `b`, which led before comic 9,
+        // takes the lead back at comic 13, before `c` gets there.
+        for comic_id in [9, 10, 11] {
+            Occurrence::create(&mut *conn, a, comic_id).await?;
+        }
+        for comic_id in [12, 13] {
+            Occurrence::create(&mut *conn, b, comic_id).await?;
+        }
+
+        assert_eq!(
+            refresh_incrementally(&mut conn, 9).await?,
+            [
+                (1, Some(6), a, 1),
+                (6, Some(11), b, 4),
+                (11, Some(13), a, 6),
+                (13, None, b, 7)
+            ]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn changing_the_type_of_an_item_recomputes_the_stints_of_both_types(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let [a, b, c] = seed(&mut conn).await?;
+        TopRankedStintRow::refresh_cache(&mut conn, ItemType::Location).await?;
+
+        Item::update_type_by_id(&mut *conn, c, ItemType::Location).await?;
+        // Until the refresh, its old stints are hidden rather than shown as cast.
+        assert_eq!(
+            cached_stints(&mut conn, ItemType::Cast).await?,
+            [(1, Some(6), a, 1), (6, Some(13), b, 4)]
+        );
+
+        for r#type in [ItemType::Cast, ItemType::Location] {
+            assert!(TopRankedStintRow::needs_refresh(&mut *conn, r#type).await?);
+            assert_eq!(
+                TopRankedStintRow::claim_dirty_from_comic(&mut *conn, r#type).await?,
+                None
+            );
+            TopRankedStintRow::refresh_cache(&mut conn, r#type).await?;
+        }
+        assert_eq!(
+            cached_stints(&mut conn, ItemType::Cast).await?,
+            [(1, Some(6), a, 1), (6, None, b, 4)]
+        );
+        assert_eq!(
+            cached_stints(&mut conn, ItemType::Location).await?,
+            [(8, None, c, 1)]
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs a MySQL server at DATABASE_URL"]
+    async fn grouping_by_era_counts_comics_up_to_the_last_one_of_each_era(
+        pool: MySqlPool,
+    ) -> sqlx::Result<()> {
+        let mut conn = pool.acquire().await?;
+        for comic_id in 1..=9 {
+            Comic::ensure_exists_by_id(&mut *conn, comic_id).await?;
+        }
+        // Created out of order; periods follow the story.
+        Era::create(&mut *conn, "later", 6, 8).await?;
+        Era::create(&mut *conn, "earlier", 1, 3).await?;
+        let created = Item::create(&mut *conn, "a", "a", ItemType::Cast).await?;
+        let a = u16::try_from(created.last_insert_id()).expect("item ids fit in u16");
+        // Comic 4 is between the eras and comic 9 after both.
+        for comic_id in [3, 4, 6, 8, 9] {
+            Occurrence::create(&mut *conn, a, comic_id).await?;
+        }
+
+        let periods: Vec<_> =
+            YearlyAppearanceRow::all(&mut *conn, &StatsFilter::default(), StatsGrouping::Era)
+                .await?
+                .into_iter()
+                .map(|row| (row.year, row.id, row.appearances))
+                .collect();
+
+        assert_eq!(periods, [(Some(1), a, 1), (Some(2), a, 2)]);
+        Ok(())
+    }
+}
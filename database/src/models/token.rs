@@ -70,4 +70,103 @@ impl Token {
         }
         Ok(permissions)
     }
+
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor))]
+    pub async fn id_by_identifier<'e, 'c: 'e, E>(
+        executor: E,
+        identifier: &str,
+    ) -> sqlx::Result<Option<String>>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query_scalar!(
+            r#"
+                SELECT `id` FROM `Token`
+                WHERE `identifier` = ?
+                LIMIT 1
+            "#,
+            identifier
+        )
+        .fetch_optional(executor)
+        .await
+    }
+
+    /// Creates a token without any permissions.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor, id))]
+    pub async fn create<'e, 'c: 'e, E>(
+        executor: E,
+        id: &str,
+        identifier: &str,
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        sqlx::query!(
+            r#"
+                INSERT INTO `Token`
+                    (`id`, `identifier`)
+                VALUES
+                    (?, ?)
+            "#,
+            id,
+            identifier,
+        )
+        .execute(executor)
+        .await
+    }
+
+    /// Creates a token with the given permissions, named as in
+    /// [`token_permissions`]. Names that are not grantable are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the query fails.
+    #[tracing::instrument(skip(executor, id))]
+    pub async fn create_with_permissions<'e, 'c: 'e, E>(
+        executor: E,
+        id: &str,
+        identifier: &str,
+        permissions: &[&str],
+    ) -> sqlx::Result<crate::DatabaseQueryResult>
+    where
+        E: 'e + sqlx::Executor<'c, Database = crate::DatabaseDriver>,
+    {
+        let has = |permission| u8::from(permissions.contains(&permission));
+        sqlx::query!(
+            r#"
+                INSERT INTO `Token`
+                    (
+                        `id`,
+                        `identifier`,
+                        `can_add_advance_comic`,
+                        `can_add_image_to_item`,
+                        `can_add_item_to_comic`,
+                        `can_change_comic_data`,
+                        `can_change_item_data`,
+                        `can_remove_image_from_item`,
+                        `can_remove_item_from_comic`
+                    )
+                VALUES
+                    (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            identifier,
+            has(token_permissions::CAN_ADD_ADVANCE_COMIC),
+            has(token_permissions::CAN_ADD_IMAGE_TO_ITEM),
+            has(token_permissions::CAN_ADD_ITEM_TO_COMIC),
+            has(token_permissions::CAN_CHANGE_COMIC_DATA),
+            has(token_permissions::CAN_CHANGE_ITEM_DATA),
+            has(token_permissions::CAN_REMOVE_IMAGE_FROM_ITEM),
+            has(token_permissions::CAN_REMOVE_ITEM_FROM_COMIC),
+        )
+        .execute(executor)
+        .await
+    }
 }
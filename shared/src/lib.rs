@@ -9,4 +9,16 @@ pub mod token_permissions {
     pub const CAN_REMOVE_IMAGE_FROM_ITEM: &str = "CAN_REMOVE_IMAGE_FROM_ITEM";
     pub const CAN_CHANGE_ITEM_DATA: &str = "CAN_CHANGE_ITEM_DATA";
     pub const CAN_ADD_ADVANCE_COMIC: &str = "CAN_ADD_ADVANCE_COMIC";
+
+    /// The permissions that can be granted to a token; every token has
+    /// [`HAS_VALID_TOKEN`].
+    pub const GRANTABLE: [&str; 7] = [
+        CAN_ADD_ITEM_TO_COMIC,
+        CAN_REMOVE_ITEM_FROM_COMIC,
+        CAN_CHANGE_COMIC_DATA,
+        CAN_ADD_IMAGE_TO_ITEM,
+        CAN_REMOVE_IMAGE_FROM_ITEM,
+        CAN_CHANGE_ITEM_DATA,
+        CAN_ADD_ADVANCE_COMIC,
+    ];
 }
@@ -4,6 +4,8 @@ mod v1;
 mod v2;
 mod v3;
 
+pub use v3::prime_stats_response_cache;
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/v2").configure(v2::configure_v2));
     cfg.service(web::scope("/v3").configure(v3::configure_v3));
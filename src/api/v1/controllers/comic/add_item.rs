@@ -77,6 +77,7 @@ pub(crate) async fn add_item(
             primary_image: None,
             start_comic_id: None,
             end_comic_id: None,
+            version: 1,
         }
     } else {
         let item_id = request.item_id.cast_unsigned();
@@ -181,6 +181,7 @@ fn hydrate_navigation_item_with_item_data(
                 primary_image: _,
                 start_comic_id: _,
                 end_comic_id: _,
+                version: _,
             } = items
                 .remove(&unhydrated.id.into_inner())
                 .expect("item data for navigation item");
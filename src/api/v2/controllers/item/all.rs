@@ -49,6 +49,7 @@ pub(crate) async fn all(pool: web::Data<DbPool>) -> Result<HttpResponse> {
             primary_image: _,
             start_comic_id: _,
             end_comic_id: _,
+            version: _,
         } = item;
         let id = id.into();
 
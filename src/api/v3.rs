@@ -2,3 +2,4 @@ mod controllers;
 mod models;
 
 pub(super) use controllers::configure as configure_v3;
+pub(super) use controllers::prime_response_cache as prime_stats_response_cache;
@@ -1,13 +1,44 @@
+use crate::util::{cache_dataset_responses, cache_stats_responses, csv_responses};
+use actix_web::middleware::from_fn;
 use actix_web::web;
 
+mod admin;
 mod comic;
+mod era;
+mod export;
 mod item;
 mod log;
+mod search;
 mod stats;
+mod sync;
+
+pub(super) use stats::prime_response_cache;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::scope("/comicdata").configure(comic::configure));
-    cfg.service(web::scope("/itemdata").configure(item::configure));
+    cfg.service(web::scope("/admin").configure(admin::configure));
+    cfg.service(
+        web::scope("/comicdata")
+            .wrap(from_fn(cache_dataset_responses))
+            .configure(comic::configure),
+    );
+    cfg.service(
+        web::scope("/eradata")
+            .wrap(from_fn(cache_dataset_responses))
+            .configure(era::configure),
+    );
+    cfg.service(web::scope("/export").configure(export::configure));
+    cfg.service(
+        web::scope("/itemdata")
+            .wrap(from_fn(cache_dataset_responses))
+            .configure(item::configure),
+    );
     cfg.service(web::scope("/log").configure(log::configure));
-    cfg.service(web::scope("/stats").configure(stats::configure));
+    cfg.service(web::scope("/search").configure(search::configure));
+    cfg.service(
+        web::scope("/stats")
+            .wrap(from_fn(cache_stats_responses))
+            .wrap(from_fn(csv_responses))
+            .configure(stats::configure),
+    );
+    cfg.service(web::scope("/sync").configure(sync::configure));
 }
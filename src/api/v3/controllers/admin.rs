@@ -0,0 +1,431 @@
+use crate::api::v3::models::{
+    Anomaly, AnomalyCategory, AnomalyCategoryCount, AnomalyLinks, AnomalyReport,
+};
+use crate::models::{ComicId, ItemId};
+use crate::util::{NewsUpdater, ensure_is_authorized};
+use actix_web::web::Json;
+use actix_web::{Result, error, web};
+use actix_web_grants::authorities::AuthDetails;
+use api_macros::api_endpoint;
+use database::models::{
+    AnomalyCounts, HiddenComicAnomaly, News, OccurrenceAnomaly, PublishDateAnomaly,
+    UnusedItemAnomaly,
+};
+use database::{DbPool, DbPoolConnection};
+use serde::Deserialize;
+use shared::token_permissions;
+use tracing::{Instrument, info_span};
+use ts_rs::TS;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(anomalies).service(news_sweep);
+}
+
+const PAGE_SIZE: u16 = 50;
+
+/// Every check, in the order their findings are listed.
+const CATEGORIES: [AnomalyCategory; 7] = [
+    AnomalyCategory::CastInComicWithoutCast,
+    AnomalyCategory::LocationInComicWithoutLocation,
+    AnomalyCategory::StorylineInComicWithoutStoryline,
+    AnomalyCategory::StorylineOutsideLifecycle,
+    AnomalyCategory::ItemWithoutOccurrences,
+    AnomalyCategory::HiddenComicWithOccurrences,
+    AnomalyCategory::PublishDateOutOfOrder,
+];
+
+/// Returns a page of what the data-quality checks found, optionally limited
+/// to one category, along with how much every check found. Only the checks
+/// with findings on the requested page are read.
+#[api_endpoint(method = "GET", path = "admin/anomalies")]
+#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+async fn anomalies(
+    pool: web::Data<DbPool>,
+    query: web::Query<AnomaliesQuery>,
+    auth: AuthDetails,
+) -> Result<Json<AnomalyReport>> {
+    ensure_is_authorized(&auth, token_permissions::HAS_VALID_TOKEN)
+        .map_err(error::ErrorForbidden)?;
+
+    let AnomaliesQuery { category, page } = query.into_inner();
+    let page = page.unwrap_or(1).max(1);
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let counts = category_counts(
+        &AnomalyCounts::get(&mut *conn)
+            .await
+            .map_err(error::ErrorInternalServerError)?,
+    );
+
+    let mut anomalies = Vec::new();
+    for (category, limit, offset) in page_slices(&counts, category, page) {
+        anomalies.extend(
+            fetch_anomalies(&mut conn, category, limit, offset)
+                .await
+                .map_err(error::ErrorInternalServerError)?,
+        );
+    }
+
+    let anomaly_count: u64 = counts
+        .iter()
+        .filter(|(c, _)| category.is_none_or(|category| *c == category))
+        .map(|(_, count)| count)
+        .sum();
+
+    Ok(Json(AnomalyReport {
+        categories: counts
+            .into_iter()
+            .map(|(category, count)| AnomalyCategoryCount {
+                category,
+                count: i32::try_from(count).unwrap_or(i32::MAX),
+            })
+            .collect(),
+        anomalies,
+        page,
+        page_count: u16::try_from(anomaly_count.div_ceil(u64::from(PAGE_SIZE))).unwrap_or(u16::MAX),
+        anomaly_count: i32::try_from(anomaly_count).unwrap_or(i32::MAX),
+    }))
+}
+
+/// Schedules a news update check for every comic whose news is outdated.
+#[api_endpoint(method = "POST", path = "admin/news-sweep")]
+#[tracing::instrument(skip(pool, news_updater, auth), fields(permissions = ?auth.authorities))]
+async fn news_sweep(
+    pool: web::Data<DbPool>,
+    news_updater: web::Data<NewsUpdater>,
+    auth: AuthDetails,
+) -> Result<Json<String>> {
+    ensure_is_authorized(&auth, token_permissions::CAN_CHANGE_COMIC_DATA)
+        .map_err(error::ErrorForbidden)?;
+
+    let news = News::all(&***pool)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let mut scheduled = 0;
+    for news in news.iter().filter(|n| n.is_outdated()) {
+        news_updater.check_for(ComicId::from_trusted(news.comic_id));
+        scheduled += 1;
+    }
+
+    Ok(Json(format!(
+        "Scheduled a news update check for {scheduled} comic(s)"
+    )))
+}
+
+/// The number of findings of every check, in the order of [`CATEGORIES`].
+fn category_counts(counts: &AnomalyCounts) -> [(AnomalyCategory, u64); 7] {
+    CATEGORIES.map(|category| {
+        let count = match category {
+            AnomalyCategory::CastInComicWithoutCast => counts.cast_in_comics_without_cast,
+            AnomalyCategory::LocationInComicWithoutLocation => {
+                counts.locations_in_comics_without_location
+            }
+            AnomalyCategory::StorylineInComicWithoutStoryline => {
+                counts.storylines_in_comics_without_storyline
+            }
+            AnomalyCategory::StorylineOutsideLifecycle => counts.storylines_outside_lifecycle,
+            AnomalyCategory::ItemWithoutOccurrences => counts.unused_items,
+            AnomalyCategory::HiddenComicWithOccurrences => counts.hidden_comics,
+            AnomalyCategory::PublishDateOutOfOrder => counts.publish_dates,
+        };
+        (category, u64::try_from(count).unwrap_or(0))
+    })
+}
+
+/// Which findings are on `page` of those in `category` (or of all of them,
+/// in the order of `counts`), as `(category, limit, offset)` per check.
+fn page_slices(
+    counts: &[(AnomalyCategory, u64)],
+    category: Option<AnomalyCategory>,
+    page: u16,
+) -> Vec<(AnomalyCategory, u32, u32)> {
+    let mut skip = u64::from(page - 1) * u64::from(PAGE_SIZE);
+    let mut take = u64::from(PAGE_SIZE);
+    let mut slices = Vec::new();
+    for &(c, count) in counts
+        .iter()
+        .filter(|(c, _)| category.is_none_or(|category| *c == category))
+    {
+        if take == 0 {
+            break;
+        }
+        if skip >= count {
+            skip -= count;
+            continue;
+        }
+
+        let limit = take.min(count - skip);
+        slices.push((
+            c,
+            u32::try_from(limit).unwrap_or(u32::MAX),
+            u32::try_from(skip).unwrap_or(u32::MAX),
+        ));
+        take -= limit;
+        skip = 0;
+    }
+    slices
+}
+
+/// Runs the check of `category`, reading `limit` findings from `offset` on.
+async fn fetch_anomalies(
+    conn: &mut DbPoolConnection,
+    category: AnomalyCategory,
+    limit: u32,
+    offset: u32,
+) -> sqlx::Result<Vec<Anomaly>> {
+    let occurrences = |rows: Vec<OccurrenceAnomaly>| -> Vec<Anomaly> {
+        rows.iter()
+            .map(|row| occurrence_anomaly(category, row))
+            .collect()
+    };
+
+    Ok(match category {
+        AnomalyCategory::CastInComicWithoutCast => occurrences(
+            OccurrenceAnomaly::cast_in_comics_without_cast(&mut **conn, limit, offset).await?,
+        ),
+        AnomalyCategory::LocationInComicWithoutLocation => occurrences(
+            OccurrenceAnomaly::locations_in_comics_without_location(&mut **conn, limit, offset)
+                .await?,
+        ),
+        AnomalyCategory::StorylineInComicWithoutStoryline => occurrences(
+            OccurrenceAnomaly::storylines_in_comics_without_storyline(&mut **conn, limit, offset)
+                .await?,
+        ),
+        AnomalyCategory::StorylineOutsideLifecycle => occurrences(
+            OccurrenceAnomaly::storylines_outside_lifecycle(&mut **conn, limit, offset).await?,
+        ),
+        AnomalyCategory::ItemWithoutOccurrences => {
+            UnusedItemAnomaly::all(&mut **conn, limit, offset)
+                .await?
+                .iter()
+                .map(unused_item_anomaly)
+                .collect()
+        }
+        AnomalyCategory::HiddenComicWithOccurrences => {
+            HiddenComicAnomaly::all(&mut **conn, limit, offset)
+                .await?
+                .iter()
+                .map(hidden_comic_anomaly)
+                .collect()
+        }
+        AnomalyCategory::PublishDateOutOfOrder => {
+            PublishDateAnomaly::all(&mut **conn, limit, offset)
+                .await?
+                .iter()
+                .map(publish_date_anomaly)
+                .collect()
+        }
+    })
+}
+
+fn occurrence_anomaly(category: AnomalyCategory, row: &OccurrenceAnomaly) -> Anomaly {
+    let marked_without = match category {
+        AnomalyCategory::CastInComicWithoutCast => Some("cast"),
+        AnomalyCategory::LocationInComicWithoutLocation => Some("location"),
+        AnomalyCategory::StorylineInComicWithoutStoryline => Some("storyline"),
+        _ => None,
+    };
+    let description = marked_without.map_or_else(
+        || {
+            format!(
+                "{} is in comic {}, outside of its lifecycle",
+                row.item_name, row.comic_id
+            )
+        },
+        |marked_without| {
+            format!(
+                "{} is in comic {}, which is marked as having no {marked_without}",
+                row.item_name, row.comic_id
+            )
+        },
+    );
+    anomaly(category, Some(row.comic_id), Some(row.item_id), description)
+}
+
+fn unused_item_anomaly(row: &UnusedItemAnomaly) -> Anomaly {
+    anomaly(
+        AnomalyCategory::ItemWithoutOccurrences,
+        None,
+        Some(row.item_id),
+        format!("{} is not in any comic", row.item_name),
+    )
+}
+
+fn hidden_comic_anomaly(row: &HiddenComicAnomaly) -> Anomaly {
+    anomaly(
+        AnomalyCategory::HiddenComicWithOccurrences,
+        Some(row.comic_id),
+        None,
+        format!(
+            "Comic {} is hidden but has {} items",
+            row.comic_id, row.occurrences
+        ),
+    )
+}
+
+fn publish_date_anomaly(row: &PublishDateAnomaly) -> Anomaly {
+    anomaly(
+        AnomalyCategory::PublishDateOutOfOrder,
+        Some(row.comic_id),
+        None,
+        format!(
+            "Comic {} was published on {}, before comic {} on {}",
+            row.comic_id,
+            row.publish_date.format("%Y-%m-%d %H:%M"),
+            row.previous_comic_id,
+            row.previous_publish_date.format("%Y-%m-%d %H:%M"),
+        ),
+    )
+}
+
+fn anomaly(
+    category: AnomalyCategory,
+    comic_id: Option<u16>,
+    item_id: Option<u16>,
+    description: String,
+) -> Anomaly {
+    Anomaly {
+        category,
+        comic_id: comic_id.map(ComicId::from_trusted),
+        item_id: item_id.map(ItemId::from),
+        description,
+        links: AnomalyLinks {
+            comic: comic_id.map(|id| format!("/api/v3/comicdata/{id}")),
+            item: item_id.map(|id| format!("/api/v3/itemdata/{id}")),
+        },
+    }
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+struct AnomaliesQuery {
+    #[ts(optional)]
+    category: Option<AnomalyCategory>,
+    #[ts(optional)]
+    page: Option<u16>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn counts() -> [(AnomalyCategory, u64); 7] {
+        category_counts(&AnomalyCounts {
+            cast_in_comics_without_cast: 30,
+            locations_in_comics_without_location: 0,
+            storylines_in_comics_without_storyline: 0,
+            storylines_outside_lifecycle: 45,
+            unused_items: 1,
+            hidden_comics: 0,
+            publish_dates: 120,
+        })
+    }
+
+    #[test]
+    fn counts_every_category() {
+        assert_eq!(
+            counts(),
+            [
+                (AnomalyCategory::CastInComicWithoutCast, 30),
+                (AnomalyCategory::LocationInComicWithoutLocation, 0),
+                (AnomalyCategory::StorylineInComicWithoutStoryline, 0),
+                (AnomalyCategory::StorylineOutsideLifecycle, 45),
+                (AnomalyCategory::ItemWithoutOccurrences, 1),
+                (AnomalyCategory::HiddenComicWithOccurrences, 0),
+                (AnomalyCategory::PublishDateOutOfOrder, 120),
+            ]
+        );
+    }
+
+    #[test]
+    fn pages_of_all_findings_span_categories() {
+        assert_eq!(
+            page_slices(&counts(), None, 1),
+            [
+                (AnomalyCategory::CastInComicWithoutCast, 30, 0),
+                (AnomalyCategory::StorylineOutsideLifecycle, 20, 0),
+            ]
+        );
+        assert_eq!(
+            page_slices(&counts(), None, 2),
+            [
+                (AnomalyCategory::StorylineOutsideLifecycle, 25, 20),
+                (AnomalyCategory::ItemWithoutOccurrences, 1, 0),
+                (AnomalyCategory::PublishDateOutOfOrder, 24, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn pages_of_one_category_only_read_that_check() {
+        assert_eq!(
+            page_slices(&counts(), Some(AnomalyCategory::PublishDateOutOfOrder), 3),
+            [(AnomalyCategory::PublishDateOutOfOrder, 20, 100)]
+        );
+        assert_eq!(
+            page_slices(&counts(), Some(AnomalyCategory::PublishDateOutOfOrder), 4),
+            []
+        );
+    }
+
+    #[test]
+    fn occurrence_findings_are_described_and_linked() {
+        let anomaly = occurrence_anomaly(
+            AnomalyCategory::StorylineOutsideLifecycle,
+            &OccurrenceAnomaly {
+                comic_id: 5,
+                item_id: 3,
+                item_name: "Item 3".to_string(),
+            },
+        );
+
+        assert_eq!(
+            anomaly.description,
+            "Item 3 is in comic 5, outside of its lifecycle"
+        );
+        assert_eq!(anomaly.links.comic.as_deref(), Some("/api/v3/comicdata/5"));
+        assert_eq!(anomaly.links.item.as_deref(), Some("/api/v3/itemdata/3"));
+
+        let anomaly = occurrence_anomaly(
+            AnomalyCategory::CastInComicWithoutCast,
+            &OccurrenceAnomaly {
+                comic_id: 10,
+                item_id: 1,
+                item_name: "Item 1".to_string(),
+            },
+        );
+        assert_eq!(
+            anomaly.description,
+            "Item 1 is in comic 10, which is marked as having no cast"
+        );
+    }
+
+    #[test]
+    fn publish_date_findings_name_both_comics() {
+        let date = |day| {
+            NaiveDate::from_ymd_opt(2004, 1, day)
+                .and_then(|d| d.and_hms_opt(12, 0, 0))
+                .unwrap()
+        };
+
+        let anomaly = publish_date_anomaly(&PublishDateAnomaly {
+            comic_id: 20,
+            publish_date: date(1),
+            previous_comic_id: 19,
+            previous_publish_date: date(2),
+        });
+
+        assert_eq!(
+            anomaly.description,
+            "Comic 20 was published on 2004-01-01 12:00, before comic 19 on 2004-01-02 12:00"
+        );
+        assert_eq!(anomaly.item_id, None);
+    }
+}
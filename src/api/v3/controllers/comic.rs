@@ -6,15 +6,19 @@ pub(super) mod navigation_data;
 mod add_advance_comic;
 mod add_item;
 mod all;
+mod batch;
 mod by_id;
+mod co_appearance_navigation;
 mod editor_data;
 mod patch_comic;
+mod query;
 mod remove_item;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(all::all)
         .service(all::excluded)
         .service(all::containing_items)
+        .service(query::query)
         .service(add_item::add_item)
         .service(add_item::add_items)
         .service(remove_item::remove_item)
@@ -22,5 +26,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(add_advance_comic::add_advance_comic)
         .service(add_advance_comic::list_advance_comics)
         .service(add_advance_comic::run_comic_updater)
+        .service(batch::batch)
+        .service(co_appearance_navigation::co_appearance_navigation)
         .service(by_id::by_id);
 }
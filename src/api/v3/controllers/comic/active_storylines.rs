@@ -3,6 +3,7 @@ use crate::models::ComicId;
 use actix_web::{Result, error};
 use database::DbPoolConnection;
 use database::models::{ActiveStorylineOccurrence, Comic as DatabaseComic, Item as DatabaseItem};
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 
 /// Fetches every storyline active at `comic_id` and pre-aggregates its
@@ -23,29 +24,75 @@ pub async fn fetch_active_storylines(
         .map_err(error::ErrorInternalServerError)?
         .unwrap_or_else(|| comic_id.into_inner());
 
-    group_into_summaries(&occurrences, latest_comic_id)
-        .into_iter()
-        .map(|group| {
-            Ok(ActiveStorylineSummary {
-                id: group.item_id.into(),
-                start_comic_id: group
-                    .start_comic_id
-                    .try_into()
-                    .expect("database has valid comicIds"),
-                end_comic_id: group
-                    .end_comic_id
-                    .map(TryInto::try_into)
-                    .transpose()
-                    .expect("database has valid comicIds"),
-                segments: build_segments(
-                    group.start_comic_id,
-                    group.end_comic_id,
-                    latest_comic_id,
-                    &group.occurrence_comic_ids,
-                ),
-            })
-        })
-        .collect()
+    Ok(group_into_summaries(&occurrences, latest_comic_id)
+        .iter()
+        .map(|group| summarize(group, latest_comic_id))
+        .collect())
+}
+
+/// Like [`fetch_active_storylines`], but for every comic in `from..=to` at
+/// once, keyed by comic id. Comics without any active storyline are absent
+/// from the map.
+#[tracing::instrument(skip(conn))]
+pub async fn fetch_active_storylines_for_range(
+    conn: &mut DbPoolConnection,
+    from: ComicId,
+    to: ComicId,
+) -> Result<BTreeMap<ComicId, Vec<ActiveStorylineSummary>>> {
+    let occurrences = DatabaseItem::active_storylines_in_comic_range(
+        &mut **conn,
+        from.into_inner(),
+        to.into_inner(),
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    let latest_comic_id = DatabaseComic::latest_id(&mut **conn)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .unwrap_or_else(|| to.into_inner());
+
+    let mut by_comic: BTreeMap<ComicId, Vec<ActiveStorylineSummary>> = BTreeMap::new();
+    for group in group_into_summaries(&occurrences, latest_comic_id) {
+        let active_from = group.start_comic_id.max(from.into_inner());
+        let active_to = group.end_comic_id.map_or(to.into_inner(), |end| {
+            end.saturating_sub(1).min(to.into_inner())
+        });
+        if active_from > active_to {
+            continue;
+        }
+
+        let summary = summarize(&group, latest_comic_id);
+        for comic_id in active_from..=active_to {
+            by_comic
+                .entry(ComicId::from_trusted(comic_id))
+                .or_default()
+                .push(summary.clone());
+        }
+    }
+
+    Ok(by_comic)
+}
+
+fn summarize(group: &StorylineGroup, latest_comic_id: u16) -> ActiveStorylineSummary {
+    ActiveStorylineSummary {
+        id: group.item_id.into(),
+        start_comic_id: group
+            .start_comic_id
+            .try_into()
+            .expect("database has valid comicIds"),
+        end_comic_id: group
+            .end_comic_id
+            .map(TryInto::try_into)
+            .transpose()
+            .expect("database has valid comicIds"),
+        segments: build_segments(
+            group.start_comic_id,
+            group.end_comic_id,
+            latest_comic_id,
+            &group.occurrence_comic_ids,
+        ),
+    }
 }
 
 struct StorylineGroup {
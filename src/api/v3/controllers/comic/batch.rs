@@ -0,0 +1,169 @@
+use crate::api::v3::controllers::comic::active_storylines::fetch_active_storylines_for_range;
+use crate::api::v3::controllers::comic::by_id::present_comic_data;
+use crate::api::v3::controllers::comic::navigation_data::fetch_item_navigation_data_for_range;
+use crate::api::v3::models::{
+    Comic, ComicData, EditorData, Exclusion, MissingComic, MissingEditorData,
+};
+use crate::models::{ComicId, False};
+use crate::util::NewsUpdater;
+use actix_web::web::Json;
+use actix_web::{Result, error, web};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
+use api_macros::api_endpoint;
+use database::DbPool;
+use database::models::Comic as DatabaseComic;
+use serde::Deserialize;
+use shared::token_permissions;
+use std::collections::BTreeMap;
+use tracing::{Instrument, info_span};
+use ts_rs::TS;
+
+/// The largest `max - min + 1` a batch may cover. Everything in between the
+/// lowest and highest requested id is fetched, so this also bounds sparse
+/// `ids` lists.
+const MAX_BATCH_SPAN: u16 = 100;
+
+/// Fetches many comics in one request, either as `ids=1,2,3` or as the
+/// inclusive range `from=1&to=3`. Returns the same payload as
+/// `comicdata/{comicId}` for each comic, in request order, except that
+/// `allItems` and `editorData` are never populated.
+#[api_endpoint(method = "GET", path = "comicdata/batch")]
+#[tracing::instrument(skip(pool, news_updater, auth), fields(permissions = ?auth.authorities))]
+pub async fn batch(
+    pool: web::Data<DbPool>,
+    news_updater: web::Data<NewsUpdater>,
+    query: web::Query<BatchQuery>,
+    auth: AuthDetails,
+) -> Result<Json<Vec<Comic>>> {
+    let comic_ids = query.comic_ids().map_err(error::ErrorBadRequest)?;
+    let (Some(&from), Some(&to)) = (comic_ids.iter().min(), comic_ids.iter().max()) else {
+        return Ok(Json(Vec::new()));
+    };
+    if to.into_inner() - from.into_inner() >= MAX_BATCH_SPAN {
+        return Err(error::ErrorBadRequest(format!(
+            "a batch can span at most {MAX_BATCH_SPAN} comics"
+        )));
+    }
+
+    let (include_guest_comics, include_non_canon_comics) = match query.exclude {
+        None => (None, None),
+        Some(Exclusion::Guest) => (Some(false), None),
+        Some(Exclusion::NonCanon) => (None, Some(false)),
+    };
+    let include_hidden = auth.has_authority(token_permissions::HAS_VALID_TOKEN);
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let mut comics: BTreeMap<ComicId, _> = DatabaseComic::range_with_navigation_and_news(
+        &mut *conn,
+        from.into_inner(),
+        to.into_inner(),
+        include_guest_comics,
+        include_non_canon_comics,
+        include_hidden,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?
+    .into_iter()
+    .map(|comic| (ComicId::from_trusted(comic.id), comic))
+    .collect();
+
+    let mut navigation_items = fetch_item_navigation_data_for_range(
+        &mut conn,
+        from,
+        to,
+        include_guest_comics,
+        include_non_canon_comics,
+    )
+    .await?;
+
+    let mut active_storylines = fetch_active_storylines_for_range(&mut conn, from, to).await?;
+
+    Ok(Json(
+        comic_ids
+            .into_iter()
+            .map(|comic_id| {
+                let data = if let Some(comic) = comics.remove(&comic_id) {
+                    news_updater.check_for(comic_id);
+
+                    present_comic_data(
+                        comic,
+                        navigation_items
+                            .remove(&comic_id)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        active_storylines.remove(&comic_id).unwrap_or_default(),
+                    )
+                } else {
+                    ComicData::Missing(MissingComic {
+                        has_data: False::default(),
+                    })
+                };
+
+                Comic {
+                    comic: comic_id,
+                    editor_data: EditorData::Missing(MissingEditorData::default()),
+                    all_items: Vec::new(),
+                    data,
+                }
+            })
+            .collect(),
+    ))
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct BatchQuery {
+    /// Comma-separated list of comic ids.
+    #[ts(optional)]
+    ids: Option<String>,
+    #[ts(optional)]
+    from: Option<ComicId>,
+    #[ts(optional)]
+    to: Option<ComicId>,
+    #[ts(optional)]
+    exclude: Option<Exclusion>,
+}
+
+impl BatchQuery {
+    /// The requested comic ids, deduplicated but otherwise in request order.
+    fn comic_ids(&self) -> Result<Vec<ComicId>, String> {
+        match (&self.ids, self.from, self.to) {
+            (Some(ids), None, None) => {
+                let mut comic_ids = Vec::new();
+                for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+                    let comic_id = id
+                        .parse::<u16>()
+                        .ok()
+                        .and_then(|id| ComicId::try_from(id).ok())
+                        .ok_or_else(|| format!("{id} is not a valid comic id"))?;
+                    if !comic_ids.contains(&comic_id) {
+                        comic_ids.push(comic_id);
+                    }
+                    if comic_ids.len() > usize::from(MAX_BATCH_SPAN) {
+                        return Err(format!("a batch can span at most {MAX_BATCH_SPAN} comics"));
+                    }
+                }
+                Ok(comic_ids)
+            }
+            (None, Some(from), Some(to)) if from > to => {
+                Err("from must not be larger than to".to_owned())
+            }
+            (None, Some(from), Some(to))
+                if to.into_inner() - from.into_inner() >= MAX_BATCH_SPAN =>
+            {
+                Err(format!("a batch can span at most {MAX_BATCH_SPAN} comics"))
+            }
+            (None, Some(from), Some(to)) => Ok((from.into_inner().max(1)..=to.into_inner())
+                .map(ComicId::from_trusted)
+                .collect()),
+            _ => Err("either ids or both from and to must be given".to_owned()),
+        }
+    }
+}
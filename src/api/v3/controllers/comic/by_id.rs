@@ -4,18 +4,18 @@ use crate::api::v3::controllers::comic::navigation_data::{
     ItemNavigationDataSorting, fetch_all_item_navigation_data, fetch_comic_item_navigation_data,
 };
 use crate::api::v3::models::{
-    Comic, ComicData, EditorData, Exclusion, ImageType, Inclusion, ItemNavigationData,
-    MissingComic, MissingEditorData, PresentComic, Sorting,
+    ActiveStorylineSummary, Comic, ComicData, EditorData, Exclusion, ImageType, Inclusion,
+    ItemNavigationData, MissingComic, MissingEditorData, PresentComic, Sorting,
 };
 use crate::models::{ComicId, False, True};
-use crate::util::NewsUpdater;
+use crate::util::{NewsUpdater, mark_cacheable};
 use actix_web::web::Json;
-use actix_web::{Result, error, web};
+use actix_web::{HttpRequest, Result, error, web};
 use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
 use api_macros::api_endpoint;
 use chrono::{TimeZone, Utc};
 use database::DbPool;
-use database::models::{Comic as DatabaseComic, Item as DatabaseItem};
+use database::models::{Comic as DatabaseComic, ComicWithNavigationAndNews, Item as DatabaseItem};
 use serde::Deserialize;
 use shared::token_permissions;
 use std::convert::TryInto;
@@ -23,9 +23,10 @@ use tracing::{Instrument, info_span};
 use ts_rs::TS;
 
 #[api_endpoint(method = "GET", path = "comicdata/{comicId}")]
-#[tracing::instrument(skip(pool, news_updater, auth), fields(permissions = ?auth.authorities))]
+#[tracing::instrument(skip(req, pool, news_updater, auth), fields(permissions = ?auth.authorities))]
 #[expect(clippy::too_many_lines)]
 pub async fn by_id(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
     news_updater: web::Data<NewsUpdater>,
     query: web::Query<ByIdQuery>,
@@ -46,6 +47,12 @@ pub async fn by_id(
         Some(Exclusion::NonCanon) => (None, Some(false)),
     };
 
+    // Read before the comic itself, so a concurrent write can only make the
+    // tag older than the data, never newer.
+    let version = DatabaseComic::version_by_id(&mut *conn, comic_id.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
     let include_hidden = auth.has_authority(token_permissions::HAS_VALID_TOKEN);
     let comic = DatabaseComic::by_id_with_navigation_and_news(
         &mut *conn,
@@ -60,6 +67,11 @@ pub async fn by_id(
     if comic.is_some() {
         news_updater.check_for(comic_id);
     }
+    // Hidden comics look missing to anonymous callers; don't reveal their
+    // version either. Note that a `304 Not Modified` skips the news check
+    // above, but any edit (or news update) anywhere ends that, so the news
+    // still gets checked regularly for comics people read.
+    mark_cacheable(&req, comic.as_ref().and(version));
 
     let editor_data = if include_hidden {
         fetch_editor_data_for_comic(&pool, comic_id).await?
@@ -132,34 +144,7 @@ pub async fn by_id(
             comic: comic_id,
             editor_data,
             all_items: all_navigation_items,
-            data: ComicData::Present(PresentComic {
-                has_data: True::default(),
-                image_type: Some(ImageType::from_trusted(comic.image_type)),
-                publish_date: comic.publish_date.map(|nd| Utc.from_utc_datetime(&nd)),
-                is_accurate_publish_date: comic.is_accurate_publish_date != 0,
-                title: comic.title,
-                tagline: comic.tagline,
-                is_guest_comic: comic.is_guest_comic != 0,
-                is_non_canon: comic.is_non_canon != 0,
-                has_no_cast: comic.has_no_cast != 0,
-                has_no_location: comic.has_no_location != 0,
-                has_no_storyline: comic.has_no_storyline != 0,
-                has_no_title: comic.has_no_title != 0,
-                has_no_tagline: comic.has_no_tagline != 0,
-                news: comic.news,
-                previous: comic
-                    .prev_id
-                    .map(TryInto::try_into)
-                    .transpose()
-                    .expect("database has valid comicIds"),
-                next: comic
-                    .next_id
-                    .map(TryInto::try_into)
-                    .transpose()
-                    .expect("database has valid comicIds"),
-                items: comic_navigation_items,
-                active_storylines,
-            }),
+            data: present_comic_data(comic, comic_navigation_items, active_storylines),
         }
     } else {
         Comic {
@@ -175,6 +160,41 @@ pub async fn by_id(
     Ok(Json(comic))
 }
 
+pub(super) fn present_comic_data(
+    comic: ComicWithNavigationAndNews,
+    items: Vec<ItemNavigationData>,
+    active_storylines: Vec<ActiveStorylineSummary>,
+) -> ComicData {
+    ComicData::Present(PresentComic {
+        has_data: True::default(),
+        image_type: Some(ImageType::from_trusted(comic.image_type)),
+        publish_date: comic.publish_date.map(|nd| Utc.from_utc_datetime(&nd)),
+        is_accurate_publish_date: comic.is_accurate_publish_date != 0,
+        title: comic.title,
+        tagline: comic.tagline,
+        is_guest_comic: comic.is_guest_comic != 0,
+        is_non_canon: comic.is_non_canon != 0,
+        has_no_cast: comic.has_no_cast != 0,
+        has_no_location: comic.has_no_location != 0,
+        has_no_storyline: comic.has_no_storyline != 0,
+        has_no_title: comic.has_no_title != 0,
+        has_no_tagline: comic.has_no_tagline != 0,
+        news: comic.news,
+        previous: comic
+            .prev_id
+            .map(TryInto::try_into)
+            .transpose()
+            .expect("database has valid comicIds"),
+        next: comic
+            .next_id
+            .map(TryInto::try_into)
+            .transpose()
+            .expect("database has valid comicIds"),
+        items,
+        active_storylines,
+    })
+}
+
 #[derive(Debug, Deserialize, TS)]
 #[ts(export)]
 pub struct ByIdQuery {
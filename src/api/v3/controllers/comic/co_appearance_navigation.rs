@@ -0,0 +1,160 @@
+use crate::api::v3::models::{Exclusion, ItemSetNavigationData, NavigationData};
+use crate::models::{ComicId, ItemId};
+use crate::util::ensure_is_valid;
+use actix_web::web::Json;
+use actix_web::{Result, error, web};
+use actix_web_lab::extract::Query;
+use api_macros::api_endpoint;
+use database::DbPool;
+use database::models::Item as DatabaseItem;
+use parse_display::Display;
+use semval::Validate;
+use semval::context::Context as ValidationContext;
+use serde::Deserialize;
+use std::convert::TryInto;
+use tracing::{Instrument, info_span};
+use ts_rs::TS;
+
+const MAX_ITEM_COUNT: usize = 16;
+
+/// Like the per-item navigation data in `comicdata/{comicId}`, but for the
+/// comics in which all of the given items appear together.
+#[api_endpoint(method = "GET", path = "comicdata/{comicId}/navigation")]
+#[tracing::instrument(skip(pool))]
+pub async fn co_appearance_navigation(
+    pool: web::Data<DbPool>,
+    comic_id: web::Path<ComicId>,
+    query: Query<CoAppearanceNavigationQuery>,
+) -> Result<Json<ItemSetNavigationData>> {
+    let comic_id = comic_id.into_inner();
+    ensure_is_valid(&*query).map_err(error::ErrorBadRequest)?;
+
+    let (include_guest_comics, include_non_canon_comics) = match query.exclude {
+        None => (None, None),
+        Some(Exclusion::Guest) => (Some(false), None),
+        Some(Exclusion::NonCanon) => (None, Some(false)),
+    };
+
+    let mut item_ids = query.item_ids.clone();
+    item_ids.sort_unstable();
+    item_ids.dedup();
+    let database_item_ids: Vec<u16> = item_ids.iter().copied().map(ItemId::into_inner).collect();
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let navigation = DatabaseItem::co_appearance_navigation_by_ids(
+        &mut *conn,
+        &database_item_ids,
+        comic_id.into_inner(),
+        include_guest_comics,
+        include_non_canon_comics,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    Ok(Json(ItemSetNavigationData {
+        item_ids,
+        navigation_data: NavigationData {
+            first: navigation
+                .first
+                .map(TryInto::try_into)
+                .transpose()
+                .expect("database has valid comicIds"),
+            previous: navigation
+                .previous
+                .map(TryInto::try_into)
+                .transpose()
+                .expect("database has valid comicIds"),
+            next: navigation
+                .next
+                .map(TryInto::try_into)
+                .transpose()
+                .expect("database has valid comicIds"),
+            last: navigation
+                .last
+                .map(TryInto::try_into)
+                .transpose()
+                .expect("database has valid comicIds"),
+        },
+        count: i32::try_from(navigation.count).unwrap_or(i32::MAX),
+    }))
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CoAppearanceNavigationQuery {
+    #[serde(default, rename = "item-id")]
+    #[ts(rename = "item-id")]
+    item_ids: Vec<ItemId>,
+    #[ts(optional)]
+    exclude: Option<Exclusion>,
+}
+
+impl Validate for CoAppearanceNavigationQuery {
+    type Invalidity = CoAppearanceNavigationQueryInvalidity;
+    fn validate(&self) -> semval::ValidationResult<Self::Invalidity> {
+        ValidationContext::new()
+            .invalidate_if(
+                self.item_ids.is_empty(),
+                CoAppearanceNavigationQueryInvalidity::NoItems,
+            )
+            .invalidate_if(
+                self.item_ids.len() > MAX_ITEM_COUNT,
+                CoAppearanceNavigationQueryInvalidity::TooManyItems,
+            )
+            .into()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Display, Eq, PartialEq)]
+pub enum CoAppearanceNavigationQueryInvalidity {
+    #[display("at least one item-id must be given")]
+    NoItems,
+    #[display("at most 16 item-ids can be given")]
+    TooManyItems,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalidities(
+        item_ids: impl IntoIterator<Item = u16>,
+    ) -> Vec<CoAppearanceNavigationQueryInvalidity> {
+        let query = CoAppearanceNavigationQuery {
+            item_ids: item_ids.into_iter().map(ItemId::from).collect(),
+            exclude: None,
+        };
+        query
+            .validate()
+            .err()
+            .map(|context| context.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn an_empty_item_list_is_rejected() {
+        assert_eq!(
+            invalidities([]),
+            [CoAppearanceNavigationQueryInvalidity::NoItems]
+        );
+    }
+
+    #[test]
+    fn more_than_16_items_are_rejected() {
+        assert_eq!(
+            invalidities(1..=17),
+            [CoAppearanceNavigationQueryInvalidity::TooManyItems]
+        );
+    }
+
+    #[test]
+    fn up_to_16_items_are_accepted() {
+        assert!(invalidities([1]).is_empty());
+        assert!(invalidities(1..=16).is_empty());
+    }
+}
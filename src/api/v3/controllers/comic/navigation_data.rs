@@ -205,6 +205,63 @@ pub async fn fetch_comic_item_navigation_data(
         .collect())
 }
 
+/// Per-comic item navigation data for every comic in `from..=to`, computed
+/// in a single query instead of three queries per comic. Items are ordered by
+/// count, as in [`fetch_comic_item_navigation_data`].
+#[tracing::instrument(skip(conn))]
+pub async fn fetch_item_navigation_data_for_range(
+    conn: &mut DbPoolConnection,
+    from: ComicId,
+    to: ComicId,
+    include_guest_comics: Option<bool>,
+    include_non_canon_comics: Option<bool>,
+) -> Result<BTreeMap<ComicId, Vec<UnhydratedItemNavigationData>>> {
+    let navigation = DatabaseItem::navigation_of_items_in_comic_range(
+        &mut **conn,
+        from.into_inner(),
+        to.into_inner(),
+        include_guest_comics,
+        include_non_canon_comics,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    // Rows arrive ordered by comic, then by count, so each list is sorted.
+    let mut by_comic: BTreeMap<ComicId, Vec<UnhydratedItemNavigationData>> = BTreeMap::new();
+    for row in navigation {
+        by_comic
+            .entry(ComicId::from_trusted(row.comic_id))
+            .or_default()
+            .push(UnhydratedItemNavigationData {
+                id: row.item_id.into(),
+                navigation_data: NavigationData {
+                    first: row
+                        .first
+                        .map(TryInto::try_into)
+                        .transpose()
+                        .expect("database has valid comicIds"),
+                    previous: row
+                        .previous
+                        .map(TryInto::try_into)
+                        .transpose()
+                        .expect("database has valid comicIds"),
+                    next: row
+                        .next
+                        .map(TryInto::try_into)
+                        .transpose()
+                        .expect("database has valid comicIds"),
+                    last: row
+                        .last
+                        .map(TryInto::try_into)
+                        .transpose()
+                        .expect("database has valid comicIds"),
+                },
+            });
+    }
+
+    Ok(by_comic)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
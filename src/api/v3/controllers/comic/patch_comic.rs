@@ -1,7 +1,12 @@
+use crate::api::v3::models::SyncComic;
 use crate::models::{ComicId, Token};
-use crate::util::{andify_comma_string, ensure_is_authorized};
+use crate::util::{
+    andify_comma_string, ensure_is_authorized, if_match_satisfied, precondition_failed,
+    set_response_etag, version_etag,
+};
+use actix_web::http::header::IfMatch;
 use actix_web::web::Json;
-use actix_web::{Result, error, web};
+use actix_web::{HttpRequest, Result, error, web};
 use actix_web_grants::authorities::AuthDetails;
 use api_macros::api_endpoint;
 use chrono::{DateTime, TimeZone, Utc};
@@ -13,12 +18,14 @@ use tracing::{Instrument, info_span};
 use ts_rs::TS;
 
 #[api_endpoint(method = "PATCH", path = "comicdata/{comicId}")]
-#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+#[tracing::instrument(skip(req, pool, auth), fields(permissions = ?auth.authorities))]
 #[expect(clippy::too_many_lines)]
 pub async fn patch_comic(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
     request: web::Json<PatchComicBody>,
     comic_id: web::Path<ComicId>,
+    if_match: Option<web::Header<IfMatch>>,
     token: web::ReqData<Token>,
     auth: AuthDetails,
 ) -> Result<Json<String>> {
@@ -33,9 +40,23 @@ pub async fn patch_comic(
         .map_err(error::ErrorInternalServerError)?;
 
     let comic_id = comic_id.into_inner();
-    DatabaseComic::ensure_exists_by_id(&mut *transaction, comic_id.into_inner())
+    // Lock the row before anything else reads it, so that the state read
+    // below is the one the version belongs to.
+    let version = DatabaseComic::version_by_id_for_update(&mut *transaction, comic_id.into_inner())
         .await
-        .map_err(error::ErrorInternalServerError)?;
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound(format!("No comic with id {comic_id} exists")))?;
+    if !if_match_satisfied(if_match.as_deref(), version) {
+        let current = DatabaseComic::by_id(&mut *transaction, comic_id.into_inner())
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .expect("The comic is locked, so it still exists");
+        return Err(precondition_failed(
+            format!("Comic {comic_id} has been changed since it was fetched"),
+            version,
+            &SyncComic::from(current),
+        ));
+    }
 
     let PatchComicBody {
         publish_date,
@@ -172,12 +193,19 @@ pub async fn patch_comic(
         updated.push("hasNoTagline flag");
     }
 
+    let new_version = DatabaseComic::version_by_id(&mut *transaction, comic_id.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .expect("The comic is locked, so it still exists");
+
     transaction
         .commit()
         .instrument(info_span!("Transaction::commit"))
         .await
         .map_err(error::ErrorInternalServerError)?;
 
+    set_response_etag(&req, version_etag(new_version));
+
     let mut changed = updated.join(", ");
     andify_comma_string(&mut changed);
 
@@ -250,7 +278,7 @@ async fn update_title(
     let old_title = DatabaseComic::title_by_id(&mut **transaction, comic_id.into_inner())
         .await
         .map_err(error::ErrorInternalServerError)?
-        .expect("Due to ensure_exists_by_id call, this should never be None");
+        .expect("The comic is locked, so it still exists");
     DatabaseComic::update_title_by_id(&mut **transaction, comic_id.into_inner(), &title)
         .await
         .map_err(error::ErrorInternalServerError)?;
@@ -0,0 +1,97 @@
+use crate::api::v3::models::{ComicList, ComicQueryExpression, ComicQueryResponse};
+use crate::util::ensure_is_valid;
+use actix_web::web::Json;
+use actix_web::{Result, error, web};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
+use api_macros::api_endpoint;
+use database::DbPool;
+use database::models::{Comic as DatabaseComic, ComicPredicate};
+use parse_display::Display;
+use semval::Validate;
+use semval::context::Context as ValidationContext;
+use serde::Deserialize;
+use shared::token_permissions;
+use tracing::{Instrument, info_span};
+use ts_rs::TS;
+
+const PAGE_SIZE: u16 = 100;
+const MAX_QUERY_DEPTH: usize = 16;
+const MAX_QUERY_NODES: usize = 64;
+
+/// Looks up comics matching a boolean expression over their items and flags.
+/// Hidden comics are only considered for callers with a valid token.
+#[api_endpoint(method = "POST", path = "comicdata/query")]
+#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+pub async fn query(
+    pool: web::Data<DbPool>,
+    request: web::Json<ComicQueryBody>,
+    auth: AuthDetails,
+) -> Result<Json<ComicQueryResponse>> {
+    let request = request.into_inner();
+    ensure_is_valid(&request).map_err(error::ErrorBadRequest)?;
+
+    let include_hidden = auth.has_authority(token_permissions::HAS_VALID_TOKEN);
+    let page = request.page.unwrap_or(1).max(1);
+    let predicate = ComicPredicate::from(request.query);
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let comic_count = DatabaseComic::count_matching(&mut *conn, &predicate, include_hidden)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let comics = DatabaseComic::matching_with_mapping(
+        &mut *conn,
+        &predicate,
+        include_hidden,
+        page,
+        PAGE_SIZE,
+        ComicList::from,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    Ok(Json(ComicQueryResponse {
+        comics,
+        page,
+        page_count: u16::try_from((comic_count + i64::from(PAGE_SIZE) - 1) / i64::from(PAGE_SIZE))
+            .unwrap_or(u16::MAX),
+        comic_count: i32::try_from(comic_count).unwrap_or(i32::MAX),
+    }))
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct ComicQueryBody {
+    query: ComicQueryExpression,
+    #[ts(optional)]
+    page: Option<u16>,
+}
+
+impl Validate for ComicQueryBody {
+    type Invalidity = ComicQueryBodyInvalidity;
+    fn validate(&self) -> semval::ValidationResult<Self::Invalidity> {
+        ValidationContext::new()
+            .invalidate_if(
+                self.query.depth() > MAX_QUERY_DEPTH,
+                ComicQueryBodyInvalidity::TooDeep,
+            )
+            .invalidate_if(
+                self.query.node_count() > MAX_QUERY_NODES,
+                ComicQueryBodyInvalidity::TooLarge,
+            )
+            .into()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Display, Eq, PartialEq)]
+pub enum ComicQueryBodyInvalidity {
+    #[display("query cannot be nested more than 16 levels deep")]
+    TooDeep,
+    #[display("query cannot contain more than 64 terms")]
+    TooLarge,
+}
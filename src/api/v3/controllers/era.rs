@@ -0,0 +1,316 @@
+use crate::api::v3::models::Era;
+use crate::models::{ComicId, Token};
+use crate::util::{ensure_is_authorized, mark_cacheable};
+use actix_web::web::Json;
+use actix_web::{HttpRequest, Result, error, web};
+use actix_web_grants::authorities::AuthDetails;
+use anyhow::anyhow;
+use api_macros::api_endpoint;
+use database::models::{Era as DatabaseEra, LogEntry};
+use database::{DbPool, DbTransaction};
+use serde::Deserialize;
+use shared::token_permissions;
+use tracing::{Instrument, info_span};
+use ts_rs::TS;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(all)
+        .service(create)
+        .service(patch_era)
+        .service(delete);
+}
+
+/// Every era, in story order. The position of an era in this list is the
+/// period reported by the stats endpoints when grouping by era.
+#[api_endpoint(method = "GET", path = "eradata/")]
+#[tracing::instrument(skip(req, pool))]
+async fn all(req: HttpRequest, pool: web::Data<DbPool>) -> Result<Json<Vec<Era>>> {
+    let eras = DatabaseEra::all(&***pool)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .into_iter()
+        .map(Era::from)
+        .collect();
+
+    mark_cacheable(&req, None);
+    Ok(Json(eras))
+}
+
+#[api_endpoint(method = "POST", path = "eradata/")]
+#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+async fn create(
+    pool: web::Data<DbPool>,
+    request: web::Json<CreateEraBody>,
+    token: web::ReqData<Token>,
+    auth: AuthDetails,
+) -> Result<Json<Era>> {
+    ensure_is_authorized(&auth, token_permissions::CAN_CHANGE_COMIC_DATA)
+        .map_err(error::ErrorForbidden)?;
+
+    let token = *token;
+    let CreateEraBody {
+        name,
+        start_comic_id,
+        last_comic_id,
+    } = request.into_inner();
+    let name = name.trim();
+    let (start_comic_id, last_comic_id) = (start_comic_id.into_inner(), last_comic_id.into_inner());
+
+    let mut transaction = pool
+        .begin()
+        .instrument(info_span!("Pool::begin"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    ensure_is_valid_era(&mut transaction, None, name, start_comic_id, last_comic_id).await?;
+
+    let result = DatabaseEra::create(&mut *transaction, name, start_comic_id, last_comic_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let era_id = u16::try_from(result.last_insert_id()).expect("new era ID fits in u16");
+
+    LogEntry::log_action(
+        &mut *transaction,
+        token.to_string(),
+        format!("Created era #{era_id} (\"{name}\", comics #{start_comic_id} to #{last_comic_id})"),
+        None,
+        None,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    transaction
+        .commit()
+        .instrument(info_span!("Transaction::commit"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(Json(Era {
+        id: era_id,
+        name: name.to_owned(),
+        start_comic_id: ComicId::from_trusted(start_comic_id),
+        last_comic_id: ComicId::from_trusted(last_comic_id),
+    }))
+}
+
+#[api_endpoint(method = "PATCH", path = "eradata/{eraId}")]
+#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+async fn patch_era(
+    pool: web::Data<DbPool>,
+    request: web::Json<PatchEraBody>,
+    era_id: web::Path<u16>,
+    token: web::ReqData<Token>,
+    auth: AuthDetails,
+) -> Result<Json<Era>> {
+    ensure_is_authorized(&auth, token_permissions::CAN_CHANGE_COMIC_DATA)
+        .map_err(error::ErrorForbidden)?;
+
+    let token = *token;
+    let era_id = era_id.into_inner();
+
+    let mut transaction = pool
+        .begin()
+        .instrument(info_span!("Pool::begin"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let old_era = DatabaseEra::by_id(&mut *transaction, era_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound(anyhow!("No era with id {era_id} exists")))?;
+
+    let PatchEraBody {
+        name,
+        start_comic_id,
+        last_comic_id,
+    } = request.into_inner();
+    let name = name.as_deref().map_or(&*old_era.name, str::trim);
+    let start_comic_id = start_comic_id.map_or(old_era.start_comic_id, ComicId::into_inner);
+    let last_comic_id = last_comic_id.map_or(old_era.last_comic_id, ComicId::into_inner);
+
+    ensure_is_valid_era(
+        &mut transaction,
+        Some(era_id),
+        name,
+        start_comic_id,
+        last_comic_id,
+    )
+    .await?;
+
+    DatabaseEra::update_by_id(
+        &mut *transaction,
+        era_id,
+        name,
+        start_comic_id,
+        last_comic_id,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    LogEntry::log_action(
+        &mut *transaction,
+        token.to_string(),
+        format!(
+            "Changed era #{era_id} from \"{}\" (comics #{} to #{}) to \"{name}\" (comics #{start_comic_id} to #{last_comic_id})",
+            old_era.name, old_era.start_comic_id, old_era.last_comic_id
+        ),
+        None,
+        None,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    transaction
+        .commit()
+        .instrument(info_span!("Transaction::commit"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(Json(Era {
+        id: era_id,
+        name: name.to_owned(),
+        start_comic_id: ComicId::from_trusted(start_comic_id),
+        last_comic_id: ComicId::from_trusted(last_comic_id),
+    }))
+}
+
+#[api_endpoint(method = "DELETE", path = "eradata/{eraId}")]
+#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+async fn delete(
+    pool: web::Data<DbPool>,
+    era_id: web::Path<u16>,
+    token: web::ReqData<Token>,
+    auth: AuthDetails,
+) -> Result<Json<String>> {
+    ensure_is_authorized(&auth, token_permissions::CAN_CHANGE_COMIC_DATA)
+        .map_err(error::ErrorForbidden)?;
+
+    let token = *token;
+    let era_id = era_id.into_inner();
+
+    let mut transaction = pool
+        .begin()
+        .instrument(info_span!("Pool::begin"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let era = DatabaseEra::by_id(&mut *transaction, era_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound(anyhow!("No era with id {era_id} exists")))?;
+
+    DatabaseEra::delete_by_id(&mut *transaction, era_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    LogEntry::log_action(
+        &mut *transaction,
+        token.to_string(),
+        format!("Deleted era #{era_id} (\"{}\")", era.name),
+        None,
+        None,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    transaction
+        .commit()
+        .instrument(info_span!("Transaction::commit"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(Json(format!("Deleted era #{era_id}")))
+}
+
+/// Eras need a name and a non-empty comic range that no other era shares a
+/// comic with, so that every comic belongs to at most one era.
+async fn ensure_is_valid_era(
+    transaction: &mut DbTransaction<'_>,
+    era_id: Option<u16>,
+    name: &str,
+    start_comic_id: u16,
+    last_comic_id: u16,
+) -> Result<()> {
+    check_era_fields(name, start_comic_id, last_comic_id).map_err(error::ErrorBadRequest)?;
+
+    if let Some(other) =
+        DatabaseEra::first_overlapping(&mut **transaction, start_comic_id, last_comic_id, era_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+    {
+        return Err(error::ErrorConflict(anyhow!(
+            "Comics #{start_comic_id} to #{last_comic_id} overlap era #{} (\"{}\", comics #{} to #{})",
+            other.id,
+            other.name,
+            other.start_comic_id,
+            other.last_comic_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// The checks of [`ensure_is_valid_era`] that don't need the other eras.
+/// A single comic is a valid range, as both ends are part of the era.
+fn check_era_fields(name: &str, start_comic_id: u16, last_comic_id: u16) -> anyhow::Result<()> {
+    if name.is_empty() {
+        return Err(anyhow!("An era needs a name"));
+    }
+    if last_comic_id < start_comic_id {
+        return Err(anyhow!(
+            "lastComicId ({last_comic_id}) cannot be before startComicId ({start_comic_id})"
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CreateEraBody {
+    pub name: String,
+    pub start_comic_id: ComicId,
+    pub last_comic_id: ComicId,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PatchEraBody {
+    #[ts(optional)]
+    pub name: Option<String>,
+    #[ts(optional)]
+    pub start_comic_id: Option<ComicId>,
+    #[ts(optional)]
+    pub last_comic_id: Option<ComicId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eras_need_a_name() {
+        assert_eq!(
+            check_era_fields("", 1, 10).unwrap_err().to_string(),
+            "An era needs a name"
+        );
+    }
+
+    #[test]
+    fn eras_cannot_end_before_they_start() {
+        assert_eq!(
+            check_era_fields("Faye's arrival", 10, 9)
+                .unwrap_err()
+                .to_string(),
+            "lastComicId (9) cannot be before startComicId (10)"
+        );
+    }
+
+    #[test]
+    fn eras_can_span_a_single_comic() {
+        assert!(check_era_fields("Faye's arrival", 10, 10).is_ok());
+        assert!(check_era_fields("Faye's arrival", 10, 11).is_ok());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::util::{
+    ExportArchive, ExportArchiveCache, cache_dataset_responses, cache_stats_responses,
+    mark_cacheable,
+};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::middleware::from_fn;
+use actix_web::{HttpRequest, HttpResponse, Result, error, web};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
+use database::DbPool;
+use database::dump::{Dump, ExportOptions};
+use database::models::ChangeLogEntry;
+use shared::token_permissions;
+use tracing::{Instrument, info, info_span};
+
+mod graph;
+
+/// The dump is tagged with the data version, like the dataset endpoints; the
+/// graph is built from the stats caches, so it is tagged like the stats.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("")
+            .wrap(from_fn(cache_dataset_responses))
+            .route(web::get().to(export)),
+    )
+    .service(
+        web::resource("graph")
+            .wrap(from_fn(cache_stats_responses))
+            .route(web::get().to(graph::graph)),
+    );
+}
+
+/// Serves a dump of the whole dataset (see [`database::dump`]) as a
+/// `.tar.gz` download. Hidden comics are only included for callers with a
+/// valid token. The archive is assembled in memory, which is fine for the
+/// size of the dataset; the public one is built once per data version and
+/// kept in the [`ExportArchiveCache`].
+#[tracing::instrument(skip(req, pool, cache, auth), fields(permissions = ?auth.authorities))]
+async fn export(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    cache: web::Data<ExportArchiveCache>,
+    auth: AuthDetails,
+) -> Result<HttpResponse> {
+    let include_hidden = auth.has_authority(token_permissions::HAS_VALID_TOKEN);
+
+    let data_version = ChangeLogEntry::latest_seq(&***pool)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let archive = if include_hidden {
+        build_archive(&pool, data_version, ExportOptions { include_hidden }).await?
+    } else {
+        mark_cacheable(&req, None);
+        cache
+            .get_or_build(data_version, || {
+                build_archive(&pool, data_version, ExportOptions::default())
+            })
+            .await?
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(archive.file_name)],
+        })
+        .body(archive.data))
+}
+
+async fn build_archive(
+    pool: &DbPool,
+    data_version: Option<u64>,
+    options: ExportOptions,
+) -> Result<ExportArchive> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let dump = Dump::load(&mut conn, options)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    drop(conn);
+
+    info!("Exporting dump: {:?}", dump.manifest.counts);
+
+    let file_name = format!(
+        "qcext-dump-{}.tar.gz",
+        dump.manifest.exported_at.format("%Y%m%d%H%M%S")
+    );
+    let data = web::block(move || {
+        let mut archive = Vec::new();
+        dump.write_to(&mut archive).map(|()| archive)
+    })
+    .await
+    .map_err(error::ErrorInternalServerError)?
+    .map_err(error::ErrorInternalServerError)?;
+
+    Ok(ExportArchive {
+        data_version,
+        file_name,
+        data: data.into(),
+    })
+}
@@ -0,0 +1,446 @@
+use crate::api::v3::models::{ItemColor, ItemType};
+use crate::util::StatsResponseCache;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{HttpResponse, Result, error, web};
+use actix_web_lab::extract::Query;
+use chrono::Utc;
+use database::DbPool;
+use database::models::stats::{GraphEdgeRow, GraphNodeRow, StatsFilter};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+use std::sync::Arc;
+use tracing::{Instrument, info, info_span};
+
+/// Serves the weighted co-appearance graph of the chosen item types (cast
+/// and locations by default) as a download for tools like Gephi. Two items
+/// are connected when they appear together, weighted by the number of comics
+/// they share. Unlike the co-appearance stats, nothing is capped.
+///
+/// The graph is built from the same data as the stats, so it is kept in the
+/// [`StatsResponseCache`] like a filtered stats response, and tagged with the
+/// data version of that cache.
+#[tracing::instrument(skip(pool, cache))]
+pub async fn graph(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    query: Query<GraphExportQuery>,
+) -> Result<HttpResponse> {
+    let GraphExportQuery {
+        format,
+        r#type,
+        from_comic,
+        to_comic,
+        min_weight,
+    } = query.into_inner();
+    let types = if r#type.is_empty() {
+        vec![ItemType::Cast, ItemType::Location]
+    } else {
+        r#type
+    };
+    let types = types
+        .into_iter()
+        .map(ItemType::as_str)
+        .collect::<Vec<_>>()
+        .join(",");
+    let filter = StatsFilter {
+        from_comic,
+        to_comic,
+        ..StatsFilter::default()
+    };
+    let min_weight = min_weight.unwrap_or(1);
+
+    let graph = cache
+        .get_or_compute_transient(
+            &format!("export/graph?type={types}&min-weight={min_weight} {filter:?}"),
+            load_graph(pool.get_ref().clone(), filter, types, min_weight),
+        )
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let Graph { nodes, edges } = &*graph;
+
+    info!(
+        "Exporting co-appearance graph with {} nodes and {} edges",
+        nodes.len(),
+        edges.len()
+    );
+
+    let body = match format {
+        GraphFormat::Graphml => write_graphml(nodes, edges),
+        GraphFormat::Gexf => write_gexf(nodes, edges),
+        GraphFormat::Json => serde_json::to_string(&NodeLinkGraph::new(nodes, edges))
+            .map_err(error::ErrorInternalServerError)?,
+    };
+
+    let file_name = format!(
+        "qcext-co-appearances-{}.{}",
+        Utc::now().format("%Y%m%d%H%M%S"),
+        format.extension()
+    );
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file_name)],
+        })
+        .body(body))
+}
+
+#[derive(Debug)]
+struct Graph {
+    nodes: Vec<GraphNodeRow>,
+    edges: Vec<GraphEdgeRow>,
+}
+
+async fn load_graph(
+    pool: DbPool,
+    filter: StatsFilter,
+    types: String,
+    min_weight: u32,
+) -> sqlx::Result<Arc<Graph>> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await?;
+
+    let nodes = GraphNodeRow::all(&mut *conn, &filter, &types).await?;
+    let edges = GraphEdgeRow::all(&mut *conn, &filter, &types, min_weight).await?;
+
+    Ok(Arc::new(Graph { nodes, edges }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GraphExportQuery {
+    #[serde(default)]
+    format: GraphFormat,
+    /// Repeatable; cast and locations when absent.
+    #[serde(default)]
+    r#type: Vec<ItemType>,
+    from_comic: Option<u16>,
+    to_comic: Option<u16>,
+    /// The fewest shared comics for two items to be connected; 1 when absent.
+    min_weight: Option<u32>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum GraphFormat {
+    #[default]
+    Graphml,
+    Gexf,
+    /// A node-link document, as read by e.g. `networkx` and D3.
+    Json,
+}
+
+impl GraphFormat {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Graphml => "graphml",
+            Self::Gexf => "gexf",
+            Self::Json => "json",
+        }
+    }
+
+    const fn content_type(self) -> &'static str {
+        match self {
+            Self::Graphml => "application/graphml+xml",
+            Self::Gexf => "application/gexf+xml",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct NodeLinkGraph<'a> {
+    directed: bool,
+    multigraph: bool,
+    nodes: Vec<NodeLinkNode<'a>>,
+    links: Vec<NodeLinkEdge>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeLinkNode<'a> {
+    id: u16,
+    short_name: &'a str,
+    name: &'a str,
+    r#type: &'a str,
+    color: ItemColor,
+    appearances: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct NodeLinkEdge {
+    source: u16,
+    target: u16,
+    weight: i64,
+}
+
+impl<'a> NodeLinkGraph<'a> {
+    fn new(nodes: &'a [GraphNodeRow], edges: &[GraphEdgeRow]) -> Self {
+        Self {
+            directed: false,
+            multigraph: false,
+            nodes: nodes
+                .iter()
+                .map(|n| NodeLinkNode {
+                    id: n.id,
+                    short_name: &n.short_name,
+                    name: &n.name,
+                    r#type: &n.r#type,
+                    color: node_color(n),
+                    appearances: n.appearances,
+                })
+                .collect(),
+            links: edges
+                .iter()
+                .map(|e| NodeLinkEdge {
+                    source: e.source,
+                    target: e.target,
+                    weight: e.weight,
+                })
+                .collect(),
+        }
+    }
+}
+
+const fn node_color(node: &GraphNodeRow) -> ItemColor {
+    ItemColor(node.color_red, node.color_green, node.color_blue)
+}
+
+fn write_graphml(nodes: &[GraphNodeRow], edges: &[GraphEdgeRow]) -> String {
+    let mut out = String::new();
+    try_write_graphml(&mut out, nodes, edges).expect("writing to a String cannot fail");
+    out
+}
+
+fn try_write_graphml(
+    out: &mut String,
+    nodes: &[GraphNodeRow],
+    edges: &[GraphEdgeRow],
+) -> fmt::Result {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for (id, kind) in [
+        ("short_name", "string"),
+        ("name", "string"),
+        ("type", "string"),
+        ("color", "string"),
+        ("appearances", "int"),
+    ] {
+        writeln!(
+            out,
+            r#"  <key id="{id}" for="node" attr.name="{id}" attr.type="{kind}"/>"#
+        )?;
+    }
+    writeln!(
+        out,
+        r#"  <key id="weight" for="edge" attr.name="weight" attr.type="int"/>"#
+    )?;
+    writeln!(
+        out,
+        r#"  <graph id="co-appearances" edgedefault="undirected">"#
+    )?;
+    for node in nodes {
+        writeln!(out, r#"    <node id="{}">"#, node.id)?;
+        writeln!(
+            out,
+            r#"      <data key="short_name">{}</data>"#,
+            escape_xml(&node.short_name)
+        )?;
+        writeln!(
+            out,
+            r#"      <data key="name">{}</data>"#,
+            escape_xml(&node.name)
+        )?;
+        writeln!(
+            out,
+            r#"      <data key="type">{}</data>"#,
+            escape_xml(&node.r#type)
+        )?;
+        writeln!(
+            out,
+            r#"      <data key="color">{:#}</data>"#,
+            node_color(node)
+        )?;
+        writeln!(
+            out,
+            r#"      <data key="appearances">{}</data>"#,
+            node.appearances
+        )?;
+        writeln!(out, "    </node>")?;
+    }
+    for (i, edge) in edges.iter().enumerate() {
+        writeln!(
+            out,
+            r#"    <edge id="e{i}" source="{}" target="{}"><data key="weight">{}</data></edge>"#,
+            edge.source, edge.target, edge.weight
+        )?;
+    }
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+fn write_gexf(nodes: &[GraphNodeRow], edges: &[GraphEdgeRow]) -> String {
+    let mut out = String::new();
+    try_write_gexf(&mut out, nodes, edges).expect("writing to a String cannot fail");
+    out
+}
+
+fn try_write_gexf(out: &mut String, nodes: &[GraphNodeRow], edges: &[GraphEdgeRow]) -> fmt::Result {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<gexf xmlns="http://gexf.net/1.3" xmlns:viz="http://gexf.net/1.3/viz" version="1.3">"#
+    )?;
+    writeln!(out, r#"  <graph defaultedgetype="undirected">"#)?;
+    writeln!(out, r#"    <attributes class="node">"#)?;
+    writeln!(
+        out,
+        r#"      <attribute id="short_name" title="short_name" type="string"/>"#
+    )?;
+    writeln!(
+        out,
+        r#"      <attribute id="type" title="type" type="string"/>"#
+    )?;
+    writeln!(
+        out,
+        r#"      <attribute id="appearances" title="appearances" type="integer"/>"#
+    )?;
+    writeln!(out, "    </attributes>")?;
+    writeln!(out, "    <nodes>")?;
+    for node in nodes {
+        writeln!(
+            out,
+            r#"      <node id="{}" label="{}">"#,
+            node.id,
+            escape_xml(&node.name)
+        )?;
+        writeln!(out, "        <attvalues>")?;
+        writeln!(
+            out,
+            r#"          <attvalue for="short_name" value="{}"/>"#,
+            escape_xml(&node.short_name)
+        )?;
+        writeln!(
+            out,
+            r#"          <attvalue for="type" value="{}"/>"#,
+            escape_xml(&node.r#type)
+        )?;
+        writeln!(
+            out,
+            r#"          <attvalue for="appearances" value="{}"/>"#,
+            node.appearances
+        )?;
+        writeln!(out, "        </attvalues>")?;
+        writeln!(
+            out,
+            r#"        <viz:color r="{}" g="{}" b="{}"/>"#,
+            node.color_red, node.color_green, node.color_blue
+        )?;
+        writeln!(out, "      </node>")?;
+    }
+    writeln!(out, "    </nodes>")?;
+    writeln!(out, "    <edges>")?;
+    for (i, edge) in edges.iter().enumerate() {
+        writeln!(
+            out,
+            r#"      <edge id="{i}" source="{}" target="{}" weight="{}"/>"#,
+            edge.source, edge.target, edge.weight
+        )?;
+    }
+    writeln!(out, "    </edges>")?;
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</gexf>")
+}
+
+/// Escapes `s` for use in XML text and attribute values.
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> (Vec<GraphNodeRow>, Vec<GraphEdgeRow>) {
+        let node = |id, name: &str| GraphNodeRow {
+            id,
+            short_name: name.to_lowercase(),
+            name: name.to_string(),
+            r#type: "cast".to_string(),
+            color_red: 255,
+            color_green: 0,
+            color_blue: 16,
+            appearances: 10,
+        };
+        (
+            vec![node(1, "Faye"), node(2, "Dora & \"Sven\"")],
+            vec![GraphEdgeRow {
+                source: 1,
+                target: 2,
+                weight: 7,
+            }],
+        )
+    }
+
+    #[test]
+    fn escapes_xml_special_characters() {
+        assert_eq!(
+            escape_xml(r#"<Dora & "Sven's">"#),
+            "&lt;Dora &amp; &quot;Sven&apos;s&quot;&gt;"
+        );
+    }
+
+    #[test]
+    fn graphml_has_escaped_nodes_and_weighted_edges() {
+        let (nodes, edges) = sample();
+
+        let graphml = write_graphml(&nodes, &edges);
+
+        assert!(graphml.contains(r#"<data key="name">Dora &amp; &quot;Sven&quot;</data>"#));
+        assert!(graphml.contains(r#"<data key="color">#ff0010</data>"#));
+        assert!(
+            graphml.contains(
+                r#"<edge id="e0" source="1" target="2"><data key="weight">7</data></edge>"#
+            )
+        );
+        assert!(graphml.trim_end().ends_with("</graphml>"));
+    }
+
+    #[test]
+    fn gexf_has_colors_and_weighted_edges() {
+        let (nodes, edges) = sample();
+
+        let gexf = write_gexf(&nodes, &edges);
+
+        assert!(gexf.contains(r#"<node id="2" label="Dora &amp; &quot;Sven&quot;">"#));
+        assert!(gexf.contains(r#"<viz:color r="255" g="0" b="16"/>"#));
+        assert!(gexf.contains(r#"<edge id="0" source="1" target="2" weight="7"/>"#));
+    }
+
+    #[test]
+    fn node_link_document_links_item_ids() {
+        let (nodes, edges) = sample();
+
+        let json = serde_json::to_value(NodeLinkGraph::new(&nodes, &edges)).unwrap();
+
+        assert_eq!(json["nodes"][0]["color"], "ff0010");
+        assert_eq!(json["links"][0]["source"], 1);
+        assert_eq!(json["links"][0]["weight"], 7);
+    }
+}
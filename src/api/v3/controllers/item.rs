@@ -5,6 +5,7 @@ mod by_id;
 mod image;
 mod image_upload;
 mod patch_item;
+mod timeline;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(all::all)
@@ -16,6 +17,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(by_id::random_comic)
         .service(by_id::friends)
         .service(by_id::locations)
+        .service(timeline::timeline)
         .service(image::images)
         .service(web::resource("{itemId}/images").route(web::post().to(image_upload::image_upload)))
         .service(image::set_primary);
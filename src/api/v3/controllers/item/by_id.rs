@@ -1,7 +1,8 @@
 use crate::api::v3::models::{ComicList, Exclusion, Item, ItemColor, ItemType, RelatedItem};
 use crate::models::ItemId;
+use crate::util::mark_cacheable;
 use actix_web::web::Json;
-use actix_web::{Result, error, web};
+use actix_web::{HttpRequest, Result, error, web};
 use anyhow::anyhow;
 use api_macros::api_endpoint;
 use database::DbPool;
@@ -14,8 +15,12 @@ use tracing::{Instrument, info_span};
 use ts_rs::TS;
 
 #[api_endpoint(method = "GET", path = "itemdata/{itemId}")]
-#[tracing::instrument(skip(pool))]
-pub async fn by_id(pool: web::Data<DbPool>, item_id: web::Path<ItemId>) -> Result<Json<Item>> {
+#[tracing::instrument(skip(req, pool))]
+pub async fn by_id(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    item_id: web::Path<ItemId>,
+) -> Result<Json<Item>> {
     let item_id = item_id.into_inner();
 
     let mut conn = pool
@@ -24,10 +29,17 @@ pub async fn by_id(pool: web::Data<DbPool>, item_id: web::Path<ItemId>) -> Resul
         .await
         .map_err(error::ErrorInternalServerError)?;
 
+    // Read before the item itself, so a concurrent write can only make the
+    // tag older than the data, never newer.
+    let version = DatabaseItem::version_by_id(&mut *conn, item_id.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
     let row = DatabaseItem::by_id_with_stats(&mut *conn, item_id.into_inner())
         .await
         .map_err(error::ErrorInternalServerError)?
         .ok_or_else(|| error::ErrorNotFound(anyhow!("No item with id {item_id} exists")))?;
+    mark_cacheable(&req, version);
 
     let item = build_item_response(item_id, row).map_err(error::ErrorInternalServerError)?;
 
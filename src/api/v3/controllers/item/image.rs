@@ -1,8 +1,9 @@
 use crate::api::v3::models::ItemImageList;
 use crate::models::{ImageId, Token};
-use crate::util::ensure_is_authorized;
+use crate::util::{ensure_is_authorized, is_not_modified};
+use actix_web::http::header::{self, ETag, EntityTag};
 use actix_web::web::Json;
-use actix_web::{HttpResponse, Result, error, web};
+use actix_web::{HttpRequest, HttpResponse, Result, error, web};
 use actix_web_grants::authorities::AuthDetails;
 use anyhow::anyhow;
 use api_macros::api_endpoint;
@@ -39,8 +40,31 @@ pub async fn images(
     Ok(Json(item_image_list))
 }
 
-#[tracing::instrument(skip(pool))]
-pub async fn image(pool: web::Data<DbPool>, image_id: web::Path<u32>) -> Result<HttpResponse> {
+/// Image data only changes if an image is deleted and its id reused, which a
+/// day-long cache lifetime papers over well enough.
+const IMAGE_CACHE_CONTROL: &str = "public, max-age=86400";
+
+#[tracing::instrument(skip(req, pool))]
+pub async fn image(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    image_id: web::Path<u32>,
+) -> Result<HttpResponse> {
+    let record = DatabaseItem::image_records_by_image_ids(&***pool, &[*image_id])
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .pop()
+        .ok_or_else(|| {
+            error::ErrorNotFound(anyhow!("No item image with id {} exists", *image_id))
+        })?;
+    let etag = EntityTag::new_strong(format!("i{:08x}", record.crc32c_hash));
+    if is_not_modified(&req, &etag) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header((header::CACHE_CONTROL, IMAGE_CACHE_CONTROL))
+            .finish());
+    }
+
     let image = DatabaseItem::image_by_image_id(&***pool, *image_id)
         .await
         .map_err(error::ErrorInternalServerError)?
@@ -49,7 +73,11 @@ pub async fn image(pool: web::Data<DbPool>, image_id: web::Path<u32>) -> Result<
         })?;
 
     let content_type = detect_mime_type(&image);
-    Ok(HttpResponse::Ok().content_type(content_type).body(image))
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(ETag(etag))
+        .insert_header((header::CACHE_CONTROL, IMAGE_CACHE_CONTROL))
+        .body(image))
 }
 
 #[api_endpoint(method = "DELETE", path = "itemdata/image/{imageId}")]
@@ -1,8 +1,13 @@
+use crate::api::v3::controllers::sync::sync_item_from_db;
 use crate::api::v3::models::{ItemColor, ItemType};
 use crate::models::{ComicId, ItemId, Token};
-use crate::util::{andify_comma_string, ensure_is_authorized};
+use crate::util::{
+    andify_comma_string, ensure_is_authorized, if_match_satisfied, precondition_failed,
+    set_response_etag, version_etag,
+};
+use actix_web::http::header::IfMatch;
 use actix_web::web::Json;
-use actix_web::{Result, error, web};
+use actix_web::{HttpRequest, Result, error, web};
 use actix_web_grants::authorities::AuthDetails;
 use anyhow::anyhow;
 use api_macros::api_endpoint;
@@ -14,12 +19,14 @@ use tracing::{Instrument, info_span};
 use ts_rs::TS;
 
 #[api_endpoint(method = "PATCH", path = "itemdata/{itemId}")]
-#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+#[tracing::instrument(skip(req, pool, auth), fields(permissions = ?auth.authorities))]
 #[expect(clippy::too_many_lines)]
 pub async fn patch_item(
+    req: HttpRequest,
     pool: web::Data<DbPool>,
     request: web::Json<PatchItemBody>,
     item_id: web::Path<ItemId>,
+    if_match: Option<web::Header<IfMatch>>,
     token: web::ReqData<Token>,
     auth: AuthDetails,
 ) -> Result<Json<String>> {
@@ -35,11 +42,23 @@ pub async fn patch_item(
         .await
         .map_err(error::ErrorInternalServerError)?;
 
+    let version = DatabaseItem::version_by_id_for_update(&mut *transaction, item_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound(anyhow!("No item with id {item_id} exists")))?;
     let old_item = DatabaseItem::by_id(&mut *transaction, item_id)
         .await
         .map_err(error::ErrorInternalServerError)?
         .ok_or_else(|| error::ErrorNotFound(anyhow!("No item with id {item_id} exists")))?;
 
+    if !if_match_satisfied(if_match.as_deref(), version) {
+        return Err(precondition_failed(
+            format!("Item {item_id} has been changed since it was fetched"),
+            version,
+            &sync_item_from_db(old_item)?,
+        ));
+    }
+
     let PatchItemBody {
         name,
         short_name,
@@ -234,12 +253,19 @@ pub async fn patch_item(
         updated.push("end comic");
     }
 
+    let new_version = DatabaseItem::version_by_id(&mut *transaction, item_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound(anyhow!("No item with id {item_id} exists")))?;
+
     transaction
         .commit()
         .instrument(info_span!("Transaction::commit"))
         .await
         .map_err(error::ErrorInternalServerError)?;
 
+    set_response_etag(&req, version_etag(new_version));
+
     let mut changed = updated.join(", ");
     andify_comma_string(&mut changed);
 
@@ -0,0 +1,291 @@
+use crate::api::v3::models::{AppearanceRun, ItemTimeline, TimelineBucket, TimelineBucketing};
+use crate::models::{ComicId, ItemId};
+use crate::util::mark_cacheable;
+use actix_web::web::Json;
+use actix_web::{HttpRequest, Result, error, web};
+use anyhow::anyhow;
+use api_macros::api_endpoint;
+use database::DbPool;
+use database::models::{Item as DatabaseItem, ItemTimelineComic};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tracing::{Instrument, info_span};
+use ts_rs::TS;
+
+/// Comics per bucket when bucketing by comics without a `size`.
+const DEFAULT_WINDOW_SIZE: u16 = 100;
+
+/// How many streaks and absences are returned.
+const LONGEST_RUNS: usize = 5;
+
+/// The appearances of an item over time. Storylines only cover their
+/// lifecycle, from `startComicId` up to but excluding `endComicId`; cast and
+/// locations cover every comic.
+#[api_endpoint(method = "GET", path = "itemdata/{itemId}/timeline")]
+#[tracing::instrument(skip(req, pool))]
+pub async fn timeline(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    item_id: web::Path<ItemId>,
+    query: web::Query<ItemTimelineQuery>,
+) -> Result<Json<ItemTimeline>> {
+    let item_id = item_id.into_inner();
+    let bucketing = query.bucket.unwrap_or_default();
+    let size = query.size.unwrap_or(DEFAULT_WINDOW_SIZE);
+    if size == 0 {
+        return Err(error::ErrorBadRequest("size must be at least 1"));
+    }
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let item = DatabaseItem::by_id(&mut *conn, item_id.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound(anyhow!("No item with id {item_id} exists")))?;
+
+    let comics = DatabaseItem::timeline_by_id(
+        &mut *conn,
+        item_id.into_inner(),
+        item.start_comic_id,
+        item.end_comic_id,
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+    mark_cacheable(&req, None);
+
+    Ok(Json(build_timeline(item_id, &comics, bucketing, size)))
+}
+
+fn build_timeline(
+    item_id: ItemId,
+    comics: &[ItemTimelineComic],
+    bucketing: TimelineBucketing,
+    size: u16,
+) -> ItemTimeline {
+    let buckets = match bucketing {
+        TimelineBucketing::Year => bucket_by_date(comics, "%Y"),
+        TimelineBucketing::Month => bucket_by_date(comics, "%Y-%m"),
+        TimelineBucketing::Comics => comics
+            .chunks(usize::from(size))
+            .map(|window| {
+                let first = window[0].comic_id;
+                let last = window[window.len() - 1].comic_id;
+                let mut bucket = new_bucket(format!("{first}-{last}"), first);
+                for comic in window {
+                    add_to_bucket(&mut bucket, comic);
+                }
+                bucket
+            })
+            .collect(),
+    };
+
+    ItemTimeline {
+        id: item_id,
+        from_comic_id: comics.first().map(|c| ComicId::from_trusted(c.comic_id)),
+        to_comic_id: comics.last().map(|c| ComicId::from_trusted(c.comic_id)),
+        buckets,
+        streaks: longest_runs(comics, true),
+        absences: longest_runs(comics, false),
+    }
+}
+
+/// Buckets `comics` by their publish date formatted with `format`. Comics
+/// without a publish date are left out.
+fn bucket_by_date(comics: &[ItemTimelineComic], format: &str) -> Vec<TimelineBucket> {
+    let mut buckets: BTreeMap<String, TimelineBucket> = BTreeMap::new();
+    for comic in comics {
+        let Some(publish_date) = comic.publish_date else {
+            continue;
+        };
+        let label = publish_date.format(format).to_string();
+        let bucket = buckets
+            .entry(label.clone())
+            .or_insert_with(|| new_bucket(label, comic.comic_id));
+        add_to_bucket(bucket, comic);
+    }
+
+    buckets.into_values().collect()
+}
+
+fn new_bucket(label: String, first_comic_id: u16) -> TimelineBucket {
+    TimelineBucket {
+        label,
+        first_comic_id: ComicId::from_trusted(first_comic_id),
+        last_comic_id: ComicId::from_trusted(first_comic_id),
+        comics: 0,
+        appearances: 0,
+    }
+}
+
+fn add_to_bucket(bucket: &mut TimelineBucket, comic: &ItemTimelineComic) {
+    let comic_id = ComicId::from_trusted(comic.comic_id);
+    bucket.first_comic_id = bucket.first_comic_id.min(comic_id);
+    bucket.last_comic_id = bucket.last_comic_id.max(comic_id);
+    bucket.comics += 1;
+    if comic.appears != 0 {
+        bucket.appearances += 1;
+    }
+}
+
+/// The longest runs of consecutive comics in which the item does (or, for
+/// `appearing == false`, does not) appear, longest and then earliest first.
+/// Runs without the item before its first appearance are not absences.
+fn longest_runs(comics: &[ItemTimelineComic], appearing: bool) -> Vec<AppearanceRun> {
+    let last_comic_id = comics.last().map(|c| c.comic_id);
+
+    let mut runs: Vec<AppearanceRun> = comics
+        .chunk_by(|a, b| a.appears == b.appears)
+        .skip_while(|run| run[0].appears == 0)
+        .filter(|run| (run[0].appears != 0) == appearing)
+        .map(|run| {
+            let last = run[run.len() - 1].comic_id;
+            AppearanceRun {
+                first_comic_id: ComicId::from_trusted(run[0].comic_id),
+                last_comic_id: ComicId::from_trusted(last),
+                length: i32::try_from(run.len()).expect("known to be much smaller than i32::MAX"),
+                ongoing: Some(last) == last_comic_id,
+            }
+        })
+        .collect();
+
+    runs.sort_by(|a, b| {
+        b.length
+            .cmp(&a.length)
+            .then(a.first_comic_id.cmp(&b.first_comic_id))
+    });
+    runs.truncate(LONGEST_RUNS);
+    runs
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct ItemTimelineQuery {
+    #[ts(optional)]
+    bucket: Option<TimelineBucketing>,
+    /// Comics per bucket when bucketing by comics; defaults to 100.
+    #[ts(optional, type = "string")]
+    size: Option<u16>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn comic(comic_id: u16, date: (i32, u32, u32), appears: bool) -> ItemTimelineComic {
+        ItemTimelineComic {
+            comic_id,
+            publish_date: NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+                .and_then(|d| d.and_hms_opt(12, 0, 0)),
+            appears: i64::from(appears),
+        }
+    }
+
+    fn run(first: u16, last: u16, length: i32, ongoing: bool) -> AppearanceRun {
+        AppearanceRun {
+            first_comic_id: ComicId::from_trusted(first),
+            last_comic_id: ComicId::from_trusted(last),
+            length,
+            ongoing,
+        }
+    }
+
+    fn sample_comics() -> Vec<ItemTimelineComic> {
+        vec![
+            comic(1, (2003, 12, 30), false),
+            comic(2, (2003, 12, 31), true),
+            comic(3, (2004, 1, 1), true),
+            comic(4, (2004, 1, 2), false),
+            comic(5, (2004, 2, 1), false),
+            comic(6, (2004, 2, 2), false),
+            comic(7, (2004, 2, 3), true),
+            comic(8, (2004, 2, 4), false),
+        ]
+    }
+
+    #[test]
+    fn buckets_by_year_and_month() {
+        let comics = sample_comics();
+
+        let years = build_timeline(ItemId::from(1), &comics, TimelineBucketing::Year, 1).buckets;
+        let years: Vec<_> = years
+            .iter()
+            .map(|b| (b.label.as_str(), b.comics, b.appearances))
+            .collect();
+        assert_eq!(years, [("2003", 2, 1), ("2004", 6, 2)]);
+
+        let months = build_timeline(ItemId::from(1), &comics, TimelineBucketing::Month, 1).buckets;
+        let months: Vec<_> = months
+            .iter()
+            .map(|b| (b.label.as_str(), b.comics, b.appearances))
+            .collect();
+        assert_eq!(
+            months,
+            [("2003-12", 2, 1), ("2004-01", 2, 1), ("2004-02", 4, 1)]
+        );
+    }
+
+    #[test]
+    fn buckets_by_comic_windows() {
+        let buckets = build_timeline(
+            ItemId::from(1),
+            &sample_comics(),
+            TimelineBucketing::Comics,
+            3,
+        )
+        .buckets;
+
+        let buckets: Vec<_> = buckets
+            .iter()
+            .map(|b| {
+                (
+                    b.label.as_str(),
+                    b.first_comic_id.into_inner(),
+                    b.last_comic_id.into_inner(),
+                    b.appearances,
+                )
+            })
+            .collect();
+        assert_eq!(
+            buckets,
+            [("1-3", 1, 3, 2), ("4-6", 4, 6, 0), ("7-8", 7, 8, 1)]
+        );
+    }
+
+    #[test]
+    fn comics_without_publish_date_are_left_out_of_date_buckets() {
+        let comics = [comic(1, (2004, 2, 30), true), comic(2, (2004, 3, 1), true)];
+
+        let buckets = build_timeline(ItemId::from(1), &comics, TimelineBucketing::Year, 1).buckets;
+
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].comics, 1);
+    }
+
+    #[test]
+    fn absences_start_after_the_first_appearance() {
+        let timeline = build_timeline(
+            ItemId::from(1),
+            &sample_comics(),
+            TimelineBucketing::Year,
+            1,
+        );
+
+        assert_eq!(timeline.streaks, [run(2, 3, 2, false), run(7, 7, 1, false)]);
+        assert_eq!(timeline.absences, [run(4, 6, 3, false), run(8, 8, 1, true)]);
+    }
+
+    #[test]
+    fn empty_timeline_has_no_bounds() {
+        let timeline = build_timeline(ItemId::from(1), &[], TimelineBucketing::Comics, 10);
+
+        assert_eq!(timeline.from_comic_id, None);
+        assert!(timeline.buckets.is_empty());
+        assert!(timeline.streaks.is_empty());
+        assert!(timeline.absences.is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::api::v3::models::{
+    ComicList, Exclusion, SearchField, SearchResponse, SearchResult, SearchSnippet,
+};
+use crate::models::{ComicId, ItemId};
+use actix_web::web::Json;
+use actix_web::{Result, error, web};
+use actix_web_lab::extract::Query;
+use api_macros::api_endpoint;
+use database::DbPool;
+use database::models::{ComicSearchFilter, ComicSearchHit as DatabaseComicSearchHit};
+use scraper::Html;
+use serde::Deserialize;
+use tracing::{Instrument, info, info_span};
+use ts_rs::TS;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
+
+const PAGE_SIZE: u16 = 20;
+const MAX_QUERY_LENGTH: usize = 200;
+/// How many characters of context to keep on each side of the first match
+/// when cutting a snippet out of a (long) news post.
+const SNIPPET_CONTEXT: usize = 80;
+
+#[api_endpoint(method = "GET", path = "search/")]
+#[tracing::instrument(skip(pool))]
+async fn search(
+    pool: web::Data<DbPool>,
+    query: Query<SearchQuery>,
+) -> Result<Json<SearchResponse>> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(error::ErrorBadRequest("q parameter must not be empty"));
+    }
+    if q.chars().count() > MAX_QUERY_LENGTH {
+        return Err(error::ErrorBadRequest(format!(
+            "q parameter must be at most {MAX_QUERY_LENGTH} characters long"
+        )));
+    }
+
+    let (is_guest_comic, is_non_canon) = match query.exclude {
+        None => (None, None),
+        Some(Exclusion::Guest) => (Some(false), None),
+        Some(Exclusion::NonCanon) => (None, Some(false)),
+    };
+
+    let mut item_ids: Vec<u16> = query
+        .item_ids
+        .iter()
+        .copied()
+        .map(ItemId::into_inner)
+        .collect();
+    item_ids.sort_unstable();
+    item_ids.dedup();
+
+    let filter = ComicSearchFilter {
+        item_ids,
+        is_guest_comic,
+        is_non_canon,
+    };
+    let page = query.page.unwrap_or(1).max(1);
+
+    info!("Searching comics for {q:?} (page {page})");
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let result_count = DatabaseComicSearchHit::count(&mut *conn, q, &filter)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let hits = DatabaseComicSearchHit::search(&mut *conn, q, &filter, page, PAGE_SIZE)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let terms = search_terms(q);
+    let results = hits
+        .into_iter()
+        .map(|hit| search_result_from_db(hit, &terms))
+        .collect();
+
+    Ok(Json(SearchResponse {
+        results,
+        page,
+        page_count: u16::try_from((result_count + i64::from(PAGE_SIZE) - 1) / i64::from(PAGE_SIZE))
+            .unwrap_or(u16::MAX),
+        result_count: i32::try_from(result_count).unwrap_or(i32::MAX),
+    }))
+}
+
+fn search_result_from_db(hit: DatabaseComicSearchHit, terms: &[String]) -> SearchResult {
+    let mut snippets = Vec::new();
+    snippets.extend(build_snippet(SearchField::Title, &hit.title, terms, false));
+    if let Some(tagline) = &hit.tagline {
+        snippets.extend(build_snippet(SearchField::Tagline, tagline, terms, false));
+    }
+    if let Some(news) = &hit.news {
+        let news_text = news_to_plain_text(news);
+        snippets.extend(build_snippet(SearchField::News, &news_text, terms, true));
+    }
+
+    SearchResult {
+        comic: ComicList {
+            comic: ComicId::from_trusted(hit.id),
+            title: hit.title,
+            tagline: hit.tagline,
+            is_guest_comic: hit.is_guest_comic != 0,
+            is_non_canon: hit.is_non_canon != 0,
+        },
+        relevance: hit.relevance,
+        snippets,
+    }
+}
+
+/// Splits a search query into the lowercase words MySQL's full-text parser
+/// would match on.
+fn search_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    terms.sort_unstable();
+    terms.dedup();
+    terms
+}
+
+/// News is stored as the inner HTML of the news post; snippets are built from
+/// its text content with whitespace collapsed.
+fn news_to_plain_text(news: &str) -> String {
+    let fragment = Html::parse_fragment(news);
+    let text: String = fragment.root_element().text().collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Finds whole-word, case-insensitive matches of `terms` in `text`. Returns a
+/// snippet only if at least one term matched. When `truncate` is set, the
+/// snippet is cut down to [`SNIPPET_CONTEXT`] characters around the first
+/// match.
+fn build_snippet(
+    field: SearchField,
+    text: &str,
+    terms: &[String],
+    truncate: bool,
+) -> Option<SearchSnippet> {
+    let matches: Vec<(usize, usize)> = words(text)
+        .filter(|&(start, end)| terms.contains(&text[start..end].to_lowercase()))
+        .collect();
+    let &(first_start, first_end) = matches.first()?;
+
+    let (window_start, window_end) = if truncate {
+        (
+            floor_word_boundary(text, back_chars(text, first_start, SNIPPET_CONTEXT))
+                .min(first_start),
+            ceil_word_boundary(text, forward_chars(text, first_end, SNIPPET_CONTEXT))
+                .max(first_end),
+        )
+    } else {
+        (0, text.len())
+    };
+
+    let mut snippet = String::new();
+    if window_start > 0 {
+        snippet.push('…');
+    }
+    let prefix_len = utf16_len(&snippet);
+    snippet.push_str(text[window_start..window_end].trim());
+    if window_end < text.len() {
+        snippet.push('…');
+    }
+
+    let leading_whitespace =
+        text[window_start..window_end].len() - text[window_start..window_end].trim_start().len();
+    let offset_of = |byte_index: usize| {
+        prefix_len + utf16_len(&text[window_start + leading_whitespace..byte_index])
+    };
+    let highlights = matches
+        .into_iter()
+        .filter(|&(start, end)| start >= window_start && end <= window_end)
+        .map(|(start, end)| [offset_of(start), offset_of(end)])
+        .collect();
+
+    Some(SearchSnippet {
+        field,
+        text: snippet,
+        highlights,
+    })
+}
+
+/// Byte ranges of the maximal alphanumeric runs in `text`.
+fn words(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = chars.find(|(_, c)| c.is_alphanumeric())?;
+        let mut end = text.len();
+        while let Some(&(index, c)) = chars.peek() {
+            if !c.is_alphanumeric() {
+                end = index;
+                break;
+            }
+            chars.next();
+        }
+        Some((start, end))
+    })
+}
+
+fn back_chars(text: &str, from: usize, count: usize) -> usize {
+    text[..from]
+        .char_indices()
+        .rev()
+        .nth(count.saturating_sub(1))
+        .map_or(0, |(index, _)| index)
+}
+
+fn forward_chars(text: &str, from: usize, count: usize) -> usize {
+    text[from..]
+        .char_indices()
+        .nth(count)
+        .map_or(text.len(), |(index, _)| from + index)
+}
+
+/// Moves `index` forward past the word it falls in the middle of, so snippets
+/// never begin with a partial word.
+fn floor_word_boundary(text: &str, index: usize) -> usize {
+    if index == 0 {
+        return 0;
+    }
+    text[index..]
+        .char_indices()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(index, |(offset, _)| index + offset)
+}
+
+/// Moves `index` back before the word it falls in the middle of, so snippets
+/// never end with a partial word.
+fn ceil_word_boundary(text: &str, index: usize) -> usize {
+    if index == text.len() {
+        return index;
+    }
+    text[..index]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(index, |(offset, _)| offset)
+}
+
+fn utf16_len(text: &str) -> u32 {
+    u32::try_from(text.encode_utf16().count()).unwrap_or(u32::MAX)
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+struct SearchQuery {
+    q: String,
+    #[ts(optional)]
+    page: Option<u16>,
+    #[ts(optional)]
+    exclude: Option<Exclusion>,
+    #[serde(default, rename = "item-id")]
+    #[ts(optional = nullable, rename = "item-id")]
+    item_ids: Vec<ItemId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_terms_are_lowercased_and_deduplicated() {
+        assert_eq!(
+            search_terms("Coffee of DOOM, coffee!"),
+            vec!["coffee".to_owned(), "doom".to_owned(), "of".to_owned()]
+        );
+    }
+
+    #[test]
+    fn snippet_highlights_whole_words_only() {
+        let snippet = build_snippet(
+            SearchField::Title,
+            "Hannelore and the hand",
+            &search_terms("hand"),
+            false,
+        )
+        .expect("a match");
+        assert_eq!(snippet.text, "Hannelore and the hand");
+        assert_eq!(snippet.highlights, vec![[18, 22]]);
+    }
+
+    #[test]
+    fn snippet_without_matches_is_omitted() {
+        assert!(
+            build_snippet(
+                SearchField::Title,
+                "Pintsize",
+                &search_terms("marten"),
+                false
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn truncated_snippet_offsets_account_for_ellipsis() {
+        let text = format!(
+            "{} Marten arrives {}",
+            "lorem ".repeat(40),
+            "ipsum ".repeat(40)
+        );
+        let snippet = build_snippet(SearchField::News, &text, &search_terms("marten"), true)
+            .expect("a match");
+
+        assert!(snippet.text.starts_with('…'));
+        assert!(snippet.text.ends_with('…'));
+        let [start, end] = snippet.highlights[0];
+        let utf16: Vec<u16> = snippet.text.encode_utf16().collect();
+        assert_eq!(
+            String::from_utf16(&utf16[start as usize..end as usize]).unwrap(),
+            "Marten"
+        );
+    }
+
+    #[test]
+    fn news_html_is_flattened() {
+        assert_eq!(
+            news_to_plain_text("<p>Hello <b>there</b></p>\n<p>&amp; welcome</p>"),
+            "Hello there & welcome"
+        );
+    }
+}
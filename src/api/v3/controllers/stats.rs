@@ -1,23 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 
 use crate::api::v3::models::ItemColor;
 use crate::api::v3::models::stats::{
     AvgCastPerYear, BestFriendPair, BestFriendResponse, BreakoutYear, CastTurnoverYear,
     CharacterHomeTurfEntry, CharacterMeta, CharacterRegularity, CharacterSeasonEntry,
     CoAppearanceCharacterMeta, CoAppearancePair, CoAppearancesResponse, ComebackCharacter,
-    ComebackLocation, CrowdedComicsResponse, DailyComics, DebutCharacter, DebutYear, DebutsPerYear,
-    EnsembleRatio, HomeTurfLocation, ItemStats, LocationAffinity, LocationAffinityCharacter,
-    LocationBreakoutYear, LocationCoOccurrenceEntry, LocationCoOccurrencePair,
-    LocationCoOccurrenceResponse, LocationRegularity, LocationSeasonEntry, LocationSocialHubEntry,
-    LocationSpotlightResponse, LocationSpotlightYear, LocationTurnoverYear, LonerEntry,
-    MilestoneComic, MonthlyComics, MonthlyHeatmapEntry, MostCrowdedComic, NeverMetPair,
-    PairEvolutionYear, PublicationCalendar, PublicationGap, PublicationStreak, PublishTimeYear,
-    ScheduleEvolutionYear, SocialHubEntry, TopRankedOverTimeResponse, TrendingItem, YearlyOverview,
-    YearlyRankEntry, YearlySpotlightResponse, YearlySpotlightYear,
+    ComebackLocation, ComparedItem, ComparisonGap, ComparisonResponse, ComparisonYear,
+    CrowdedComicsResponse, DailyComics, DebutCharacter, DebutYear, DebutsPerYear, EnsembleRatio,
+    HomeTurfLocation, ItemStats, LocationAffinity, LocationAffinityCharacter, LocationBreakoutYear,
+    LocationCoOccurrenceEntry, LocationCoOccurrencePair, LocationCoOccurrenceResponse,
+    LocationRegularity, LocationSeasonEntry, LocationSocialHubEntry, LocationSpotlightResponse,
+    LocationSpotlightYear, LocationTurnoverYear, LonerEntry, MeetingLocation, MilestoneComic,
+    MonthlyComics, MonthlyHeatmapEntry, MostCrowdedComic, NeverMetPair, PairEvolutionYear,
+    PublicationCalendar, PublicationGap, PublicationStreak, PublishTimeYear, ScheduleEvolutionYear,
+    SocialHubEntry, StatsFilter, StatsGroupingQuery, StorylineCast, StorylineCastMember,
+    StorylineConcurrency, StorylineDormantGap, StorylineStats, TopRankedOverTimeResponse,
+    TrendingItem, YearlyOverview, YearlyRankEntry, YearlySpotlightResponse, YearlySpotlightYear,
 };
 use crate::models::{ComicId, ItemId};
+use crate::util::StatsResponseCache;
 use actix_web::web::Json;
 use actix_web::{Result, error, web};
+use actix_web_lab::extract::Query;
+use anyhow::anyhow;
 use api_macros::api_endpoint;
 use database::DbPool;
 use database::models::stats::{
@@ -34,17 +40,21 @@ use database::models::stats::{
     LocationRegularityRow as DbLocationRegularityRow, LocationSeasonRow as DbLocationSeasonRow,
     LocationSocialHubRow as DbLocationSocialHubRow, LocationTurnoverRow as DbLocationTurnoverRow,
     LocationYearlyAppearanceRow as DbLocationYearlyAppearanceRow, LonerIndexRow as DbLonerIndexRow,
-    MilestoneComicRow as DbMilestoneComicRow, MonthlyHeatmapRow as DbMonthlyHeatmapRow,
-    NeverMetRow as DbNeverMetRow, PairEvolutionRow as DbPairEvolutionRow,
+    MeetingLocationRow as DbMeetingLocationRow, MilestoneComicRow as DbMilestoneComicRow,
+    MonthlyHeatmapRow as DbMonthlyHeatmapRow, NeverMetRow as DbNeverMetRow,
+    PairComicRow as DbPairComicRow, PairEvolutionRow as DbPairEvolutionRow,
     PublicationDowRow as DbPublicationDowRow, PublicationGapRow as DbPublicationGapRow,
     PublicationMonthRow as DbPublicationMonthRow, PublishTimeRow as DbPublishTimeRow,
     PublishedDateRow as DbPublishedDateRow, ScheduleEvolutionRow as DbScheduleEvolutionRow,
-    SocialHubRow as DbSocialHubRow, TopRankedStintRow as DbTopRankedStintRow,
-    TrendingItemRow as DbTrendingItemRow, YearlyAppearanceRow as DbYearlyAppearanceRow,
-    YearlyOverviewRow as DbYearlyOverviewRow,
+    SocialHubRow as DbSocialHubRow, StatsFilter as DbStatsFilter, StatsGrouping as DbStatsGrouping,
+    StatsResponseCacheMeta, StorylineCastRow as DbStorylineCastRow,
+    StorylineFeatureRow as DbStorylineFeatureRow, StorylineLifecycleRow as DbStorylineLifecycleRow,
+    TopRankedStintRow as DbTopRankedStintRow, TrendingItemRow as DbTrendingItemRow,
+    YearlyAppearanceRow as DbYearlyAppearanceRow, YearlyOverviewRow as DbYearlyOverviewRow,
 };
+use database::models::{ChangeLogEntry, Item as DbItem, ItemType as DbItemType};
 use serde::Deserialize;
-use tracing::{Instrument, info_span};
+use tracing::{Instrument, error, info_span};
 use ts_rs::TS;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -74,6 +84,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(breakout_years)
         .service(character_home_turf)
         .service(pair_evolution)
+        .service(compare)
         .service(loner_index)
         .service(never_met)
         .service(schedule_evolution)
@@ -86,93 +97,174 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .service(location_breakout_years)
         .service(location_social_hub)
         .service(location_turnover)
-        .service(top_ranked_over_time);
+        .service(top_ranked_over_time)
+        .service(top_ranked_locations_over_time)
+        .service(top_ranked_storylines_over_time)
+        .service(storylines)
+        .service(storyline_concurrency)
+        .service(storyline_dormant_gaps)
+        .service(storyline_cast);
 }
 
 #[api_endpoint(method = "GET", path = "stats/cast")]
+#[tracing::instrument(skip(pool, cache))]
+async fn cast(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<ItemStats>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "cast",
+        filter.into_inner().into(),
+        cast_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn cast(pool: web::Data<DbPool>) -> Result<Json<Vec<ItemStats>>> {
+async fn cast_response(pool: DbPool, filter: DbStatsFilter) -> sqlx::Result<Vec<ItemStats>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbItemStats::cast(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbItemStats::cast(&mut *conn, &filter).await?;
 
-    Ok(Json(
-        rows.into_iter()
-            .filter_map(item_stats_from_db)
-            .collect::<Vec<_>>(),
-    ))
+    Ok(rows
+        .into_iter()
+        .filter_map(item_stats_from_db)
+        .collect::<Vec<_>>())
 }
 
 #[api_endpoint(method = "GET", path = "stats/locations")]
+#[tracing::instrument(skip(pool, cache))]
+async fn locations(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<ItemStats>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "locations",
+        filter.into_inner().into(),
+        locations_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn locations(pool: web::Data<DbPool>) -> Result<Json<Vec<ItemStats>>> {
+async fn locations_response(pool: DbPool, filter: DbStatsFilter) -> sqlx::Result<Vec<ItemStats>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbItemStats::locations(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbItemStats::locations(&mut *conn, &filter).await?;
 
-    Ok(Json(
-        rows.into_iter()
-            .filter_map(item_stats_from_db)
-            .collect::<Vec<_>>(),
-    ))
+    Ok(rows
+        .into_iter()
+        .filter_map(item_stats_from_db)
+        .collect::<Vec<_>>())
 }
 
 #[api_endpoint(method = "GET", path = "stats/co-appearances")]
+#[tracing::instrument(skip(pool, cache))]
+async fn co_appearances(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<CoAppearancesResponse>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "co-appearances",
+        filter.into_inner().into(),
+        co_appearances_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn co_appearances(pool: web::Data<DbPool>) -> Result<Json<CoAppearancesResponse>> {
+async fn co_appearances_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<CoAppearancesResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbCoAppearance::top(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbCoAppearance::top(&mut *conn, &filter).await?;
 
-    Ok(Json(build_co_appearances_response(rows)))
+    Ok(build_co_appearances_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/yearly-spotlight")]
+#[tracing::instrument(skip(pool, cache))]
+async fn yearly_spotlight(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+    grouping: web::Query<StatsGroupingQuery>,
+) -> Result<Json<YearlySpotlightResponse>> {
+    let grouping: DbStatsGrouping = grouping.grouping.unwrap_or_default().into();
+    respond_filtered(
+        &cache,
+        &pool,
+        &grouped_key("yearly-spotlight", grouping),
+        filter.into_inner().into(),
+        move |pool, filter| yearly_spotlight_response(pool, filter, grouping),
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn yearly_spotlight(pool: web::Data<DbPool>) -> Result<Json<YearlySpotlightResponse>> {
+async fn yearly_spotlight_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    grouping: DbStatsGrouping,
+) -> sqlx::Result<YearlySpotlightResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbYearlyAppearanceRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbYearlyAppearanceRow::all(&mut *conn, &filter, grouping).await?;
 
-    Ok(Json(build_yearly_spotlight_response(rows)))
+    Ok(build_yearly_spotlight_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/debuts-per-year")]
+#[tracing::instrument(skip(pool, cache))]
+async fn debuts_per_year(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<DebutsPerYear>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "debuts-per-year",
+        filter.into_inner().into(),
+        debuts_per_year_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn debuts_per_year(pool: web::Data<DbPool>) -> Result<Json<Vec<DebutsPerYear>>> {
+async fn debuts_per_year_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<DebutsPerYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbDebutsPerYearRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbDebutsPerYearRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<DebutsPerYear> = rows
         .into_iter()
@@ -185,21 +277,37 @@ async fn debuts_per_year(pool: web::Data<DbPool>) -> Result<Json<Vec<DebutsPerYe
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/yearly-overview")]
+#[tracing::instrument(skip(pool, cache))]
+async fn yearly_overview(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<YearlyOverview>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "yearly-overview",
+        filter.into_inner().into(),
+        yearly_overview_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn yearly_overview(pool: web::Data<DbPool>) -> Result<Json<Vec<YearlyOverview>>> {
+async fn yearly_overview_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<YearlyOverview>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbYearlyOverviewRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbYearlyOverviewRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<YearlyOverview> = rows
         .into_iter()
@@ -212,25 +320,39 @@ async fn yearly_overview(pool: web::Data<DbPool>) -> Result<Json<Vec<YearlyOverv
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/publication-calendar")]
+#[tracing::instrument(skip(pool, cache))]
+async fn publication_calendar(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<PublicationCalendar>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "publication-calendar",
+        filter.into_inner().into(),
+        publication_calendar_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn publication_calendar(pool: web::Data<DbPool>) -> Result<Json<PublicationCalendar>> {
+async fn publication_calendar_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<PublicationCalendar> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let month_rows = DbPublicationMonthRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let month_rows = DbPublicationMonthRow::all(&mut *conn, &filter).await?;
 
-    let dow_rows = DbPublicationDowRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let dow_rows = DbPublicationDowRow::all(&mut *conn, &filter).await?;
 
     let monthly: Vec<MonthlyComics> = month_rows
         .into_iter()
@@ -252,21 +374,37 @@ async fn publication_calendar(pool: web::Data<DbPool>) -> Result<Json<Publicatio
         })
         .collect();
 
-    Ok(Json(PublicationCalendar { monthly, daily }))
+    Ok(PublicationCalendar { monthly, daily })
 }
 
 #[api_endpoint(method = "GET", path = "stats/comeback-characters")]
+#[tracing::instrument(skip(pool, cache))]
+async fn comeback_characters(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<ComebackCharacter>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "comeback-characters",
+        filter.into_inner().into(),
+        comeback_characters_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn comeback_characters(pool: web::Data<DbPool>) -> Result<Json<Vec<ComebackCharacter>>> {
+async fn comeback_characters_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<ComebackCharacter>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbComebackCharacterRow::top(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbComebackCharacterRow::top(&mut *conn, &filter).await?;
 
     let result: Vec<ComebackCharacter> = rows
         .into_iter()
@@ -281,41 +419,71 @@ async fn comeback_characters(pool: web::Data<DbPool>) -> Result<Json<Vec<Comebac
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-affinity")]
+#[tracing::instrument(skip(pool, cache))]
+async fn location_affinity(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<LocationAffinity>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "location-affinity",
+        filter.into_inner().into(),
+        location_affinity_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn location_affinity(pool: web::Data<DbPool>) -> Result<Json<Vec<LocationAffinity>>> {
+async fn location_affinity_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<LocationAffinity>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationAffinityRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationAffinityRow::all(&mut *conn, &filter).await?;
 
-    Ok(Json(build_location_affinity_response(rows)))
+    Ok(build_location_affinity_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/crowded-comics")]
+#[tracing::instrument(skip(pool, cache))]
+async fn crowded_comics(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<CrowdedComicsResponse>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "crowded-comics",
+        filter.into_inner().into(),
+        crowded_comics_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn crowded_comics(pool: web::Data<DbPool>) -> Result<Json<CrowdedComicsResponse>> {
+async fn crowded_comics_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<CrowdedComicsResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let comic_rows = DbCrowdedComicRow::top(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let comic_rows = DbCrowdedComicRow::top(&mut *conn, &filter).await?;
 
-    let avg_rows = DbAvgCastPerYearRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let avg_rows = DbAvgCastPerYearRow::all(&mut *conn, &filter).await?;
 
     let top_comics: Vec<MostCrowdedComic> = comic_rows
         .into_iter()
@@ -335,42 +503,75 @@ async fn crowded_comics(pool: web::Data<DbPool>) -> Result<Json<CrowdedComicsRes
         })
         .collect();
 
-    Ok(Json(CrowdedComicsResponse {
+    Ok(CrowdedComicsResponse {
         top_comics,
         avg_per_year,
-    }))
+    })
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-yearly-spotlight")]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, cache))]
 async fn location_yearly_spotlight(
     pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+    grouping: web::Query<StatsGroupingQuery>,
 ) -> Result<Json<LocationSpotlightResponse>> {
+    let grouping: DbStatsGrouping = grouping.grouping.unwrap_or_default().into();
+    respond_filtered(
+        &cache,
+        &pool,
+        &grouped_key("location-yearly-spotlight", grouping),
+        filter.into_inner().into(),
+        move |pool, filter| location_yearly_spotlight_response(pool, filter, grouping),
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn location_yearly_spotlight_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    grouping: DbStatsGrouping,
+) -> sqlx::Result<LocationSpotlightResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationYearlyAppearanceRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationYearlyAppearanceRow::all(&mut *conn, &filter, grouping).await?;
 
-    Ok(Json(build_location_spotlight_response(rows)))
+    Ok(build_location_spotlight_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/publication-gaps")]
+#[tracing::instrument(skip(pool, cache))]
+async fn publication_gaps(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<PublicationGap>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "publication-gaps",
+        filter.into_inner().into(),
+        publication_gaps_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn publication_gaps(pool: web::Data<DbPool>) -> Result<Json<Vec<PublicationGap>>> {
+async fn publication_gaps_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<PublicationGap>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbPublicationGapRow::top(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbPublicationGapRow::top(&mut *conn, &filter).await?;
 
     let result: Vec<PublicationGap> = rows
         .into_iter()
@@ -383,37 +584,69 @@ async fn publication_gaps(pool: web::Data<DbPool>) -> Result<Json<Vec<Publicatio
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/debut-clusters")]
+#[tracing::instrument(skip(pool, cache))]
+async fn debut_clusters(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<DebutYear>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "debut-clusters",
+        filter.into_inner().into(),
+        debut_clusters_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn debut_clusters(pool: web::Data<DbPool>) -> Result<Json<Vec<DebutYear>>> {
+async fn debut_clusters_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<DebutYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbDebutDetailRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbDebutDetailRow::all(&mut *conn, &filter).await?;
 
-    Ok(Json(build_debut_clusters_response(rows)))
+    Ok(build_debut_clusters_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/ensemble-ratio")]
+#[tracing::instrument(skip(pool, cache))]
+async fn ensemble_ratio(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<EnsembleRatio>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "ensemble-ratio",
+        filter.into_inner().into(),
+        ensemble_ratio_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn ensemble_ratio(pool: web::Data<DbPool>) -> Result<Json<Vec<EnsembleRatio>>> {
+async fn ensemble_ratio_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<EnsembleRatio>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbEnsembleRatioRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbEnsembleRatioRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<EnsembleRatio> = rows
         .into_iter()
@@ -429,21 +662,37 @@ async fn ensemble_ratio(pool: web::Data<DbPool>) -> Result<Json<Vec<EnsembleRati
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/character-regularity")]
+#[tracing::instrument(skip(pool, cache))]
+async fn character_regularity(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<CharacterRegularity>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "character-regularity",
+        filter.into_inner().into(),
+        character_regularity_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn character_regularity(pool: web::Data<DbPool>) -> Result<Json<Vec<CharacterRegularity>>> {
+async fn character_regularity_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<CharacterRegularity>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbCharacterRegularityRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbCharacterRegularityRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<CharacterRegularity> = rows
         .into_iter()
@@ -458,21 +707,37 @@ async fn character_regularity(pool: web::Data<DbPool>) -> Result<Json<Vec<Charac
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-regularity")]
+#[tracing::instrument(skip(pool, cache))]
+async fn location_regularity(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<LocationRegularity>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "location-regularity",
+        filter.into_inner().into(),
+        location_regularity_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn location_regularity(pool: web::Data<DbPool>) -> Result<Json<Vec<LocationRegularity>>> {
+async fn location_regularity_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<LocationRegularity>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationRegularityRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationRegularityRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<LocationRegularity> = rows
         .into_iter()
@@ -487,41 +752,358 @@ async fn location_regularity(pool: web::Data<DbPool>) -> Result<Json<Vec<Locatio
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-co-occurrences")]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, cache))]
 async fn location_co_occurrences(
     pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
 ) -> Result<Json<LocationCoOccurrenceResponse>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "location-co-occurrences",
+        filter.into_inner().into(),
+        location_co_occurrences_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn location_co_occurrences_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<LocationCoOccurrenceResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationCoOccurrenceRow::top(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationCoOccurrenceRow::top(&mut *conn, &filter).await?;
 
-    Ok(Json(build_location_co_occurrences_response(rows)))
+    Ok(build_location_co_occurrences_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/best-friend-score")]
+#[tracing::instrument(skip(pool, cache))]
+async fn best_friend_score(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<BestFriendResponse>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "best-friend-score",
+        filter.into_inner().into(),
+        best_friend_score_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn best_friend_score(pool: web::Data<DbPool>) -> Result<Json<BestFriendResponse>> {
+async fn best_friend_score_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<BestFriendResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
+
+    let rows = DbCoAppearance::top_normalized(&mut *conn, &filter).await?;
+
+    Ok(build_best_friend_response(rows))
+}
+
+/// Serves a stats response from `cache`, computing it with `compute` on a miss.
+/// The response is kept and recomputed on every refresh, so `key` must not be
+/// built from request parameters; see [`respond_transient`].
+async fn respond<T, F, Fut>(
+    cache: &StatsResponseCache,
+    pool: &DbPool,
+    key: &str,
+    compute: F,
+) -> Result<Json<T>>
+where
+    T: Clone + Send + Sync + 'static,
+    F: Fn(DbPool) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = sqlx::Result<T>> + Send + 'static,
+{
+    cache
+        .get_or_compute(key, pool, compute)
+        .await
+        .map(Json)
+        .map_err(error::ErrorInternalServerError)
+}
+
+/// Like [`respond`], for a response to a request with parameters. It is only
+/// kept in the cache briefly, so `key` can be built from the request, but it
+/// must include every parameter `compute` depends on.
+async fn respond_transient<T>(
+    cache: &StatsResponseCache,
+    key: &str,
+    compute: impl Future<Output = sqlx::Result<T>>,
+) -> Result<Json<T>>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    cache
+        .get_or_compute_transient(key, compute)
+        .await
+        .map(Json)
+        .map_err(error::ErrorInternalServerError)
+}
 
-    let rows = DbCoAppearance::top_normalized(&mut *conn)
+/// Like [`respond`], for a response computed over the comics matched by
+/// `filter`. The unfiltered response is cached under `key` itself, so that it
+/// is shared with [`prime_response_cache`]; filtered ones are only kept
+/// briefly, like [`respond_transient`].
+async fn respond_filtered<T, F, Fut>(
+    cache: &StatsResponseCache,
+    pool: &DbPool,
+    key: &str,
+    filter: DbStatsFilter,
+    compute: F,
+) -> Result<Json<T>>
+where
+    T: Clone + Send + Sync + 'static,
+    F: Fn(DbPool, DbStatsFilter) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = sqlx::Result<T>> + Send + 'static,
+{
+    if filter == DbStatsFilter::default() {
+        respond(cache, pool, key, move |pool| compute(pool, filter)).await
+    } else {
+        respond_transient(
+            cache,
+            &format!("{key} {filter:?}"),
+            compute(pool.clone(), filter),
+        )
         .await
-        .map_err(error::ErrorInternalServerError)?;
+    }
+}
+
+/// The cache key of a response of a year-based endpoint under `grouping`.
+fn grouped_key(key: &str, grouping: DbStatsGrouping) -> String {
+    match grouping {
+        DbStatsGrouping::Year => key.to_owned(),
+        DbStatsGrouping::Era => format!("{key}?grouping=era"),
+    }
+}
+
+/// Computes the unfiltered response of every stats endpoint that takes no
+/// other parameters, so that the first visit to each stats page is served
+/// from memory as well. Claims the dirty flag first, like a refresh does.
+///
+/// # Errors
+///
+/// Returns a database error if the dirty flag cannot be claimed or the
+/// current data version cannot be read.
+/// Responses that fail to compute are logged and left to the first request.
+pub async fn prime_response_cache(cache: &StatsResponseCache, pool: &DbPool) -> sqlx::Result<()> {
+    StatsResponseCacheMeta::clear_needs_refresh(&**pool).await?;
+    let data_version = ChangeLogEntry::latest_seq(&**pool).await?;
+
+    prime_filtered(cache, pool, "cast", cast_response).await;
+    prime_filtered(cache, pool, "locations", locations_response).await;
+    prime_filtered(cache, pool, "co-appearances", co_appearances_response).await;
+    prime_filtered(cache, pool, "yearly-spotlight", |pool, filter| {
+        yearly_spotlight_response(pool, filter, DbStatsGrouping::Year)
+    })
+    .await;
+    prime_filtered(cache, pool, "debuts-per-year", debuts_per_year_response).await;
+    prime_filtered(cache, pool, "yearly-overview", yearly_overview_response).await;
+    prime_filtered(
+        cache,
+        pool,
+        "publication-calendar",
+        publication_calendar_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "comeback-characters",
+        comeback_characters_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "location-affinity", location_affinity_response).await;
+    prime_filtered(cache, pool, "crowded-comics", crowded_comics_response).await;
+    prime_filtered(cache, pool, "location-yearly-spotlight", |pool, filter| {
+        location_yearly_spotlight_response(pool, filter, DbStatsGrouping::Year)
+    })
+    .await;
+    prime_filtered(cache, pool, "publication-gaps", publication_gaps_response).await;
+    prime_filtered(cache, pool, "debut-clusters", debut_clusters_response).await;
+    prime_filtered(cache, pool, "ensemble-ratio", ensemble_ratio_response).await;
+    prime_filtered(
+        cache,
+        pool,
+        "character-regularity",
+        character_regularity_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "location-regularity",
+        location_regularity_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "location-co-occurrences",
+        location_co_occurrences_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "best-friend-score", best_friend_score_response).await;
+    prime_filtered(cache, pool, "social-hub", social_hub_response).await;
+    prime_filtered(
+        cache,
+        pool,
+        "trending-characters",
+        trending_characters_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "trending-locations",
+        trending_locations_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "cast-turnover", |pool, filter| {
+        cast_turnover_response(pool, filter, DbStatsGrouping::Year)
+    })
+    .await;
+    prime_filtered(cache, pool, "character-seasons", character_seasons_response).await;
+    prime_filtered(cache, pool, "breakout-years", |pool, filter| {
+        breakout_years_response(pool, filter, DbStatsGrouping::Year)
+    })
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "character-home-turf",
+        character_home_turf_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "loner-index", loner_index_response).await;
+    prime_filtered(cache, pool, "never-met", never_met_response).await;
+    prime_filtered(
+        cache,
+        pool,
+        "schedule-evolution",
+        schedule_evolution_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "publish-time-evolution",
+        publish_time_evolution_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "publication-streaks",
+        publication_streaks_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "monthly-heatmap", monthly_heatmap_response).await;
+    prime_filtered(cache, pool, "milestones", milestones_response).await;
+    prime_filtered(
+        cache,
+        pool,
+        "comeback-locations",
+        comeback_locations_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "location-seasons", location_seasons_response).await;
+    prime_filtered(cache, pool, "location-breakout-years", |pool, filter| {
+        location_breakout_years_response(pool, filter, DbStatsGrouping::Year)
+    })
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "location-social-hub",
+        location_social_hub_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "location-turnover", |pool, filter| {
+        location_turnover_response(pool, filter, DbStatsGrouping::Year)
+    })
+    .await;
+    prime(
+        cache,
+        pool,
+        "top-ranked-over-time",
+        top_ranked_over_time_response,
+    )
+    .await;
+    prime(
+        cache,
+        pool,
+        "top-ranked-locations-over-time",
+        top_ranked_locations_over_time_response,
+    )
+    .await;
+    prime(
+        cache,
+        pool,
+        "top-ranked-storylines-over-time",
+        top_ranked_storylines_over_time_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "storylines", storylines_response).await;
+    prime_filtered(
+        cache,
+        pool,
+        "storyline-concurrency",
+        storyline_concurrency_response,
+    )
+    .await;
+    prime_filtered(
+        cache,
+        pool,
+        "storyline-dormant-gaps",
+        storyline_dormant_gaps_response,
+    )
+    .await;
+    prime_filtered(cache, pool, "storyline-cast", storyline_cast_response).await;
+
+    cache.set_data_version(data_version);
+    Ok(())
+}
 
-    Ok(Json(build_best_friend_response(rows)))
+async fn prime<T, F, Fut>(cache: &StatsResponseCache, pool: &DbPool, key: &str, compute: F)
+where
+    T: Clone + Send + Sync + 'static,
+    F: Fn(DbPool) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = sqlx::Result<T>> + Send + 'static,
+{
+    if let Err(e) = cache.get_or_compute(key, pool, compute).await {
+        error!("stats response cache: computing {key} failed: {e}");
+    }
+}
+
+/// Like [`prime`], for the unfiltered response of a filterable endpoint.
+async fn prime_filtered<T, F, Fut>(cache: &StatsResponseCache, pool: &DbPool, key: &str, compute: F)
+where
+    T: Clone + Send + Sync + 'static,
+    F: Fn(DbPool, DbStatsFilter) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = sqlx::Result<T>> + Send + 'static,
+{
+    prime(cache, pool, key, move |pool| {
+        compute(pool, DbStatsFilter::default())
+    })
+    .await;
 }
 
 fn item_stats_from_db(row: DbItemStats) -> Option<ItemStats> {
@@ -569,7 +1151,9 @@ fn build_yearly_spotlight_response(rows: Vec<DbYearlyAppearanceRow>) -> YearlySp
     let mut years: Vec<YearlySpotlightYear> = Vec::new();
 
     for row in rows {
-        let Some(year) = row.year else { continue };
+        let Some(year) = row.year.and_then(|year| i32::try_from(year).ok()) else {
+            continue;
+        };
 
         match years.last_mut() {
             Some(spotlight) if spotlight.year == year => {
@@ -610,7 +1194,9 @@ fn build_location_spotlight_response(
     let mut years: Vec<LocationSpotlightYear> = Vec::new();
 
     for row in rows {
-        let Some(year) = row.year else { continue };
+        let Some(year) = row.year.and_then(|year| i32::try_from(year).ok()) else {
+            continue;
+        };
 
         match years.last_mut() {
             Some(spotlight) if spotlight.year == year => {
@@ -737,17 +1323,33 @@ fn build_best_friend_response(rows: Vec<DbCoAppearance>) -> BestFriendResponse {
 }
 
 #[api_endpoint(method = "GET", path = "stats/social-hub")]
+#[tracing::instrument(skip(pool, cache))]
+async fn social_hub(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<SocialHubEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "social-hub",
+        filter.into_inner().into(),
+        social_hub_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn social_hub(pool: web::Data<DbPool>) -> Result<Json<Vec<SocialHubEntry>>> {
+async fn social_hub_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<SocialHubEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbSocialHubRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbSocialHubRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<SocialHubEntry> = rows
         .into_iter()
@@ -758,59 +1360,110 @@ async fn social_hub(pool: web::Data<DbPool>) -> Result<Json<Vec<SocialHubEntry>>
             distinct_partners: u32::try_from(r.distinct_partners).unwrap_or(u32::MAX),
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/trending-characters")]
+#[tracing::instrument(skip(pool, cache))]
+async fn trending_characters(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<TrendingItem>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "trending-characters",
+        filter.into_inner().into(),
+        trending_characters_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn trending_characters(pool: web::Data<DbPool>) -> Result<Json<Vec<TrendingItem>>> {
+async fn trending_characters_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<TrendingItem>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbTrendingItemRow::cast_trending(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbTrendingItemRow::cast_trending(&mut *conn, &filter).await?;
 
-    Ok(Json(build_trending_response(rows)))
+    Ok(build_trending_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/trending-locations")]
+#[tracing::instrument(skip(pool, cache))]
+async fn trending_locations(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<TrendingItem>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "trending-locations",
+        filter.into_inner().into(),
+        trending_locations_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn trending_locations(pool: web::Data<DbPool>) -> Result<Json<Vec<TrendingItem>>> {
+async fn trending_locations_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<TrendingItem>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbTrendingItemRow::location_trending(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbTrendingItemRow::location_trending(&mut *conn, &filter).await?;
 
-    Ok(Json(build_trending_response(rows)))
+    Ok(build_trending_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/cast-turnover")]
+#[tracing::instrument(skip(pool, cache))]
+async fn cast_turnover(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+    grouping: web::Query<StatsGroupingQuery>,
+) -> Result<Json<Vec<CastTurnoverYear>>> {
+    let grouping: DbStatsGrouping = grouping.grouping.unwrap_or_default().into();
+    respond_filtered(
+        &cache,
+        &pool,
+        &grouped_key("cast-turnover", grouping),
+        filter.into_inner().into(),
+        move |pool, filter| cast_turnover_response(pool, filter, grouping),
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn cast_turnover(pool: web::Data<DbPool>) -> Result<Json<Vec<CastTurnoverYear>>> {
+async fn cast_turnover_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    grouping: DbStatsGrouping,
+) -> sqlx::Result<Vec<CastTurnoverYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbCastTurnoverRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbCastTurnoverRow::all(&mut *conn, &filter, grouping).await?;
 
     let result: Vec<CastTurnoverYear> = rows
         .into_iter()
         .filter_map(|r| {
             Some(CastTurnoverYear {
-                year: r.year?,
+                year: i32::try_from(r.year?).ok()?,
                 new_chars: u32::try_from(r.new_chars).unwrap_or(u32::MAX),
                 continuing_chars: u32::try_from(r.continuing_chars).unwrap_or(u32::MAX),
                 returning_chars: u32::try_from(r.returning_chars).unwrap_or(u32::MAX),
@@ -818,37 +1471,72 @@ async fn cast_turnover(pool: web::Data<DbPool>) -> Result<Json<Vec<CastTurnoverY
             })
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/character-seasons")]
+#[tracing::instrument(skip(pool, cache))]
+async fn character_seasons(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<CharacterSeasonEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "character-seasons",
+        filter.into_inner().into(),
+        character_seasons_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn character_seasons(pool: web::Data<DbPool>) -> Result<Json<Vec<CharacterSeasonEntry>>> {
+async fn character_seasons_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<CharacterSeasonEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbCharacterSeasonRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbCharacterSeasonRow::all(&mut *conn, &filter).await?;
 
-    Ok(Json(build_character_seasons_response(rows)))
+    Ok(build_character_seasons_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/breakout-years")]
+#[tracing::instrument(skip(pool, cache))]
+async fn breakout_years(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+    grouping: web::Query<StatsGroupingQuery>,
+) -> Result<Json<Vec<BreakoutYear>>> {
+    let grouping: DbStatsGrouping = grouping.grouping.unwrap_or_default().into();
+    respond_filtered(
+        &cache,
+        &pool,
+        &grouped_key("breakout-years", grouping),
+        filter.into_inner().into(),
+        move |pool, filter| breakout_years_response(pool, filter, grouping),
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn breakout_years(pool: web::Data<DbPool>) -> Result<Json<Vec<BreakoutYear>>> {
+async fn breakout_years_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    grouping: DbStatsGrouping,
+) -> sqlx::Result<Vec<BreakoutYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbBreakoutYearRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbBreakoutYearRow::all(&mut *conn, &filter, grouping).await?;
 
     let result: Vec<BreakoutYear> = rows
         .into_iter()
@@ -874,21 +1562,37 @@ async fn breakout_years(pool: web::Data<DbPool>) -> Result<Json<Vec<BreakoutYear
             })
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/character-home-turf")]
+#[tracing::instrument(skip(pool, cache))]
+async fn character_home_turf(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<CharacterHomeTurfEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "character-home-turf",
+        filter.into_inner().into(),
+        character_home_turf_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn character_home_turf(pool: web::Data<DbPool>) -> Result<Json<Vec<CharacterHomeTurfEntry>>> {
+async fn character_home_turf_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<CharacterHomeTurfEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbCharacterHomeTurfRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbCharacterHomeTurfRow::all(&mut *conn, &filter).await?;
 
     let mut result: Vec<CharacterHomeTurfEntry> = Vec::new();
     for r in rows {
@@ -910,7 +1614,7 @@ async fn character_home_turf(pool: web::Data<DbPool>) -> Result<Json<Vec<Charact
             character_appearances: u32::try_from(r.character_appearances).unwrap_or(u32::MAX),
         });
     }
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -921,20 +1625,36 @@ struct PairEvolutionQuery {
 }
 
 #[api_endpoint(method = "GET", path = "stats/pair-evolution")]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, cache))]
 async fn pair_evolution(
     pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
     query: web::Query<PairEvolutionQuery>,
+    filter: Query<StatsFilter>,
 ) -> Result<Json<Vec<PairEvolutionYear>>> {
+    let PairEvolutionQuery { char1, char2 } = query.into_inner();
+    let filter: DbStatsFilter = filter.into_inner().into();
+    respond_transient(
+        &cache,
+        &format!("pair-evolution?char1={char1}&char2={char2} {filter:?}"),
+        pair_evolution_response(pool.get_ref().clone(), filter, char1, char2),
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn pair_evolution_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    char1: u16,
+    char2: u16,
+) -> sqlx::Result<Vec<PairEvolutionYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbPairEvolutionRow::for_pair(&mut *conn, query.char1, query.char2)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbPairEvolutionRow::for_pair(&mut *conn, &filter, char1, char2).await?;
 
     let result: Vec<PairEvolutionYear> = rows
         .into_iter()
@@ -945,21 +1665,182 @@ async fn pair_evolution(
             })
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+struct CompareQuery {
+    a: u16,
+    b: u16,
+}
+
+/// A head-to-head comparison of two items.
+#[api_endpoint(method = "GET", path = "stats/compare")]
+#[tracing::instrument(skip(pool, cache))]
+async fn compare(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    query: web::Query<CompareQuery>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<ComparisonResponse>> {
+    let CompareQuery { a, b } = query.into_inner();
+    if a == b {
+        return Err(error::ErrorBadRequest("a and b must be different items"));
+    }
+
+    let filter: DbStatsFilter = filter.into_inner().into();
+    let Json(comparison) = respond_transient(
+        &cache,
+        &format!("compare?a={a}&b={b} {filter:?}"),
+        compare_response(pool.get_ref().clone(), filter, a, b),
+    )
+    .await?;
+    comparison
+        .map(Json)
+        .map_err(|id| error::ErrorNotFound(anyhow!("No item with id {id} exists")))
+}
+
+/// The comparison of items `a` and `b`, or the id of one of them if it
+/// doesn't exist.
+#[tracing::instrument(skip(pool))]
+async fn compare_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    a: u16,
+    b: u16,
+) -> sqlx::Result<Result<ComparisonResponse, u16>> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await?;
+
+    let items = DbItem::by_ids(&mut *conn, &[a, b]).await?;
+    let name_of = |id: u16| {
+        items
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| item.name.clone())
+            .ok_or(id)
+    };
+    let (a_name, b_name) = match (name_of(a), name_of(b)) {
+        (Ok(a_name), Ok(b_name)) => (a_name, b_name),
+        (Err(id), _) | (_, Err(id)) => return Ok(Err(id)),
+    };
+
+    let comics = DbPairComicRow::for_pair(&mut *conn, &filter, a, b).await?;
+    let locations = DbMeetingLocationRow::top_for_pair(&mut *conn, &filter, a, b).await?;
+
+    Ok(Ok(build_comparison_response(
+        (a, a_name),
+        (b, b_name),
+        &comics,
+        locations,
+    )))
+}
+
+fn build_comparison_response(
+    (a_id, a_name): (u16, String),
+    (b_id, b_name): (u16, String),
+    comics: &[DbPairComicRow],
+    locations: Vec<DbMeetingLocationRow>,
+) -> ComparisonResponse {
+    let count = |n: usize| u32::try_from(n).unwrap_or(u32::MAX);
+    let together: Vec<&DbPairComicRow> = comics
+        .iter()
+        .filter(|c| c.a_appears != 0 && c.b_appears != 0)
+        .collect();
+
+    let mut years: BTreeMap<i32, ComparisonYear> = BTreeMap::new();
+    for comic in comics {
+        let Some(year) = comic.year else {
+            continue;
+        };
+        let entry = years.entry(year).or_insert(ComparisonYear {
+            year,
+            a_appearances: 0,
+            b_appearances: 0,
+            comics_together: 0,
+        });
+        entry.a_appearances += u32::from(comic.a_appears != 0);
+        entry.b_appearances += u32::from(comic.b_appears != 0);
+        entry.comics_together += u32::from(comic.a_appears != 0 && comic.b_appears != 0);
+    }
+
+    let gap = |after: &DbPairComicRow, until: Option<&DbPairComicRow>| ComparisonGap {
+        after_comic: ComicId::from_trusted(after.comic_id),
+        until_comic: until.map(|c| ComicId::from_trusted(c.comic_id)),
+        comics: u32::try_from(until.map_or(after.total, |c| c.position - 1) - after.position)
+            .unwrap_or(u32::MAX),
+    };
+    let longest_apart = together
+        .windows(2)
+        .map(|pair| gap(pair[0], Some(pair[1])))
+        .chain(together.last().map(|last| gap(last, None)))
+        .filter(|g| g.comics > 0)
+        .reduce(|longest, g| {
+            if g.comics > longest.comics {
+                g
+            } else {
+                longest
+            }
+        });
+
+    ComparisonResponse {
+        a: ComparedItem {
+            id: ItemId::from(a_id),
+            name: a_name,
+            appearances: count(comics.iter().filter(|c| c.a_appears != 0).count()),
+        },
+        b: ComparedItem {
+            id: ItemId::from(b_id),
+            name: b_name,
+            appearances: count(comics.iter().filter(|c| c.b_appears != 0).count()),
+        },
+        comics_together: count(together.len()),
+        first_together: together.first().map(|c| ComicId::from_trusted(c.comic_id)),
+        latest_together: together.last().map(|c| ComicId::from_trusted(c.comic_id)),
+        years: years.into_values().collect(),
+        meeting_locations: locations
+            .into_iter()
+            .map(|l| MeetingLocation {
+                id: ItemId::from(l.id),
+                name: l.name,
+                comics_together: u32::try_from(l.comics_together).unwrap_or(u32::MAX),
+            })
+            .collect(),
+        longest_apart,
+    }
 }
 
 #[api_endpoint(method = "GET", path = "stats/loner-index")]
+#[tracing::instrument(skip(pool, cache))]
+async fn loner_index(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<LonerEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "loner-index",
+        filter.into_inner().into(),
+        loner_index_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn loner_index(pool: web::Data<DbPool>) -> Result<Json<Vec<LonerEntry>>> {
+async fn loner_index_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<LonerEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLonerIndexRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLonerIndexRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<LonerEntry> = rows
         .into_iter()
@@ -970,21 +1851,37 @@ async fn loner_index(pool: web::Data<DbPool>) -> Result<Json<Vec<LonerEntry>>> {
             avg_co_cast: r.avg_co_cast.unwrap_or(0.0),
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/never-met")]
+#[tracing::instrument(skip(pool, cache))]
+async fn never_met(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<NeverMetPair>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "never-met",
+        filter.into_inner().into(),
+        never_met_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn never_met(pool: web::Data<DbPool>) -> Result<Json<Vec<NeverMetPair>>> {
+async fn never_met_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<NeverMetPair>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbNeverMetRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbNeverMetRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<NeverMetPair> = rows
         .into_iter()
@@ -998,70 +1895,134 @@ async fn never_met(pool: web::Data<DbPool>) -> Result<Json<Vec<NeverMetPair>>> {
             comics_together: u32::try_from(r.comics_together.unwrap_or(0)).unwrap_or(u32::MAX),
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/schedule-evolution")]
+#[tracing::instrument(skip(pool, cache))]
+async fn schedule_evolution(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<ScheduleEvolutionYear>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "schedule-evolution",
+        filter.into_inner().into(),
+        schedule_evolution_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn schedule_evolution(pool: web::Data<DbPool>) -> Result<Json<Vec<ScheduleEvolutionYear>>> {
+async fn schedule_evolution_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<ScheduleEvolutionYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbScheduleEvolutionRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbScheduleEvolutionRow::all(&mut *conn, &filter).await?;
 
-    Ok(Json(build_schedule_evolution_response(rows)))
+    Ok(build_schedule_evolution_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/publish-time-evolution")]
+#[tracing::instrument(skip(pool, cache))]
+async fn publish_time_evolution(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<PublishTimeYear>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "publish-time-evolution",
+        filter.into_inner().into(),
+        publish_time_evolution_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn publish_time_evolution(pool: web::Data<DbPool>) -> Result<Json<Vec<PublishTimeYear>>> {
+async fn publish_time_evolution_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<PublishTimeYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbPublishTimeRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbPublishTimeRow::all(&mut *conn, &filter).await?;
 
-    Ok(Json(build_publish_time_response(rows)))
+    Ok(build_publish_time_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/publication-streaks")]
+#[tracing::instrument(skip(pool, cache))]
+async fn publication_streaks(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<PublicationStreak>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "publication-streaks",
+        filter.into_inner().into(),
+        publication_streaks_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn publication_streaks(pool: web::Data<DbPool>) -> Result<Json<Vec<PublicationStreak>>> {
+async fn publication_streaks_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<PublicationStreak>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbPublishedDateRow::all_distinct(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbPublishedDateRow::all_distinct(&mut *conn, &filter).await?;
 
     let dates: Vec<String> = rows.into_iter().filter_map(|r| r.pub_date).collect();
-    Ok(Json(build_publication_streaks(&dates)))
+    Ok(build_publication_streaks(&dates))
 }
 
 #[api_endpoint(method = "GET", path = "stats/monthly-heatmap")]
+#[tracing::instrument(skip(pool, cache))]
+async fn monthly_heatmap(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<MonthlyHeatmapEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "monthly-heatmap",
+        filter.into_inner().into(),
+        monthly_heatmap_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn monthly_heatmap(pool: web::Data<DbPool>) -> Result<Json<Vec<MonthlyHeatmapEntry>>> {
+async fn monthly_heatmap_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<MonthlyHeatmapEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbMonthlyHeatmapRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbMonthlyHeatmapRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<MonthlyHeatmapEntry> = rows
         .into_iter()
@@ -1073,21 +2034,37 @@ async fn monthly_heatmap(pool: web::Data<DbPool>) -> Result<Json<Vec<MonthlyHeat
             })
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/milestones")]
+#[tracing::instrument(skip(pool, cache))]
+async fn milestones(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<MilestoneComic>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "milestones",
+        filter.into_inner().into(),
+        milestones_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn milestones(pool: web::Data<DbPool>) -> Result<Json<Vec<MilestoneComic>>> {
+async fn milestones_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<MilestoneComic>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbMilestoneComicRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbMilestoneComicRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<MilestoneComic> = rows
         .into_iter()
@@ -1099,7 +2076,7 @@ async fn milestones(pool: web::Data<DbPool>) -> Result<Json<Vec<MilestoneComic>>
             is_non_canon: r.is_non_canon != 0,
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 fn build_trending_response(rows: Vec<DbTrendingItemRow>) -> Vec<TrendingItem> {
@@ -1115,17 +2092,33 @@ fn build_trending_response(rows: Vec<DbTrendingItemRow>) -> Vec<TrendingItem> {
 }
 
 #[api_endpoint(method = "GET", path = "stats/comeback-locations")]
+#[tracing::instrument(skip(pool, cache))]
+async fn comeback_locations(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<ComebackLocation>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "comeback-locations",
+        filter.into_inner().into(),
+        comeback_locations_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn comeback_locations(pool: web::Data<DbPool>) -> Result<Json<Vec<ComebackLocation>>> {
+async fn comeback_locations_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<ComebackLocation>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbComebackLocationRow::top(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbComebackLocationRow::top(&mut *conn, &filter).await?;
 
     let result: Vec<ComebackLocation> = rows
         .into_iter()
@@ -1140,39 +2133,72 @@ async fn comeback_locations(pool: web::Data<DbPool>) -> Result<Json<Vec<Comeback
         })
         .collect();
 
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-seasons")]
+#[tracing::instrument(skip(pool, cache))]
+async fn location_seasons(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<LocationSeasonEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "location-seasons",
+        filter.into_inner().into(),
+        location_seasons_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn location_seasons(pool: web::Data<DbPool>) -> Result<Json<Vec<LocationSeasonEntry>>> {
+async fn location_seasons_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<LocationSeasonEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationSeasonRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationSeasonRow::all(&mut *conn, &filter).await?;
 
-    Ok(Json(build_location_seasons_response(rows)))
+    Ok(build_location_seasons_response(rows))
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-breakout-years")]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, cache))]
 async fn location_breakout_years(
     pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+    grouping: web::Query<StatsGroupingQuery>,
 ) -> Result<Json<Vec<LocationBreakoutYear>>> {
+    let grouping: DbStatsGrouping = grouping.grouping.unwrap_or_default().into();
+    respond_filtered(
+        &cache,
+        &pool,
+        &grouped_key("location-breakout-years", grouping),
+        filter.into_inner().into(),
+        move |pool, filter| location_breakout_years_response(pool, filter, grouping),
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn location_breakout_years_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    grouping: DbStatsGrouping,
+) -> sqlx::Result<Vec<LocationBreakoutYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationBreakoutYearRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationBreakoutYearRow::all(&mut *conn, &filter, grouping).await?;
 
     let result: Vec<LocationBreakoutYear> = rows
         .into_iter()
@@ -1198,21 +2224,37 @@ async fn location_breakout_years(
             })
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-social-hub")]
+#[tracing::instrument(skip(pool, cache))]
+async fn location_social_hub(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<LocationSocialHubEntry>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "location-social-hub",
+        filter.into_inner().into(),
+        location_social_hub_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn location_social_hub(pool: web::Data<DbPool>) -> Result<Json<Vec<LocationSocialHubEntry>>> {
+async fn location_social_hub_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<LocationSocialHubEntry>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationSocialHubRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationSocialHubRow::all(&mut *conn, &filter).await?;
 
     let result: Vec<LocationSocialHubEntry> = rows
         .into_iter()
@@ -1223,27 +2265,46 @@ async fn location_social_hub(pool: web::Data<DbPool>) -> Result<Json<Vec<Locatio
             distinct_characters: u32::try_from(r.distinct_characters).unwrap_or(u32::MAX),
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 #[api_endpoint(method = "GET", path = "stats/location-turnover")]
+#[tracing::instrument(skip(pool, cache))]
+async fn location_turnover(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+    grouping: web::Query<StatsGroupingQuery>,
+) -> Result<Json<Vec<LocationTurnoverYear>>> {
+    let grouping: DbStatsGrouping = grouping.grouping.unwrap_or_default().into();
+    respond_filtered(
+        &cache,
+        &pool,
+        &grouped_key("location-turnover", grouping),
+        filter.into_inner().into(),
+        move |pool, filter| location_turnover_response(pool, filter, grouping),
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn location_turnover(pool: web::Data<DbPool>) -> Result<Json<Vec<LocationTurnoverYear>>> {
+async fn location_turnover_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+    grouping: DbStatsGrouping,
+) -> sqlx::Result<Vec<LocationTurnoverYear>> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbLocationTurnoverRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbLocationTurnoverRow::all(&mut *conn, &filter, grouping).await?;
 
     let result: Vec<LocationTurnoverYear> = rows
         .into_iter()
         .filter_map(|r| {
             Some(LocationTurnoverYear {
-                year: r.year?,
+                year: i32::try_from(r.year?).ok()?,
                 new_locations: u32::try_from(r.new_locations).unwrap_or(u32::MAX),
                 continuing_locations: u32::try_from(r.continuing_locations).unwrap_or(u32::MAX),
                 returning_locations: u32::try_from(r.returning_locations).unwrap_or(u32::MAX),
@@ -1252,7 +2313,7 @@ async fn location_turnover(pool: web::Data<DbPool>) -> Result<Json<Vec<LocationT
             })
         })
         .collect();
-    Ok(Json(result))
+    Ok(result)
 }
 
 fn build_location_seasons_response(rows: Vec<DbLocationSeasonRow>) -> Vec<LocationSeasonEntry> {
@@ -1474,19 +2535,81 @@ fn build_publication_streaks(dates: &[String]) -> Vec<PublicationStreak> {
 }
 
 #[api_endpoint(method = "GET", path = "stats/top-ranked-over-time")]
+#[tracing::instrument(skip(pool, cache))]
+async fn top_ranked_over_time(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+) -> Result<Json<TopRankedOverTimeResponse>> {
+    respond(
+        &cache,
+        &pool,
+        "top-ranked-over-time",
+        top_ranked_over_time_response,
+    )
+    .await
+}
+
 #[tracing::instrument(skip(pool))]
-async fn top_ranked_over_time(pool: web::Data<DbPool>) -> Result<Json<TopRankedOverTimeResponse>> {
+async fn top_ranked_over_time_response(pool: DbPool) -> sqlx::Result<TopRankedOverTimeResponse> {
+    top_ranked_stints_response(pool, DbItemType::Cast).await
+}
+
+#[api_endpoint(method = "GET", path = "stats/top-ranked-locations-over-time")]
+#[tracing::instrument(skip(pool, cache))]
+async fn top_ranked_locations_over_time(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+) -> Result<Json<TopRankedOverTimeResponse>> {
+    respond(
+        &cache,
+        &pool,
+        "top-ranked-locations-over-time",
+        top_ranked_locations_over_time_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn top_ranked_locations_over_time_response(
+    pool: DbPool,
+) -> sqlx::Result<TopRankedOverTimeResponse> {
+    top_ranked_stints_response(pool, DbItemType::Location).await
+}
+
+#[api_endpoint(method = "GET", path = "stats/top-ranked-storylines-over-time")]
+#[tracing::instrument(skip(pool, cache))]
+async fn top_ranked_storylines_over_time(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+) -> Result<Json<TopRankedOverTimeResponse>> {
+    respond(
+        &cache,
+        &pool,
+        "top-ranked-storylines-over-time",
+        top_ranked_storylines_over_time_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn top_ranked_storylines_over_time_response(
+    pool: DbPool,
+) -> sqlx::Result<TopRankedOverTimeResponse> {
+    top_ranked_stints_response(pool, DbItemType::Storyline).await
+}
+
+async fn top_ranked_stints_response(
+    pool: DbPool,
+    item_type: DbItemType,
+) -> sqlx::Result<TopRankedOverTimeResponse> {
     let mut conn = pool
         .acquire()
         .instrument(info_span!("Pool::acquire"))
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+        .await?;
 
-    let rows = DbTopRankedStintRow::all(&mut *conn)
-        .await
-        .map_err(error::ErrorInternalServerError)?;
+    let rows = DbTopRankedStintRow::all(&mut *conn, item_type).await?;
 
-    Ok(Json(build_top_ranked_over_time_response(rows)))
+    Ok(build_top_ranked_over_time_response(rows))
 }
 
 fn build_top_ranked_over_time_response(
@@ -1515,6 +2638,291 @@ fn build_top_ranked_over_time_response(
     TopRankedOverTimeResponse { stints, characters }
 }
 
+/// How many of the longest dormant gaps are returned.
+const LONGEST_DORMANT_GAPS: usize = 20;
+
+/// How many characters are returned per storyline.
+const STORYLINE_TOP_CAST: usize = 5;
+
+#[api_endpoint(method = "GET", path = "stats/storylines")]
+#[tracing::instrument(skip(pool, cache))]
+async fn storylines(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<StorylineStats>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "storylines",
+        filter.into_inner().into(),
+        storylines_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn storylines_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<StorylineStats>> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await?;
+
+    let rows = DbStorylineLifecycleRow::all(&mut *conn, &filter).await?;
+
+    Ok(rows.iter().map(storyline_stats_from_db).collect())
+}
+
+fn storyline_stats_from_db(row: &DbStorylineLifecycleRow) -> StorylineStats {
+    let span_comics = u32::try_from(row.span_comics).unwrap_or(u32::MAX);
+    let featured_comics = u32::try_from(row.featured_comics).unwrap_or(u32::MAX);
+    let span_days = row
+        .first_publish_date
+        .zip(row.last_publish_date)
+        .map(|(first, last)| {
+            u32::try_from((last.date() - first.date()).num_days()).unwrap_or_default()
+        });
+
+    StorylineStats {
+        id: ItemId::from(row.id),
+        name: row.name.clone(),
+        first_comic: ComicId::from_trusted(row.first_comic_id),
+        last_comic: ComicId::from_trusted(row.last_comic_id),
+        ongoing: row.end_comic_id.is_none(),
+        span_comics,
+        span_days,
+        featured_comics,
+        feature_density: if span_comics == 0 {
+            0.0
+        } else {
+            f64::from(featured_comics) / f64::from(span_comics)
+        },
+    }
+}
+
+#[api_endpoint(method = "GET", path = "stats/storyline-concurrency")]
+#[tracing::instrument(skip(pool, cache))]
+async fn storyline_concurrency(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<StorylineConcurrency>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "storyline-concurrency",
+        filter.into_inner().into(),
+        storyline_concurrency_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn storyline_concurrency_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<StorylineConcurrency>> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await?;
+
+    let rows = DbStorylineLifecycleRow::all(&mut *conn, &filter).await?;
+
+    Ok(build_storyline_concurrency(&rows))
+}
+
+/// Splits the comics into segments with a constant number of running
+/// storylines. Segments start where a storyline starts or the one before
+/// ends; an ongoing storyline runs until the latest comic.
+fn build_storyline_concurrency(rows: &[DbStorylineLifecycleRow]) -> Vec<StorylineConcurrency> {
+    // Ongoing storylines end at `u32::MAX`, after every comic.
+    let mut changes: BTreeMap<u32, i64> = BTreeMap::new();
+    for row in rows {
+        *changes.entry(u32::from(row.first_comic_id)).or_default() += 1;
+        let end = if row.end_comic_id.is_some() {
+            u32::from(row.last_comic_id) + 1
+        } else {
+            u32::MAX
+        };
+        *changes.entry(end).or_default() -= 1;
+    }
+
+    let comic_id = |point: u32| u16::try_from(point).ok().map(ComicId::from_trusted);
+
+    let mut segments: Vec<StorylineConcurrency> = Vec::new();
+    let mut running = 0;
+    let mut points = changes.into_iter().peekable();
+    while let Some((point, change)) = points.next() {
+        running += change;
+        let (Some(from_comic), Some(&(next, _))) = (comic_id(point), points.peek()) else {
+            break;
+        };
+        let storylines = u32::try_from(running).unwrap_or_default();
+        match segments.last_mut() {
+            Some(last) if last.storylines == storylines => {
+                last.to_comic_exclusive = comic_id(next);
+            }
+            _ => segments.push(StorylineConcurrency {
+                from_comic,
+                to_comic_exclusive: comic_id(next),
+                storylines,
+            }),
+        }
+    }
+
+    segments
+}
+
+#[api_endpoint(method = "GET", path = "stats/storyline-dormant-gaps")]
+#[tracing::instrument(skip(pool, cache))]
+async fn storyline_dormant_gaps(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<StorylineDormantGap>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "storyline-dormant-gaps",
+        filter.into_inner().into(),
+        storyline_dormant_gaps_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn storyline_dormant_gaps_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<StorylineDormantGap>> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await?;
+
+    let storylines = DbStorylineLifecycleRow::all(&mut *conn, &filter).await?;
+    let features = DbStorylineFeatureRow::all(&mut *conn, &filter).await?;
+
+    Ok(build_storyline_dormant_gaps(&storylines, &features))
+}
+
+/// The longest runs of comics within a storyline's lifecycle that do not
+/// feature it, longest first. Comics before the first feature are not a gap.
+/// `features` must be ordered by storyline and comic.
+fn build_storyline_dormant_gaps(
+    storylines: &[DbStorylineLifecycleRow],
+    features: &[DbStorylineFeatureRow],
+) -> Vec<StorylineDormantGap> {
+    let mut gaps: Vec<StorylineDormantGap> = Vec::new();
+    for run in features.chunk_by(|a, b| a.storyline_id == b.storyline_id) {
+        let Some(storyline) = storylines.iter().find(|s| s.id == run[0].storyline_id) else {
+            continue;
+        };
+        let gap = |after: &DbStorylineFeatureRow, until: Option<&DbStorylineFeatureRow>| {
+            StorylineDormantGap {
+                id: ItemId::from(storyline.id),
+                name: storyline.name.clone(),
+                after_comic: ComicId::from_trusted(after.comic_id),
+                until_comic: until.map(|f| ComicId::from_trusted(f.comic_id)),
+                comics: u32::try_from(
+                    until.map_or(storyline.last_position, |f| f.position - 1) - after.position,
+                )
+                .unwrap_or_default(),
+            }
+        };
+        gaps.extend(
+            run.windows(2)
+                .map(|pair| gap(&pair[0], Some(&pair[1])))
+                .chain(run.last().map(|last| gap(last, None)))
+                .filter(|g| g.comics > 0),
+        );
+    }
+
+    gaps.sort_by(|a, b| b.comics.cmp(&a.comics).then(a.id.cmp(&b.id)));
+    gaps.truncate(LONGEST_DORMANT_GAPS);
+    gaps
+}
+
+#[api_endpoint(method = "GET", path = "stats/storyline-cast")]
+#[tracing::instrument(skip(pool, cache))]
+async fn storyline_cast(
+    pool: web::Data<DbPool>,
+    cache: web::Data<StatsResponseCache>,
+    filter: Query<StatsFilter>,
+) -> Result<Json<Vec<StorylineCast>>> {
+    respond_filtered(
+        &cache,
+        &pool,
+        "storyline-cast",
+        filter.into_inner().into(),
+        storyline_cast_response,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn storyline_cast_response(
+    pool: DbPool,
+    filter: DbStatsFilter,
+) -> sqlx::Result<Vec<StorylineCast>> {
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await?;
+
+    let storylines = DbStorylineLifecycleRow::all(&mut *conn, &filter).await?;
+    let cast = DbStorylineCastRow::all(&mut *conn, &filter).await?;
+
+    Ok(build_storyline_cast(&storylines, cast))
+}
+
+/// The characters most often in the comics featuring each storyline, for the
+/// storylines that are featured at all. `cast` must be ordered by storyline
+/// and then most comics together first.
+fn build_storyline_cast(
+    storylines: &[DbStorylineLifecycleRow],
+    cast: Vec<DbStorylineCastRow>,
+) -> Vec<StorylineCast> {
+    let mut by_storyline: HashMap<u16, Vec<DbStorylineCastRow>> = HashMap::new();
+    for row in cast {
+        let characters = by_storyline.entry(row.storyline_id).or_default();
+        if characters.len() < STORYLINE_TOP_CAST {
+            characters.push(row);
+        }
+    }
+
+    storylines
+        .iter()
+        .filter(|s| s.featured_comics > 0)
+        .map(|s| {
+            let featured_comics = u32::try_from(s.featured_comics).unwrap_or(u32::MAX);
+            StorylineCast {
+                id: ItemId::from(s.id),
+                name: s.name.clone(),
+                featured_comics,
+                top_characters: by_storyline
+                    .remove(&s.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| {
+                        let comics_together = u32::try_from(c.comics_together).unwrap_or(u32::MAX);
+                        StorylineCastMember {
+                            id: ItemId::from(c.character_id),
+                            name: c.character_name,
+                            comics_together,
+                            share: f64::from(comics_together) / f64::from(featured_comics),
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
 fn build_location_affinity_response(rows: Vec<DbLocationAffinityRow>) -> Vec<LocationAffinity> {
     let mut location_entries: Vec<LocationAffinity> = Vec::new();
 
@@ -1549,6 +2957,53 @@ fn build_location_affinity_response(rows: Vec<DbLocationAffinityRow>) -> Vec<Loc
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::v3::models::{Exclusion, ItemType};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn stats_filter_maps_repeated_exclusions_and_bounds() {
+        let filter = DbStatsFilter::from(StatsFilter {
+            exclude: vec![Exclusion::NonCanon, Exclusion::Guest, Exclusion::NonCanon],
+            from_comic: Some(100),
+            to_comic: Some(200),
+            from_date: NaiveDate::from_ymd_opt(2004, 1, 1),
+            to_date: None,
+            item_type: Some(ItemType::Location),
+        });
+
+        assert_eq!(
+            filter,
+            DbStatsFilter {
+                exclude_guest_comics: true,
+                exclude_non_canon_comics: true,
+                from_comic: Some(100),
+                to_comic: Some(200),
+                from_date: NaiveDate::from_ymd_opt(2004, 1, 1),
+                to_date: None,
+                item_type: Some(DbItemType::Location),
+            }
+        );
+    }
+
+    #[test]
+    fn stats_filter_maps_a_single_exclusion() {
+        let filter = DbStatsFilter::from(StatsFilter {
+            exclude: vec![Exclusion::Guest],
+            ..StatsFilter::default()
+        });
+
+        assert!(filter.exclude_guest_comics);
+        assert!(!filter.exclude_non_canon_comics);
+        assert_ne!(filter, DbStatsFilter::default());
+    }
+
+    #[test]
+    fn empty_stats_filter_maps_to_the_default() {
+        assert_eq!(
+            DbStatsFilter::from(StatsFilter::default()),
+            DbStatsFilter::default()
+        );
+    }
 
     fn make_co_appearance(
         id1: u16,
@@ -1907,4 +3362,244 @@ mod tests {
         assert_eq!(result.stints[2].to_comic_exclusive, None);
         assert_eq!(result.stints[2].appearances_at_takeover, 201);
     }
+
+    fn pair_comic(comic_id: u16, position: i64, a: bool, b: bool) -> DbPairComicRow {
+        DbPairComicRow {
+            comic_id,
+            year: Some(if comic_id < 100 { 2004 } else { 2005 }),
+            position,
+            total: 20,
+            a_appears: i64::from(a),
+            b_appears: i64::from(b),
+        }
+    }
+
+    #[test]
+    fn build_comparison_counts_shared_comics_per_year() {
+        let comics = [
+            pair_comic(10, 1, true, false),
+            pair_comic(20, 2, true, true),
+            pair_comic(30, 3, false, true),
+            pair_comic(110, 8, true, true),
+        ];
+
+        let result = build_comparison_response(
+            (1, "Alice".to_string()),
+            (2, "Bob".to_string()),
+            &comics,
+            Vec::new(),
+        );
+
+        assert_eq!(result.a.appearances, 3);
+        assert_eq!(result.b.appearances, 3);
+        assert_eq!(result.comics_together, 2);
+        assert_eq!(result.first_together, Some(ComicId::from_trusted(20)));
+        assert_eq!(result.latest_together, Some(ComicId::from_trusted(110)));
+        assert_eq!(
+            result.years,
+            [
+                ComparisonYear {
+                    year: 2004,
+                    a_appearances: 2,
+                    b_appearances: 2,
+                    comics_together: 1,
+                },
+                ComparisonYear {
+                    year: 2005,
+                    a_appearances: 1,
+                    b_appearances: 1,
+                    comics_together: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn build_comparison_finds_longest_gap_including_the_current_one() {
+        let comics = [
+            pair_comic(20, 2, true, true),
+            pair_comic(50, 5, true, true),
+            pair_comic(60, 12, true, true),
+        ];
+
+        let result = build_comparison_response(
+            (1, "Alice".to_string()),
+            (2, "Bob".to_string()),
+            &comics,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            result.longest_apart,
+            Some(ComparisonGap {
+                after_comic: ComicId::from_trusted(60),
+                until_comic: None,
+                comics: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn build_comparison_has_no_gap_when_never_met() {
+        let comics = [
+            pair_comic(20, 2, true, false),
+            pair_comic(30, 3, false, true),
+        ];
+
+        let result = build_comparison_response(
+            (1, "Alice".to_string()),
+            (2, "Bob".to_string()),
+            &comics,
+            Vec::new(),
+        );
+
+        assert_eq!(result.comics_together, 0);
+        assert_eq!(result.longest_apart, None);
+    }
+
+    fn storyline(
+        id: u16,
+        first: u16,
+        last: u16,
+        last_position: i64,
+        ongoing: bool,
+        featured_comics: i64,
+    ) -> DbStorylineLifecycleRow {
+        DbStorylineLifecycleRow {
+            id,
+            name: format!("Storyline {id}"),
+            start_comic_id: first,
+            end_comic_id: (!ongoing).then_some(last + 1),
+            first_comic_id: first,
+            last_comic_id: last,
+            last_position,
+            span_comics: i64::from(last - first + 1),
+            first_publish_date: None,
+            last_publish_date: None,
+            featured_comics,
+        }
+    }
+
+    fn feature(storyline_id: u16, comic_id: u16) -> DbStorylineFeatureRow {
+        DbStorylineFeatureRow {
+            storyline_id,
+            comic_id,
+            position: i64::from(comic_id),
+        }
+    }
+
+    fn segment(from: u16, to: Option<u16>, storylines: u32) -> StorylineConcurrency {
+        StorylineConcurrency {
+            from_comic: ComicId::from_trusted(from),
+            to_comic_exclusive: to.map(ComicId::from_trusted),
+            storylines,
+        }
+    }
+
+    #[test]
+    fn storyline_stats_compute_density_and_days() {
+        let mut row = storyline(1, 10, 19, 19, false, 5);
+        row.first_publish_date =
+            NaiveDate::from_ymd_opt(2004, 1, 1).and_then(|d| d.and_hms_opt(8, 0, 0));
+        row.last_publish_date =
+            NaiveDate::from_ymd_opt(2004, 1, 15).and_then(|d| d.and_hms_opt(20, 0, 0));
+
+        let stats = storyline_stats_from_db(&row);
+
+        assert_eq!(stats.span_comics, 10);
+        assert_eq!(stats.span_days, Some(14));
+        assert!((stats.feature_density - 0.5).abs() < f64::EPSILON);
+        assert!(!stats.ongoing);
+    }
+
+    #[test]
+    fn storyline_concurrency_counts_overlapping_storylines() {
+        let rows = [
+            storyline(1, 1, 9, 9, false, 1),
+            storyline(2, 5, 14, 14, false, 1),
+            storyline(3, 20, 30, 30, true, 1),
+        ];
+
+        assert_eq!(
+            build_storyline_concurrency(&rows),
+            [
+                segment(1, Some(5), 1),
+                segment(5, Some(10), 2),
+                segment(10, Some(15), 1),
+                segment(15, Some(20), 0),
+                segment(20, None, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn storyline_concurrency_merges_back_to_back_storylines() {
+        let rows = [
+            storyline(1, 1, 9, 9, false, 1),
+            storyline(2, 10, 19, 19, false, 1),
+        ];
+
+        assert_eq!(
+            build_storyline_concurrency(&rows),
+            [segment(1, Some(20), 1)]
+        );
+    }
+
+    #[test]
+    fn storyline_dormant_gaps_include_the_trailing_gap() {
+        let storylines = [
+            storyline(1, 1, 20, 20, false, 3),
+            storyline(2, 5, 12, 12, true, 2),
+        ];
+        let features = [
+            feature(1, 1),
+            feature(1, 2),
+            feature(1, 8),
+            feature(2, 8),
+            feature(2, 9),
+        ];
+
+        let gaps = build_storyline_dormant_gaps(&storylines, &features);
+
+        let gaps: Vec<_> = gaps
+            .iter()
+            .map(|g| {
+                (
+                    g.id.into_inner(),
+                    g.after_comic.into_inner(),
+                    g.until_comic.map(ComicId::into_inner),
+                    g.comics,
+                )
+            })
+            .collect();
+        assert_eq!(
+            gaps,
+            [(1, 8, None, 12), (1, 2, Some(8), 5), (2, 9, None, 3)]
+        );
+    }
+
+    #[test]
+    fn storyline_cast_keeps_the_top_characters_of_featured_storylines() {
+        let storylines = [
+            storyline(1, 1, 20, 20, false, 4),
+            storyline(2, 5, 12, 12, true, 0),
+        ];
+        let cast = (0..7)
+            .map(|i| DbStorylineCastRow {
+                storyline_id: 1,
+                character_id: 100 + i,
+                character_name: format!("Character {i}"),
+                comics_together: i64::from(4 - i.min(3)),
+            })
+            .collect();
+
+        let result = build_storyline_cast(&storylines, cast);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].featured_comics, 4);
+        assert_eq!(result[0].top_characters.len(), STORYLINE_TOP_CAST);
+        assert_eq!(result[0].top_characters[0].id, ItemId::from(100));
+        assert!((result[0].top_characters[0].share - 1.0).abs() < f64::EPSILON);
+        assert!((result[0].top_characters[1].share - 0.75).abs() < f64::EPSILON);
+    }
 }
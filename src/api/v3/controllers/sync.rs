@@ -0,0 +1,350 @@
+use crate::api::v3::models::{
+    Era, ItemColor, ItemType, SyncComic, SyncItem, SyncItemImage, SyncNews, SyncOccurrence,
+    SyncResponse, SyncTombstones,
+};
+use crate::models::{ComicId, ImageId, ItemId};
+use actix_web::web::Json;
+use actix_web::{Result, error, web};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
+use api_macros::api_endpoint;
+use database::DbPool;
+use database::models::{
+    ChangeLogEntry, Comic as DatabaseComic, Era as DatabaseEra, Item as DatabaseItem,
+    ItemOccurrence, News as DatabaseNews,
+};
+use serde::Deserialize;
+use shared::token_permissions;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use tracing::{Instrument, info_span, warn};
+use ts_rs::TS;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(sync);
+}
+
+/// The most change log entries handled per request. Clients with a cursor
+/// further behind receive `hasMore` and keep requesting.
+const MAX_CHANGES: u16 = 1000;
+
+/// Returns every comic, item, occurrence, item image, news entry and era changed
+/// after the `since` cursor, in their current state, plus tombstones for the
+/// ones that were deleted. Without `since`, only the current cursor is
+/// returned, for clients that just made a full fetch.
+///
+/// A changed comic also resends its occurrences and news, so a comic that
+/// becomes visible again arrives complete. Hidden comics (and everything
+/// attached to them) are reported as tombstones to callers without a valid
+/// token.
+#[api_endpoint(method = "GET", path = "sync/")]
+#[tracing::instrument(skip(pool, auth), fields(permissions = ?auth.authorities))]
+async fn sync(
+    pool: web::Data<DbPool>,
+    query: web::Query<SyncQuery>,
+    auth: AuthDetails,
+) -> Result<Json<SyncResponse>> {
+    let include_hidden = auth.has_authority(token_permissions::HAS_VALID_TOKEN);
+
+    let mut conn = pool
+        .acquire()
+        .instrument(info_span!("Pool::acquire"))
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let Some(since) = query.since else {
+        let cursor = ChangeLogEntry::latest_seq(&mut *conn)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .unwrap_or(0);
+
+        return Ok(Json(SyncResponse {
+            cursor,
+            has_more: false,
+            comics: Vec::new(),
+            items: Vec::new(),
+            occurrences: Vec::new(),
+            item_images: Vec::new(),
+            news: Vec::new(),
+            eras: Vec::new(),
+            tombstones: SyncTombstones::default(),
+        }));
+    };
+
+    let mut entries = ChangeLogEntry::since(&mut *conn, since, u32::from(MAX_CHANGES) + 1)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let has_more = entries.len() > usize::from(MAX_CHANGES);
+    entries.truncate(usize::from(MAX_CHANGES));
+    let cursor = entries.last().map_or(since, |entry| entry.seq);
+
+    let mut changes = Changes::from_entries(&entries);
+
+    let changed_comic_ids: Vec<u16> = changes.comics.iter().copied().collect();
+    changes.news.extend(&changed_comic_ids);
+    changes.occurrences.extend(
+        DatabaseItem::occurrences_in_comics(&mut *conn, &changed_comic_ids)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .map(|o| (o.comic_id, o.item_id)),
+    );
+
+    let mut tombstones = SyncTombstones::default();
+
+    let referenced_comic_ids: Vec<u16> = changes
+        .comics
+        .iter()
+        .copied()
+        .chain(changes.news.iter().copied())
+        .chain(changes.occurrences.iter().map(|&(comic_id, _)| comic_id))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let mut visible_comics: BTreeMap<u16, DatabaseComic> =
+        DatabaseComic::by_ids(&mut *conn, &referenced_comic_ids)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .filter(|comic| include_hidden || comic.hidden == 0)
+            .map(|comic| (comic.id, comic))
+            .collect();
+    let visible_comic_ids: HashSet<u16> = visible_comics.keys().copied().collect();
+
+    let mut comics = Vec::new();
+    for &comic_id in &changes.comics {
+        if let Some(comic) = visible_comics.remove(&comic_id) {
+            comics.push(SyncComic::from(comic));
+        } else {
+            tombstones.comics.push(ComicId::from_trusted(comic_id));
+        }
+    }
+
+    let changed_item_ids: Vec<u16> = changes.items.iter().copied().collect();
+    let mut current_items: BTreeMap<u16, DatabaseItem> =
+        DatabaseItem::by_ids(&mut *conn, &changed_item_ids)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .map(|item| (item.id, item))
+            .collect();
+    let mut items = Vec::new();
+    for item_id in changed_item_ids {
+        if let Some(item) = current_items.remove(&item_id) {
+            items.push(sync_item_from_db(item)?);
+        } else {
+            tombstones.items.push(ItemId::from(item_id));
+        }
+    }
+
+    let changed_occurrences: Vec<ItemOccurrence> = changes
+        .occurrences
+        .iter()
+        .map(|&(comic_id, item_id)| ItemOccurrence { item_id, comic_id })
+        .collect();
+    let current_occurrences: HashSet<ItemOccurrence> =
+        DatabaseItem::existing_occurrences(&mut *conn, &changed_occurrences)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .collect();
+    let mut occurrences = Vec::new();
+    for occurrence in changed_occurrences {
+        let sync_occurrence = SyncOccurrence {
+            comic: ComicId::from_trusted(occurrence.comic_id),
+            item: ItemId::from(occurrence.item_id),
+        };
+        if current_occurrences.contains(&occurrence)
+            && visible_comic_ids.contains(&occurrence.comic_id)
+        {
+            occurrences.push(sync_occurrence);
+        } else {
+            tombstones.occurrences.push(sync_occurrence);
+        }
+    }
+
+    let changed_image_ids: Vec<u32> = changes.item_images.iter().copied().collect();
+    let mut current_images: BTreeMap<u32, _> =
+        DatabaseItem::image_records_by_image_ids(&mut *conn, &changed_image_ids)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .map(|image| (image.id, image))
+            .collect();
+    let mut item_images = Vec::new();
+    for image_id in changed_image_ids {
+        if let Some(image) = current_images.remove(&image_id) {
+            item_images.push(SyncItemImage {
+                id: ImageId::from(image.id),
+                item_id: ItemId::from(image.item_id),
+                crc32c_hash: image.crc32c_hash,
+            });
+        } else {
+            tombstones.item_images.push(ImageId::from(image_id));
+        }
+    }
+
+    let changed_news_ids: Vec<u16> = changes.news.iter().copied().collect();
+    let mut current_news: BTreeMap<u16, DatabaseNews> =
+        DatabaseNews::by_comic_ids(&mut *conn, &changed_news_ids)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .filter(|news| visible_comic_ids.contains(&news.comic_id))
+            .map(|news| (news.comic_id, news))
+            .collect();
+    let mut news = Vec::new();
+    for comic_id in changed_news_ids {
+        if let Some(current) = current_news.remove(&comic_id) {
+            news.push(SyncNews {
+                comic: ComicId::from_trusted(comic_id),
+                last_updated: current.last_updated,
+                news: current.news,
+            });
+        } else {
+            tombstones.news.push(ComicId::from_trusted(comic_id));
+        }
+    }
+
+    let changed_era_ids: Vec<u16> = changes.eras.iter().copied().collect();
+    let mut current_eras: BTreeMap<u16, DatabaseEra> =
+        DatabaseEra::by_ids(&mut *conn, &changed_era_ids)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .map(|era| (era.id, era))
+            .collect();
+    let mut eras = Vec::new();
+    for era_id in changed_era_ids {
+        if let Some(era) = current_eras.remove(&era_id) {
+            eras.push(Era::from(era));
+        } else {
+            tombstones.eras.push(era_id);
+        }
+    }
+
+    Ok(Json(SyncResponse {
+        cursor,
+        has_more,
+        comics,
+        items,
+        occurrences,
+        item_images,
+        news,
+        eras,
+        tombstones,
+    }))
+}
+
+pub(super) fn sync_item_from_db(item: DatabaseItem) -> Result<SyncItem> {
+    Ok(SyncItem {
+        id: item.id.into(),
+        short_name: item.short_name,
+        name: item.name,
+        r#type: ItemType::try_from(&*item.r#type).map_err(error::ErrorInternalServerError)?,
+        color: ItemColor(item.color_red, item.color_green, item.color_blue),
+        primary_image: item.primary_image.map(ImageId::from),
+        start_comic_id: item.start_comic_id.map(ComicId::from_trusted),
+        end_comic_id: item.end_comic_id.map(ComicId::from_trusted),
+    })
+}
+
+/// The keys touched by a run of change log entries. Only the keys matter:
+/// whether something was deleted is decided by looking up its current state,
+/// so a row that was deleted and then recreated is sent as an upsert.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Changes {
+    comics: BTreeSet<u16>,
+    items: BTreeSet<u16>,
+    /// `(comic_id, item_id)`
+    occurrences: BTreeSet<(u16, u16)>,
+    item_images: BTreeSet<u32>,
+    news: BTreeSet<u16>,
+    eras: BTreeSet<u16>,
+}
+
+impl Changes {
+    fn from_entries(entries: &[ChangeLogEntry]) -> Self {
+        let mut changes = Self::default();
+        for entry in entries {
+            let entity_id = entry.entity_id;
+            let secondary_id = entry.secondary_id;
+            let small_id = || u16::try_from(entity_id).ok();
+            let inserted = match entry.entity.as_str() {
+                "comic" => small_id().map(|id| changes.comics.insert(id)),
+                "item" => small_id().map(|id| changes.items.insert(id)),
+                "occurrence" => small_id()
+                    .zip(secondary_id.and_then(|id| u16::try_from(id).ok()))
+                    .map(|key| changes.occurrences.insert(key)),
+                "item_image" => Some(changes.item_images.insert(entity_id)),
+                "news" => small_id().map(|id| changes.news.insert(id)),
+                "era" => small_id().map(|id| changes.eras.insert(id)),
+                _ => None,
+            };
+            if inserted.is_none() {
+                warn!(
+                    "Ignoring malformed change log entry {}: {} {} {:?}",
+                    entry.seq, entry.entity, entity_id, secondary_id
+                );
+            }
+        }
+        changes
+    }
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+struct SyncQuery {
+    #[ts(optional, type = "number")]
+    since: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seq: u64, entity: &str, entity_id: u32, secondary_id: Option<u32>) -> ChangeLogEntry {
+        ChangeLogEntry {
+            seq,
+            entity: entity.to_owned(),
+            entity_id,
+            secondary_id,
+            deleted: 0,
+        }
+    }
+
+    #[test]
+    fn changes_are_collapsed_per_key() {
+        let changes = Changes::from_entries(&[
+            entry(1, "comic", 12, None),
+            entry(2, "comic", 12, None),
+            entry(3, "occurrence", 12, Some(4)),
+            entry(4, "occurrence", 12, Some(4)),
+            entry(5, "item_image", 70_000, None),
+            entry(6, "news", 12, None),
+            entry(7, "era", 3, None),
+            entry(8, "era", 3, None),
+        ]);
+
+        assert_eq!(
+            changes,
+            Changes {
+                comics: BTreeSet::from([12]),
+                items: BTreeSet::new(),
+                occurrences: BTreeSet::from([(12, 4)]),
+                item_images: BTreeSet::from([70_000]),
+                news: BTreeSet::from([12]),
+                eras: BTreeSet::from([3]),
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_entries_are_ignored() {
+        let changes = Changes::from_entries(&[
+            entry(1, "comic", 70_000, None),
+            entry(2, "occurrence", 1, None),
+            entry(3, "token", 1, None),
+        ]);
+
+        assert_eq!(changes, Changes::default());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::models::{ComicId, False, ImageId, ItemId, True};
-use chrono::{DateTime, TimeZone, Utc};
-use database::models::{Comic as DatabaseComic, ItemImageMetadata, LogListEntry};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use database::models::{
+    Comic as DatabaseComic, ComicPredicate, Era as DatabaseEra, ItemImageMetadata, LogListEntry,
+};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
@@ -163,7 +165,7 @@ pub struct PresentComic {
     pub active_storylines: Vec<ActiveStorylineSummary>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Clone, Debug, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct ActiveStorylineSummary {
@@ -175,7 +177,7 @@ pub struct ActiveStorylineSummary {
     pub segments: Vec<StorylineFactSegment>,
 }
 
-#[derive(Debug, Serialize, TS, Eq, PartialEq)]
+#[derive(Clone, Debug, Serialize, TS, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct StorylineFactSegment {
@@ -258,6 +260,17 @@ impl From<UnhydratedItemNavigationData> for ItemNavigationData {
     }
 }
 
+/// Navigation between the comics in which every one of `item_ids` appears.
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ItemSetNavigationData {
+    pub item_ids: Vec<ItemId>,
+    #[serde(flatten)]
+    pub navigation_data: NavigationData,
+    pub count: i32,
+}
+
 #[derive(Debug, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
@@ -314,3 +327,378 @@ pub struct RelatedItem {
     pub id: ItemId,
     pub count: i32,
 }
+
+#[derive(Copy, Clone, Debug, Default, Deserialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export)]
+pub enum TimelineBucketing {
+    #[default]
+    Year,
+    Month,
+    /// Windows of a fixed number of comics.
+    Comics,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ItemTimeline {
+    pub id: ItemId,
+    /// The first comic of the timeline: the storyline's `startComicId`, or
+    /// the first comic for cast and locations. `null` if there are no comics.
+    pub from_comic_id: Option<ComicId>,
+    /// The last comic of the timeline: the one before the storyline's
+    /// `endComicId`, or the latest comic.
+    pub to_comic_id: Option<ComicId>,
+    pub buckets: Vec<TimelineBucket>,
+    /// The longest runs of consecutive comics the item appears in.
+    pub streaks: Vec<AppearanceRun>,
+    /// The longest runs of consecutive comics without the item, after its
+    /// first appearance.
+    pub absences: Vec<AppearanceRun>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct TimelineBucket {
+    /// `2010` or `2010-04` when bucketing by year or month, and the comic
+    /// range when bucketing by comics.
+    pub label: String,
+    pub first_comic_id: ComicId,
+    pub last_comic_id: ComicId,
+    pub comics: i32,
+    pub appearances: i32,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AppearanceRun {
+    pub first_comic_id: ComicId,
+    pub last_comic_id: ComicId,
+    pub length: i32,
+    /// Whether the run reaches the end of the timeline.
+    pub ongoing: bool,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export)]
+pub enum AnomalyCategory {
+    /// A cast member in a comic marked as having no cast.
+    CastInComicWithoutCast,
+    /// A location in a comic marked as having no location.
+    LocationInComicWithoutLocation,
+    /// A storyline in a comic marked as having no storyline.
+    StorylineInComicWithoutStoryline,
+    /// A storyline in a comic outside of its lifecycle.
+    StorylineOutsideLifecycle,
+    /// An item that is not in any comic.
+    ItemWithoutOccurrences,
+    /// A hidden comic that still has items.
+    HiddenComicWithOccurrences,
+    /// A comic published before the comic preceding it.
+    PublishDateOutOfOrder,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AnomalyReport {
+    /// The number of anomalies per category, including categories without
+    /// any.
+    pub categories: Vec<AnomalyCategoryCount>,
+    pub anomalies: Vec<Anomaly>,
+    pub page: u16,
+    pub page_count: u16,
+    pub anomaly_count: i32,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AnomalyCategoryCount {
+    pub category: AnomalyCategory,
+    pub count: i32,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Anomaly {
+    pub category: AnomalyCategory,
+    pub comic_id: Option<ComicId>,
+    pub item_id: Option<ItemId>,
+    pub description: String,
+    pub links: AnomalyLinks,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct AnomalyLinks {
+    /// The API path of the comic's data.
+    pub comic: Option<String>,
+    /// The API path of the item's data.
+    pub item: Option<String>,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SearchResponse {
+    pub results: Vec<SearchResult>,
+    pub page: u16,
+    pub page_count: u16,
+    pub result_count: i32,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SearchResult {
+    pub comic: ComicList,
+    pub relevance: f64,
+    pub snippets: Vec<SearchSnippet>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export)]
+pub enum SearchField {
+    Title,
+    Tagline,
+    News,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SearchSnippet {
+    pub field: SearchField,
+    pub text: String,
+    /// Half-open `[start, end)` ranges into `text` that matched a search term.
+    /// Offsets are in UTF-16 code units, so they can be used directly with
+    /// JavaScript string indexing.
+    pub highlights: Vec<[u32; 2]>,
+}
+
+/// A boolean expression over the items in a comic and the comic's flags, as
+/// accepted by `comicdata/query`. Serialized externally tagged, e.g.
+/// `{ "and": [{ "item": 1 }, { "not": { "itemType": "location" } }] }`.
+#[derive(Debug, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub enum ComicQueryExpression {
+    Item(ItemId),
+    ItemType(ItemType),
+    Flag(ComicFlag),
+    And(Vec<ComicQueryExpression>),
+    Or(Vec<ComicQueryExpression>),
+    Not(Box<ComicQueryExpression>),
+}
+
+impl ComicQueryExpression {
+    /// Nesting depth of the expression; a leaf has depth 1.
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::Item(_) | Self::ItemType(_) | Self::Flag(_) => 1,
+            Self::And(operands) | Self::Or(operands) => {
+                1 + operands.iter().map(Self::depth).max().unwrap_or(0)
+            }
+            Self::Not(operand) => 1 + operand.depth(),
+        }
+    }
+
+    /// Total number of nodes in the expression.
+    pub fn node_count(&self) -> usize {
+        match self {
+            Self::Item(_) | Self::ItemType(_) | Self::Flag(_) => 1,
+            Self::And(operands) | Self::Or(operands) => {
+                1 + operands.iter().map(Self::node_count).sum::<usize>()
+            }
+            Self::Not(operand) => 1 + operand.node_count(),
+        }
+    }
+}
+
+impl From<ComicQueryExpression> for ComicPredicate {
+    fn from(e: ComicQueryExpression) -> Self {
+        match e {
+            ComicQueryExpression::Item(item_id) => Self::Item(item_id.into_inner()),
+            ComicQueryExpression::ItemType(item_type) => Self::ItemType(item_type.into()),
+            ComicQueryExpression::Flag(ComicFlag::Guest) => Self::GuestComic,
+            ComicQueryExpression::Flag(ComicFlag::NonCanon) => Self::NonCanon,
+            ComicQueryExpression::Flag(ComicFlag::Hidden) => Self::Hidden,
+            ComicQueryExpression::And(operands) => {
+                Self::And(operands.into_iter().map(From::from).collect())
+            }
+            ComicQueryExpression::Or(operands) => {
+                Self::Or(operands.into_iter().map(From::from).collect())
+            }
+            ComicQueryExpression::Not(operand) => Self::Not(Box::new((*operand).into())),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export)]
+pub enum ComicFlag {
+    Guest,
+    NonCanon,
+    Hidden,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ComicQueryResponse {
+    pub comics: Vec<ComicList>,
+    pub page: u16,
+    pub page_count: u16,
+    pub comic_count: i32,
+}
+
+/// A named, editor-defined range of comics that the year-based stats can be
+/// grouped by instead of calendar years.
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Era {
+    pub id: u16,
+    pub name: String,
+    pub start_comic_id: ComicId,
+    pub last_comic_id: ComicId,
+}
+
+impl From<DatabaseEra> for Era {
+    fn from(e: DatabaseEra) -> Self {
+        Self {
+            id: e.id,
+            name: e.name,
+            start_comic_id: ComicId::from_trusted(e.start_comic_id),
+            last_comic_id: ComicId::from_trusted(e.last_comic_id),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncResponse {
+    /// Pass this back as `since` to receive the changes made after this
+    /// response.
+    #[ts(type = "number")]
+    pub cursor: u64,
+    /// More changes are pending; request again with `cursor` right away.
+    pub has_more: bool,
+    pub comics: Vec<SyncComic>,
+    pub items: Vec<SyncItem>,
+    pub occurrences: Vec<SyncOccurrence>,
+    pub item_images: Vec<SyncItemImage>,
+    pub news: Vec<SyncNews>,
+    pub eras: Vec<Era>,
+    pub tombstones: SyncTombstones,
+}
+
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "API response struct represents comic flags"
+)]
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncComic {
+    pub comic: ComicId,
+    pub image_type: ImageType,
+    #[ts(type = "string | null")]
+    pub publish_date: Option<DateTime<Utc>>,
+    pub is_accurate_publish_date: bool,
+    pub title: String,
+    pub tagline: Option<String>,
+    pub is_guest_comic: bool,
+    pub is_non_canon: bool,
+    pub has_no_cast: bool,
+    pub has_no_location: bool,
+    pub has_no_storyline: bool,
+    pub has_no_title: bool,
+    pub has_no_tagline: bool,
+}
+
+impl From<DatabaseComic> for SyncComic {
+    fn from(c: DatabaseComic) -> Self {
+        Self {
+            comic: ComicId::from_trusted(c.id),
+            image_type: ImageType::from_trusted(c.image_type),
+            publish_date: c.publish_date.map(|nd| Utc.from_utc_datetime(&nd)),
+            is_accurate_publish_date: c.is_accurate_publish_date != 0,
+            title: c.title,
+            tagline: c.tagline,
+            is_guest_comic: c.is_guest_comic != 0,
+            is_non_canon: c.is_non_canon != 0,
+            has_no_cast: c.has_no_cast != 0,
+            has_no_location: c.has_no_location != 0,
+            has_no_storyline: c.has_no_storyline != 0,
+            has_no_title: c.has_no_title != 0,
+            has_no_tagline: c.has_no_tagline != 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncItem {
+    pub id: ItemId,
+    pub short_name: String,
+    pub name: String,
+    pub r#type: ItemType,
+    #[ts(type = "string")]
+    pub color: ItemColor,
+    pub primary_image: Option<ImageId>,
+    pub start_comic_id: Option<ComicId>,
+    pub end_comic_id: Option<ComicId>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncOccurrence {
+    pub comic: ComicId,
+    pub item: ItemId,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncItemImage {
+    pub id: ImageId,
+    pub item_id: ItemId,
+    pub crc32c_hash: u32,
+}
+
+#[derive(Debug, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncNews {
+    pub comic: ComicId,
+    #[ts(type = "string")]
+    pub last_updated: NaiveDate,
+    pub news: String,
+}
+
+/// Entities that were deleted since the requested cursor (or are no longer
+/// visible to the caller) and should be dropped from the client's copy.
+#[derive(Debug, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SyncTombstones {
+    pub comics: Vec<ComicId>,
+    pub items: Vec<ItemId>,
+    pub occurrences: Vec<SyncOccurrence>,
+    pub item_images: Vec<ImageId>,
+    pub news: Vec<ComicId>,
+    pub eras: Vec<u16>,
+}
@@ -1,11 +1,79 @@
 use std::collections::HashMap;
 
-use crate::api::v3::models::ItemColor;
+use crate::api::v3::models::{Exclusion, ItemColor, ItemType};
 use crate::models::{ComicId, ItemId};
-use serde::Serialize;
+use chrono::NaiveDate;
+use database::models::stats::{
+    StatsFilter as DatabaseStatsFilter, StatsGrouping as DatabaseStatsGrouping,
+};
+use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-#[derive(Debug, Serialize, TS)]
+/// Query parameters accepted by every stats endpoint, restricting the comics
+/// and occurrences the stats are computed over. Bounds are inclusive.
+#[derive(Clone, Debug, Default, Deserialize, TS)]
+#[serde(default, rename_all = "kebab-case")]
+#[ts(export)]
+pub struct StatsFilter {
+    #[ts(optional = nullable)]
+    pub exclude: Vec<Exclusion>,
+    #[ts(optional)]
+    pub from_comic: Option<u16>,
+    #[ts(optional)]
+    pub to_comic: Option<u16>,
+    #[ts(optional, type = "string")]
+    pub from_date: Option<NaiveDate>,
+    #[ts(optional, type = "string")]
+    pub to_date: Option<NaiveDate>,
+    #[ts(optional)]
+    pub item_type: Option<ItemType>,
+}
+
+impl From<StatsFilter> for DatabaseStatsFilter {
+    fn from(filter: StatsFilter) -> Self {
+        Self {
+            exclude_guest_comics: filter.exclude.iter().any(|e| matches!(e, Exclusion::Guest)),
+            exclude_non_canon_comics: filter
+                .exclude
+                .iter()
+                .any(|e| matches!(e, Exclusion::NonCanon)),
+            from_comic: filter.from_comic,
+            to_comic: filter.to_comic,
+            from_date: filter.from_date,
+            to_date: filter.to_date,
+            item_type: filter.item_type.map(Into::into),
+        }
+    }
+}
+
+/// What the year-based stats endpoints group by. With `era`, the `year` of
+/// each entry is the 1-based position of the era in `eradata/`.
+#[derive(Copy, Clone, Debug, Default, Deserialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export)]
+pub enum StatsGrouping {
+    #[default]
+    Year,
+    Era,
+}
+
+impl From<StatsGrouping> for DatabaseStatsGrouping {
+    fn from(grouping: StatsGrouping) -> Self {
+        match grouping {
+            StatsGrouping::Year => Self::Year,
+            StatsGrouping::Era => Self::Era,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct StatsGroupingQuery {
+    #[ts(optional)]
+    pub grouping: Option<StatsGrouping>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct ItemStats {
@@ -16,7 +84,7 @@ pub struct ItemStats {
     pub appearances: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CoAppearancePair {
@@ -25,7 +93,7 @@ pub struct CoAppearancePair {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CoAppearanceCharacterMeta {
@@ -33,7 +101,7 @@ pub struct CoAppearanceCharacterMeta {
     pub appearances: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CoAppearancesResponse {
@@ -41,7 +109,7 @@ pub struct CoAppearancesResponse {
     pub pairs: Vec<CoAppearancePair>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CharacterMeta {
@@ -50,7 +118,7 @@ pub struct CharacterMeta {
     pub color: ItemColor,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct YearlyRankEntry {
@@ -58,7 +126,7 @@ pub struct YearlyRankEntry {
     pub appearances: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct YearlySpotlightYear {
@@ -66,7 +134,7 @@ pub struct YearlySpotlightYear {
     pub characters: Vec<YearlyRankEntry>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct YearlySpotlightResponse {
@@ -74,7 +142,7 @@ pub struct YearlySpotlightResponse {
     pub years: Vec<YearlySpotlightYear>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct DebutsPerYear {
@@ -83,7 +151,7 @@ pub struct DebutsPerYear {
     pub location_debuts: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct YearlyOverview {
@@ -92,7 +160,7 @@ pub struct YearlyOverview {
     pub new_cast: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct MonthlyComics {
@@ -100,7 +168,7 @@ pub struct MonthlyComics {
     pub comics: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct DailyComics {
@@ -108,7 +176,7 @@ pub struct DailyComics {
     pub comics: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct PublicationCalendar {
@@ -116,7 +184,7 @@ pub struct PublicationCalendar {
     pub daily: Vec<DailyComics>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct ComebackCharacter {
@@ -127,7 +195,7 @@ pub struct ComebackCharacter {
     pub gap_days: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationAffinityCharacter {
@@ -136,7 +204,7 @@ pub struct LocationAffinityCharacter {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationAffinity {
@@ -145,7 +213,7 @@ pub struct LocationAffinity {
     pub top_characters: Vec<LocationAffinityCharacter>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct MostCrowdedComic {
@@ -153,7 +221,7 @@ pub struct MostCrowdedComic {
     pub cast_count: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct AvgCastPerYear {
@@ -161,7 +229,7 @@ pub struct AvgCastPerYear {
     pub avg_cast_size: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CrowdedComicsResponse {
@@ -169,7 +237,7 @@ pub struct CrowdedComicsResponse {
     pub avg_per_year: Vec<AvgCastPerYear>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationSpotlightYear {
@@ -177,7 +245,7 @@ pub struct LocationSpotlightYear {
     pub locations: Vec<YearlyRankEntry>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationSpotlightResponse {
@@ -185,7 +253,7 @@ pub struct LocationSpotlightResponse {
     pub years: Vec<LocationSpotlightYear>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct PublicationGap {
@@ -194,7 +262,7 @@ pub struct PublicationGap {
     pub gap_days: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct DebutCharacter {
@@ -202,7 +270,7 @@ pub struct DebutCharacter {
     pub name: String,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct DebutYear {
@@ -210,7 +278,7 @@ pub struct DebutYear {
     pub characters: Vec<DebutCharacter>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct EnsembleRatio {
@@ -222,7 +290,7 @@ pub struct EnsembleRatio {
     pub total: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CharacterRegularity {
@@ -233,7 +301,7 @@ pub struct CharacterRegularity {
     pub stddev_gap_days: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationRegularity {
@@ -244,7 +312,7 @@ pub struct LocationRegularity {
     pub stddev_gap_days: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationCoOccurrenceEntry {
@@ -253,7 +321,7 @@ pub struct LocationCoOccurrenceEntry {
     pub appearances: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationCoOccurrencePair {
@@ -262,7 +330,7 @@ pub struct LocationCoOccurrencePair {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationCoOccurrenceResponse {
@@ -270,7 +338,7 @@ pub struct LocationCoOccurrenceResponse {
     pub pairs: Vec<LocationCoOccurrencePair>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct BestFriendPair {
@@ -279,7 +347,7 @@ pub struct BestFriendPair {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct BestFriendResponse {
@@ -287,7 +355,7 @@ pub struct BestFriendResponse {
     pub pairs: Vec<BestFriendPair>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct SocialHubEntry {
@@ -297,7 +365,7 @@ pub struct SocialHubEntry {
     pub distinct_partners: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct TrendingItem {
@@ -308,7 +376,7 @@ pub struct TrendingItem {
     pub career_years: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CastTurnoverYear {
@@ -319,7 +387,7 @@ pub struct CastTurnoverYear {
     pub dropped_chars: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CharacterSeasonEntry {
@@ -328,7 +396,7 @@ pub struct CharacterSeasonEntry {
     pub monthly: Vec<u32>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct BreakoutYear {
@@ -340,7 +408,7 @@ pub struct BreakoutYear {
     pub ratio: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct ComebackLocation {
@@ -351,7 +419,7 @@ pub struct ComebackLocation {
     pub gap_days: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationSeasonEntry {
@@ -360,7 +428,7 @@ pub struct LocationSeasonEntry {
     pub monthly: Vec<u32>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationBreakoutYear {
@@ -372,7 +440,7 @@ pub struct LocationBreakoutYear {
     pub ratio: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationSocialHubEntry {
@@ -382,7 +450,7 @@ pub struct LocationSocialHubEntry {
     pub distinct_characters: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LocationTurnoverYear {
@@ -393,7 +461,7 @@ pub struct LocationTurnoverYear {
     pub dropped_locations: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct HomeTurfLocation {
@@ -402,7 +470,7 @@ pub struct HomeTurfLocation {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct CharacterHomeTurfEntry {
@@ -412,7 +480,7 @@ pub struct CharacterHomeTurfEntry {
     pub character_appearances: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct PairEvolutionYear {
@@ -420,7 +488,64 @@ pub struct PairEvolutionYear {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ComparisonResponse {
+    pub a: ComparedItem,
+    pub b: ComparedItem,
+    pub comics_together: u32,
+    pub first_together: Option<ComicId>,
+    pub latest_together: Option<ComicId>,
+    pub years: Vec<ComparisonYear>,
+    /// The locations of the comics the two items share, most shared first.
+    pub meeting_locations: Vec<MeetingLocation>,
+    /// The most comics in a row without the two together since they first
+    /// met, `null` if they never met or were never apart.
+    pub longest_apart: Option<ComparisonGap>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ComparedItem {
+    pub id: ItemId,
+    pub name: String,
+    pub appearances: u32,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ComparisonYear {
+    pub year: i32,
+    pub a_appearances: u32,
+    pub b_appearances: u32,
+    pub comics_together: u32,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct MeetingLocation {
+    pub id: ItemId,
+    pub name: String,
+    pub comics_together: u32,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ComparisonGap {
+    /// The last comic the two were in together before the gap.
+    pub after_comic: ComicId,
+    /// The first comic the two were in together again, `null` if they have
+    /// not met since.
+    pub until_comic: Option<ComicId>,
+    pub comics: u32,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct LonerEntry {
@@ -430,7 +555,7 @@ pub struct LonerEntry {
     pub avg_co_cast: f64,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct NeverMetPair {
@@ -443,7 +568,7 @@ pub struct NeverMetPair {
     pub comics_together: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct PublishTimeYear {
@@ -451,7 +576,7 @@ pub struct PublishTimeYear {
     pub hour_counts: Vec<u32>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct ScheduleEvolutionYear {
@@ -459,7 +584,7 @@ pub struct ScheduleEvolutionYear {
     pub dow_counts: Vec<u32>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct PublicationStreak {
@@ -470,7 +595,7 @@ pub struct PublicationStreak {
     pub break_date: Option<String>,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct MonthlyHeatmapEntry {
@@ -479,7 +604,7 @@ pub struct MonthlyHeatmapEntry {
     pub comics: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct MilestoneComic {
@@ -490,7 +615,7 @@ pub struct MilestoneComic {
     pub is_non_canon: bool,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct TopRankedStint {
@@ -500,10 +625,76 @@ pub struct TopRankedStint {
     pub appearances_at_takeover: u32,
 }
 
-#[derive(Debug, Serialize, TS)]
+#[derive(Debug, Clone, Serialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
 pub struct TopRankedOverTimeResponse {
     pub stints: Vec<TopRankedStint>,
     pub characters: HashMap<u16, CharacterMeta>,
 }
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StorylineStats {
+    pub id: ItemId,
+    pub name: String,
+    /// The first comic of the storyline's lifecycle within the filter.
+    pub first_comic: ComicId,
+    /// The last comic of the storyline's lifecycle within the filter.
+    pub last_comic: ComicId,
+    /// Whether the storyline has no end yet.
+    pub ongoing: bool,
+    pub span_comics: u32,
+    /// Days between the first and last comic, `null` if either has no
+    /// publish date.
+    pub span_days: Option<u32>,
+    pub featured_comics: u32,
+    /// `featuredComics` divided by `spanComics`.
+    pub feature_density: f64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StorylineConcurrency {
+    pub from_comic: ComicId,
+    /// `null` if the segment lasts until the latest comic.
+    pub to_comic_exclusive: Option<ComicId>,
+    pub storylines: u32,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StorylineDormantGap {
+    pub id: ItemId,
+    pub name: String,
+    /// The last comic featuring the storyline before the gap.
+    pub after_comic: ComicId,
+    /// The next comic featuring the storyline, `null` if it has not been
+    /// featured again within its lifecycle.
+    pub until_comic: Option<ComicId>,
+    pub comics: u32,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StorylineCast {
+    pub id: ItemId,
+    pub name: String,
+    pub featured_comics: u32,
+    pub top_characters: Vec<StorylineCastMember>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct StorylineCastMember {
+    pub id: ItemId,
+    pub name: String,
+    pub comics_together: u32,
+    /// The share of the storyline's featured comics the character is in.
+    pub share: f64,
+}
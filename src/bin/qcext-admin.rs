@@ -0,0 +1,384 @@
+//! Command line tool for server operations that run directly against the database.
+
+use anyhow::{Context as _, Result, bail};
+use database::DbPool;
+use database::dump::{Dump, DumpError, ExportOptions, ImportOptions};
+use database::fsck;
+use database::models::stats::TopRankedStintRow;
+use database::models::{ItemType, Token};
+use reqwest::StatusCode;
+use shared::token_permissions;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+mod environment {
+    use ilyvion_util::environment::define_environment;
+
+    define_environment! {
+        pub database_url();
+    }
+}
+
+const USAGE: &str = "\
+usage: qcext-admin <command> [arguments]
+
+commands:
+    token create <identifier> [--permission <name>]...
+                                create a token with the given permissions and print it;
+                                <name> is e.g. CAN_CHANGE_COMIC_DATA
+    migrate                     run the pending database migrations
+    stints refresh              recompute the rank stints cache of every item type
+    comic update                ask the server to run the comic updater now
+    news sweep                  ask the server to check every comic with outdated news
+    export [--public] <file>    write a dump of the dataset to <file> (`-` for stdout);
+                                --public leaves out hidden comics
+    import [--dry-run] <file>   load a dump from <file> (`-` for stdin) into the database;
+                                --dry-run reports what would change without saving it
+    fsck [--repair]             check the database for broken references and stale cache
+                                bookkeeping; --repair fixes them in a single transaction
+
+`comic update` and `news sweep` go through the running server's API, at
+QCEXT_SERVER_URL (e.g. http://localhost:3000), with the token in QCEXT_TOKEN.
+
+exit codes:
+    0   success
+    1   failure
+    2   invalid arguments or environment
+    3   problems found (a dump failing validation, fsck without --repair)
+    4   the server is rate limiting the request; try again later";
+
+/// An error with its own exit code.
+#[derive(Debug)]
+enum Failure {
+    Usage(String),
+    ProblemsFound(String),
+    RateLimited(String),
+}
+
+impl Failure {
+    const fn exit_code(&self) -> u8 {
+        match self {
+            Self::Usage(_) => 2,
+            Self::ProblemsFound(_) => 3,
+            Self::RateLimited(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) => write!(f, "{message}\n\n{USAGE}"),
+            Self::ProblemsFound(message) | Self::RateLimited(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn usage(message: impl Into<String>) -> anyhow::Error {
+    Failure::Usage(message.into()).into()
+}
+
+#[actix_web::main]
+async fn main() -> ExitCode {
+    environment::init_dotenv();
+
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(e.downcast_ref::<Failure>().map_or(1, Failure::exit_code))
+        }
+    }
+}
+
+async fn run() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().ok_or_else(|| usage("missing <command>"))?;
+    let args: Vec<String> = args.collect();
+
+    match (&*command, args.first().map(String::as_str)) {
+        ("export", _) => export(&args).await,
+        ("import", _) => import(&args).await,
+        ("fsck", _) => fsck(&args).await,
+        ("token", Some("create")) => create_token(&args[1..]).await,
+        ("migrate", _) => migrate(&args).await,
+        ("stints", Some("refresh")) => refresh_stints(&args[1..]).await,
+        ("comic", Some("update")) => {
+            no_arguments(&args[1..])?;
+            server_request("comicdata/advance/run-updater").await
+        }
+        ("news", Some("sweep")) => {
+            no_arguments(&args[1..])?;
+            server_request("admin/news-sweep").await
+        }
+        _ => Err(usage(format!("unknown command {command:?}"))),
+    }
+}
+
+fn no_arguments(args: &[String]) -> Result<()> {
+    match args.first() {
+        Some(arg) => Err(usage(format!("unexpected argument {arg:?}"))),
+        None => Ok(()),
+    }
+}
+
+async fn connect() -> Result<DbPool> {
+    // The accessor panics on a missing variable; report it as a usage error.
+    if std::env::var_os("DATABASE_URL").is_none() {
+        return Err(usage("DATABASE_URL is not set"));
+    }
+    DbPool::try_create(environment::database_url(), 1, 1)
+        .await
+        .context("could not connect to the database")
+}
+
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the dataset cannot be read
+/// or the dump cannot be written.
+async fn export(args: &[String]) -> Result<()> {
+    let mut include_hidden = true;
+    let mut path = None;
+    for arg in args {
+        match &**arg {
+            "--public" => include_hidden = false,
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return Err(usage(format!("unexpected argument {arg:?}"))),
+        }
+    }
+    let path = path.ok_or_else(|| usage("export: missing <file>"))?;
+
+    let pool = connect().await?;
+    let mut conn = pool.acquire().await?;
+    let dump = Dump::load(&mut conn, ExportOptions { include_hidden })
+        .await
+        .context("failed to read the dataset")?;
+
+    let writer: Box<dyn Write> = if path == "-" {
+        Box::new(std::io::stdout().lock())
+    } else {
+        Box::new(File::create(path).with_context(|| format!("failed to create {path}"))?)
+    };
+    dump.write_to(BufWriter::new(writer))
+        .context("failed to write the dump")?;
+
+    eprintln!("Exported {:?}", dump.manifest.counts);
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the dump cannot be read,
+/// fails validation or cannot be written to the database.
+async fn import(args: &[String]) -> Result<()> {
+    let mut dry_run = false;
+    let mut path = None;
+    for arg in args {
+        match &**arg {
+            "--dry-run" => dry_run = true,
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return Err(usage(format!("unexpected argument {arg:?}"))),
+        }
+    }
+    let path = path.ok_or_else(|| usage("import: missing <file>"))?;
+
+    let reader: Box<dyn Read> = if path == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(File::open(path).with_context(|| format!("failed to open {path}"))?)
+    };
+    let dump = Dump::read_from(BufReader::new(reader)).context("failed to read the dump")?;
+
+    let pool = connect().await?;
+    let mut conn = pool.acquire().await?;
+    let report = match dump.import(&mut conn, ImportOptions { dry_run }).await {
+        Ok(report) => report,
+        Err(DumpError::Integrity(problems)) => {
+            for problem in &problems {
+                eprintln!("{problem}");
+            }
+            return Err(Failure::ProblemsFound(format!(
+                "the dump failed validation with {} problem(s)",
+                problems.len()
+            ))
+            .into());
+        }
+        Err(e) => return Err(e).context("failed to import the dump"),
+    };
+
+    eprintln!("comics:       {:?}", report.comics);
+    eprintln!("items:        {:?}", report.items);
+    eprintln!("item images:  {:?}", report.item_images);
+    eprintln!("occurrences:  {:?}", report.occurrences);
+    eprintln!("news:         {:?}", report.news);
+    eprintln!("log entries:  {:?}", report.log_entries);
+    if report.committed {
+        eprintln!("Import committed");
+    } else {
+        eprintln!("Dry run; nothing was saved");
+    }
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, the checks or repairs
+/// fail, or problems were found and not repaired.
+async fn fsck(args: &[String]) -> Result<()> {
+    let mut repair = false;
+    for arg in args {
+        match &**arg {
+            "--repair" => repair = true,
+            _ => return Err(usage(format!("unexpected argument {arg:?}"))),
+        }
+    }
+
+    let pool = connect().await?;
+    let mut conn = pool.acquire().await?;
+    let problems = fsck::check(&mut conn)
+        .await
+        .context("failed to check the database")?;
+
+    if problems.is_empty() {
+        eprintln!("No problems found");
+        return Ok(());
+    }
+    if !repair {
+        for problem in &problems {
+            println!("{problem}");
+        }
+        return Err(Failure::ProblemsFound(format!(
+            "found {} problem(s); run with --repair to fix them",
+            problems.len()
+        ))
+        .into());
+    }
+
+    fsck::repair(&mut conn, &problems, |problem| {
+        println!("{problem}: {}", problem.repair_description());
+    })
+    .await
+    .context("failed to repair the database; nothing was changed")?;
+    eprintln!("Repaired {} problem(s)", problems.len());
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// Returns an error if the arguments are invalid, a token with the same
+/// identifier exists or the token cannot be saved.
+async fn create_token(args: &[String]) -> Result<()> {
+    let mut identifier = None;
+    let mut permissions = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--permission" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| usage("--permission: missing <name>"))?;
+                let permission = token_permissions::GRANTABLE
+                    .into_iter()
+                    .find(|p| p == name)
+                    .ok_or_else(|| {
+                        usage(format!(
+                            "unknown permission {name:?}; expected one of {}",
+                            token_permissions::GRANTABLE.join(", ")
+                        ))
+                    })?;
+                permissions.push(permission);
+            }
+            _ if identifier.is_none() => identifier = Some(arg.as_str()),
+            _ => return Err(usage(format!("unexpected argument {arg:?}"))),
+        }
+    }
+    let identifier = identifier.ok_or_else(|| usage("token create: missing <identifier>"))?;
+
+    let pool = connect().await?;
+    if Token::id_by_identifier(&*pool, identifier).await?.is_some() {
+        bail!("a token named {identifier:?} already exists");
+    }
+
+    let token = uuid::Builder::from_random_bytes(rand::random()).into_uuid();
+    Token::create_with_permissions(&*pool, &token.to_string(), identifier, &permissions)
+        .await
+        .context("failed to save the token")?;
+
+    println!("{token}");
+    eprintln!("Created token {identifier:?} with {permissions:?}");
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or a migration fails.
+async fn migrate(args: &[String]) -> Result<()> {
+    no_arguments(args)?;
+
+    let pool = connect().await?;
+    database::migrate(&pool)
+        .await
+        .context("failed to run the migrations")?;
+    eprintln!("Migrations are up to date");
+
+    Ok(())
+}
+
+/// # Errors
+///
+/// Returns an error if the arguments are invalid or a refresh fails.
+async fn refresh_stints(args: &[String]) -> Result<()> {
+    no_arguments(args)?;
+
+    let pool = connect().await?;
+    let mut conn = pool.acquire().await?;
+    for item_type in ItemType::ALL {
+        TopRankedStintRow::refresh_cache(&mut conn, item_type)
+            .await
+            .with_context(|| format!("failed to refresh the {} stints", item_type.as_str()))?;
+        eprintln!("Refreshed the {} stints", item_type.as_str());
+    }
+
+    Ok(())
+}
+
+/// Sends an authorized `POST` to the v3 API endpoint at `path` and prints
+/// the server's answer.
+///
+/// # Errors
+///
+/// Returns an error if the server is not configured, cannot be reached or
+/// does not accept the request.
+async fn server_request(path: &str) -> Result<()> {
+    let server_url =
+        std::env::var("QCEXT_SERVER_URL").map_err(|_| usage("QCEXT_SERVER_URL is not set"))?;
+    let token = std::env::var("QCEXT_TOKEN").map_err(|_| usage("QCEXT_TOKEN is not set"))?;
+
+    let url = format!("{}/api/v3/{path}", server_url.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .post(&url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .with_context(|| format!("failed to reach {url}"))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => Err(Failure::RateLimited(body).into()),
+        _ if status.is_success() => {
+            let message = serde_json::from_str::<String>(&body).unwrap_or(body);
+            eprintln!("{message}");
+            Ok(())
+        }
+        _ => bail!("the server answered {status}: {body}"),
+    }
+}
@@ -0,0 +1,230 @@
+use actix_web::{HttpResponse, web};
+use database::DbPool;
+use serde::Serialize;
+use sqlx::Connection as _;
+use std::time::Duration;
+use tokio::time::error::Elapsed;
+use tokio::time::timeout;
+
+use crate::util::{BackgroundService, ServiceHealth, ServiceState, ServiceStatus};
+
+/// How long the readiness check waits on the database.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/healthz").route(web::get().to(healthz)))
+        .service(web::resource("/readyz").route(web::get().to(readyz)));
+}
+
+/// Liveness: the server is up and answering requests.
+async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(Liveness { status: "ok" })
+}
+
+/// Readiness: the database is reachable and migrated, and the background
+/// services are running. Answers `503 Service Unavailable` when it isn't.
+#[tracing::instrument(skip(pool, health))]
+async fn readyz(pool: web::Data<DbPool>, health: web::Data<ServiceHealth>) -> HttpResponse {
+    let database = timeout(DATABASE_TIMEOUT, ping(&pool))
+        .await
+        .unwrap_or_else(timed_out);
+    let migrations = if database.is_ok() {
+        timeout(DATABASE_TIMEOUT, database::pending_migrations(&pool))
+            .await
+            .map_or_else(timed_out, |result| result.map_err(|e| e.to_string()))
+    } else {
+        Err(String::from("the database is unreachable"))
+    };
+    let services = BackgroundService::ALL
+        .into_iter()
+        .map(|service| (service, health.status(service)))
+        .collect();
+
+    let readiness = build_readiness(database, migrations, services);
+    if readiness.status == ReadinessStatus::Unavailable {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    } else {
+        HttpResponse::Ok().json(readiness)
+    }
+}
+
+fn timed_out<T>(_: Elapsed) -> Result<T, String> {
+    Err(format!("timed out after {DATABASE_TIMEOUT:?}"))
+}
+
+async fn ping(pool: &DbPool) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    conn.ping().await.map_err(|e| e.to_string())
+}
+
+fn build_readiness(
+    database: Result<(), String>,
+    migrations: Result<Vec<i64>, String>,
+    services: Vec<(BackgroundService, ServiceStatus)>,
+) -> Readiness {
+    let database = Check {
+        ok: database.is_ok(),
+        error: database.err(),
+    };
+    let migrations = match migrations {
+        Ok(pending) => MigrationsCheck {
+            ok: pending.is_empty(),
+            pending,
+            error: None,
+        },
+        Err(e) => MigrationsCheck {
+            ok: false,
+            pending: Vec::new(),
+            error: Some(e),
+        },
+    };
+    let background_services: Vec<_> = services
+        .into_iter()
+        .map(|(service, status)| BackgroundServiceCheck {
+            name: service.name(),
+            state: status.state,
+            last_success_seconds_ago: status.last_success_age.map(|age| age.as_secs()),
+            consecutive_failures: status.consecutive_failures,
+            last_error: status.last_error,
+        })
+        .collect();
+
+    let status = if !database.ok
+        || !migrations.ok
+        || background_services
+            .iter()
+            .any(|s| s.state == ServiceState::Stalled)
+    {
+        ReadinessStatus::Unavailable
+    } else if background_services
+        .iter()
+        .any(|s| s.state == ServiceState::Degraded)
+    {
+        ReadinessStatus::Degraded
+    } else {
+        ReadinessStatus::Ready
+    };
+
+    Readiness {
+        status,
+        database,
+        migrations,
+        background_services,
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Liveness {
+    status: &'static str,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ReadinessStatus {
+    Ready,
+    /// Usable, but a background service is failing.
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Readiness {
+    status: ReadinessStatus,
+    database: Check,
+    migrations: MigrationsCheck,
+    background_services: Vec<BackgroundServiceCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MigrationsCheck {
+    ok: bool,
+    pending: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackgroundServiceCheck {
+    name: &'static str,
+    state: ServiceState,
+    last_success_seconds_ago: Option<u64>,
+    consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(state: ServiceState) -> ServiceStatus {
+        ServiceStatus {
+            state,
+            last_success_age: Some(Duration::from_secs(12)),
+            consecutive_failures: u32::from(state == ServiceState::Degraded),
+            last_error: None,
+        }
+    }
+
+    fn services(comic_updater: ServiceState) -> Vec<(BackgroundService, ServiceStatus)> {
+        vec![
+            (BackgroundService::ComicUpdater, status(comic_updater)),
+            (BackgroundService::NewsUpdater, status(ServiceState::Ok)),
+            (
+                BackgroundService::RankStintsRefresher,
+                status(ServiceState::Ok),
+            ),
+        ]
+    }
+
+    #[test]
+    fn is_ready_when_everything_is_up() {
+        let readiness = build_readiness(Ok(()), Ok(Vec::new()), services(ServiceState::Ok));
+
+        assert_eq!(readiness.status, ReadinessStatus::Ready);
+        let json = serde_json::to_value(&readiness).unwrap();
+        assert_eq!(json["backgroundServices"][0]["name"], "comic-updater");
+        assert_eq!(json["backgroundServices"][0]["state"], "ok");
+        assert_eq!(json["backgroundServices"][0]["lastSuccessSecondsAgo"], 12);
+    }
+
+    #[test]
+    fn a_failing_updater_degrades_and_a_stalled_one_is_unavailable() {
+        let readiness = build_readiness(Ok(()), Ok(Vec::new()), services(ServiceState::Degraded));
+        assert_eq!(readiness.status, ReadinessStatus::Degraded);
+
+        let readiness = build_readiness(Ok(()), Ok(Vec::new()), services(ServiceState::Stalled));
+        assert_eq!(readiness.status, ReadinessStatus::Unavailable);
+    }
+
+    #[test]
+    fn is_unavailable_without_a_migrated_database() {
+        let readiness = build_readiness(
+            Ok(()),
+            Ok(vec![20_240_101_000_000]),
+            services(ServiceState::Disabled),
+        );
+        assert_eq!(readiness.status, ReadinessStatus::Unavailable);
+        assert_eq!(readiness.migrations.pending, [20_240_101_000_000]);
+
+        let readiness = build_readiness(
+            Err(String::from("connection refused")),
+            Err(String::from("the database is unreachable")),
+            services(ServiceState::Disabled),
+        );
+        assert_eq!(readiness.status, ReadinessStatus::Unavailable);
+        assert_eq!(
+            readiness.database.error.as_deref(),
+            Some("connection refused")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use actix_web::{HttpResponse, web};
+use database::DbPool;
+
+use crate::util::{
+    BackgroundService, CacheCounters, MetricsWriter, NewsUpdater, RequestMetrics, ServiceHealth,
+    StatsResponseCache, TokenPermissionsCache,
+};
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/metrics").route(web::get().to(metrics)));
+}
+
+/// The server's metrics, in the Prometheus text exposition format.
+async fn metrics(
+    pool: web::Data<DbPool>,
+    requests: web::Data<RequestMetrics>,
+    news_updater: web::Data<NewsUpdater>,
+    token_cache: web::Data<TokenPermissionsCache>,
+    stats_cache: web::Data<StatsResponseCache>,
+    health: web::Data<ServiceHealth>,
+) -> HttpResponse {
+    let mut out = MetricsWriter::new();
+    requests.write_to(&mut out);
+    write_pool_metrics(&mut out, &pool);
+    write_service_metrics(&mut out, &news_updater, &health);
+    write_cache_metrics(&mut out, &token_cache, &stats_cache);
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(out.finish())
+}
+
+fn write_pool_metrics(out: &mut MetricsWriter, pool: &DbPool) {
+    let open = pool.size();
+    let idle = u32::try_from(pool.num_idle()).unwrap_or(u32::MAX);
+
+    out.metric(
+        "qcext_db_pool_connections",
+        "gauge",
+        "Open database connections, by state.",
+    );
+    out.sample("qcext_db_pool_connections", &[("state", "idle")], idle);
+    out.sample(
+        "qcext_db_pool_connections",
+        &[("state", "in_use")],
+        open.saturating_sub(idle),
+    );
+
+    out.metric(
+        "qcext_db_pool_max_connections",
+        "gauge",
+        "The most database connections the pool will open.",
+    );
+    out.sample(
+        "qcext_db_pool_max_connections",
+        &[],
+        pool.options().get_max_connections(),
+    );
+}
+
+fn write_service_metrics(
+    out: &mut MetricsWriter,
+    news_updater: &NewsUpdater,
+    health: &ServiceHealth,
+) {
+    out.metric(
+        "qcext_news_update_queue_depth",
+        "gauge",
+        "Comics waiting for a news update check.",
+    );
+    out.sample("qcext_news_update_queue_depth", &[], news_updater.pending());
+
+    out.metric(
+        "qcext_background_service_runs_total",
+        "counter",
+        "Runs of the background services, by service and outcome.",
+    );
+    for service in BackgroundService::ALL {
+        let counts = health.run_counts(service);
+        for (outcome, count) in [("success", counts.successes), ("failure", counts.failures)] {
+            out.sample(
+                "qcext_background_service_runs_total",
+                &[("service", service.name()), ("outcome", outcome)],
+                count,
+            );
+        }
+    }
+
+    out.metric(
+        "qcext_background_service_consecutive_failures",
+        "gauge",
+        "Runs of the background services that failed since the last successful one.",
+    );
+    for service in BackgroundService::ALL {
+        out.sample(
+            "qcext_background_service_consecutive_failures",
+            &[("service", service.name())],
+            health.status(service).consecutive_failures,
+        );
+    }
+}
+
+fn write_cache_metrics(
+    out: &mut MetricsWriter,
+    token_cache: &TokenPermissionsCache,
+    stats_cache: &StatsResponseCache,
+) {
+    out.metric(
+        "qcext_cache_lookups_total",
+        "counter",
+        "Lookups in the in-memory caches, by cache and result.",
+    );
+    let caches: [(&str, &CacheCounters); 2] = [
+        ("token_permissions", token_cache.lookups()),
+        ("stats_responses", stats_cache.lookups()),
+    ];
+    for (cache, lookups) in caches {
+        for (result, count) in [("hit", lookups.hits()), ("miss", lookups.misses())] {
+            out.sample(
+                "qcext_cache_lookups_total",
+                &[("cache", cache), ("result", result)],
+                count,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn reports_the_background_services_and_caches() {
+        let news_updater = NewsUpdater::new();
+        let health = ServiceHealth::new(true);
+        health.record_failure(BackgroundService::ComicUpdater, &"front page is empty");
+        health.record_success(BackgroundService::ComicUpdater);
+        let token_cache = TokenPermissionsCache::new();
+        token_cache.set(String::from("token"), HashSet::new());
+        let _ = token_cache.get("token");
+        let _ = token_cache.get("other");
+        let stats_cache = StatsResponseCache::new(true);
+
+        let mut out = MetricsWriter::new();
+        write_service_metrics(&mut out, &news_updater, &health);
+        write_cache_metrics(&mut out, &token_cache, &stats_cache);
+        let text = out.finish();
+
+        for line in [
+            "qcext_news_update_queue_depth 0",
+            r#"qcext_background_service_runs_total{service="comic-updater",outcome="success"} 1"#,
+            r#"qcext_background_service_runs_total{service="comic-updater",outcome="failure"} 1"#,
+            r#"qcext_background_service_consecutive_failures{service="comic-updater"} 0"#,
+            r#"qcext_cache_lookups_total{cache="token_permissions",result="hit"} 1"#,
+            r#"qcext_cache_lookups_total{cache="token_permissions",result="miss"} 1"#,
+            r#"qcext_cache_lookups_total{cache="stats_responses",result="miss"} 0"#,
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line} in\n{text}");
+        }
+    }
+}
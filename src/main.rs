@@ -1,31 +1,36 @@
 //! Questionable Content Extensions server.
 
 use crate::models::Token;
-use crate::util::{ComicUpdater, ComicUpdaterTrigger, Either, NewsUpdater, TokenPermissionsCache};
+use crate::util::{
+    BackgroundService, ComicUpdater, ComicUpdaterTrigger, Either, ExportArchiveCache, NewsUpdater,
+    OtlpProtocol, RequestMetrics, ServiceHealth, StatsResponseCache, TokenPermissionsCache,
+    TracingConfig, TracingExporter, record_request_metrics,
+};
 use actix_files::{Files, NamedFile};
 use actix_http::body::MessageBody;
 use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::from_fn;
 use actix_web::web::PayloadConfig;
 use actix_web::{App, Error, FromRequest, HttpMessage, HttpServer, error, web};
 use actix_web_grants::GrantsMiddleware;
 use anyhow::{Context as _, Result, anyhow};
 use database::DbPool;
+use database::models::ItemType as DbItemType;
 use database::models::Token as DatabaseToken;
 use database::models::stats::TopRankedStintRow as DbTopRankedStintRow;
 use futures::stream::{FuturesUnordered, StreamExt};
 use futures::{FutureExt, pin_mut};
 use opentelemetry::KeyValue;
 use opentelemetry::sdk::Resource;
-use opentelemetry::sdk::trace::Tracer;
-use opentelemetry::trace::TraceError;
+use opentelemetry::sdk::trace::{Sampler, Tracer};
 use opentelemetry_otlp::WithExportConfig;
 use serde::Deserialize;
-use std::collections::HashMap;
 use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tokio::time::{Duration, sleep};
-use tracing::{Level, Span, error, info};
+use tonic::metadata::{Ascii, MetadataKey, MetadataMap};
+use tracing::{Level, Span, error, info, warn};
 use tracing_actix_web::{DefaultRootSpanBuilder, RootSpanBuilder, TracingLogger};
 use tracing_subscriber::layer::SubscriberExt;
 use util::environment;
@@ -48,13 +53,21 @@ async fn main() -> Result<()> {
         .with_target("actix_server", Level::DEBUG)
         .with_default(Level::TRACE);
 
-    let tracer = init_tracer().unwrap();
-    let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+    // Tracing is optional; if it can't be set up, the server runs without it.
+    let (tracer, tracing_error) =
+        match TracingConfig::from_env().and_then(|config| init_tracer(&config)) {
+            Ok(tracer) => (tracer, None),
+            Err(e) => (None, Some(e)),
+        };
+    let telemetry = tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
     let subscriber = tracing_subscriber::Registry::default()
         .with(filter)
         .with(tracing_subscriber::fmt::Layer::default())
         .with(telemetry);
     tracing::subscriber::set_global_default(subscriber).unwrap();
+    if let Some(e) = tracing_error {
+        warn!("Traces are not exported, setting up the exporter failed: {e:#}");
+    }
 
     let http_db_pool = DbPool::create(
         environment::database_url(),
@@ -80,6 +93,22 @@ async fn main() -> Result<()> {
         web::Data::new(ComicUpdaterTrigger::new());
     let comic_updater_trigger = Arc::clone(&http_comic_updater_trigger);
 
+    // Without the background refresher, nothing would ever invalidate the
+    // stats response cache, so it's only enabled along with it.
+    let http_stats_response_cache: web::Data<StatsResponseCache> = web::Data::new(
+        StatsResponseCache::new(environment::background_services_bool()),
+    );
+    let stats_response_cache = Arc::clone(&http_stats_response_cache);
+
+    let http_export_archive_cache: web::Data<ExportArchiveCache> =
+        web::Data::new(ExportArchiveCache::new());
+
+    let http_service_health: web::Data<ServiceHealth> =
+        web::Data::new(ServiceHealth::new(environment::background_services_bool()));
+    let service_health = Arc::clone(&http_service_health);
+
+    let http_request_metrics: web::Data<RequestMetrics> = web::Data::new(RequestMetrics::new());
+
     // Start HTTP server
     let start_http_server = move || -> Result<actix_web::dev::Server> {
         Ok(HttpServer::new(move || {
@@ -89,6 +118,10 @@ async fn main() -> Result<()> {
                 .app_data(http_news_updater.clone())
                 .app_data(http_token_cache.clone())
                 .app_data(http_comic_updater_trigger.clone())
+                .app_data(http_stats_response_cache.clone())
+                .app_data(http_export_archive_cache.clone())
+                .app_data(http_service_health.clone())
+                .app_data(http_request_metrics.clone())
                 .app_data(PayloadConfig::new(1_048_576))
                 .wrap(auth)
                 .wrap(actix_web::middleware::Compress::default()).wrap(actix_web::middleware::Logger::new(
@@ -98,17 +131,23 @@ async fn main() -> Result<()> {
 
             // Because of legacy reasons, the old API needs to be directly at the root.
             // Any newer APIs should be mounted *inside* v1's `configure`
-            a.service(web::scope("/api").configure(api::configure))
-                .service(web::scope("/releases").configure(controllers::releases::configure))
-                .service(
-                    Files::new("/", "./build/")
-                        .index_file("index.html")
-                        .default_handler(web::to(|| async {
-                            NamedFile::open_async("./build/index.html")
-                                .await
-                                .map_err(error::ErrorInternalServerError)
-                        })),
-                )
+            a.service(
+                web::scope("/api")
+                    .wrap(from_fn(record_request_metrics))
+                    .configure(api::configure),
+            )
+            .service(web::scope("/releases").configure(controllers::releases::configure))
+            .configure(controllers::health::configure)
+            .configure(controllers::metrics::configure)
+            .service(
+                Files::new("/", "./build/")
+                    .index_file("index.html")
+                    .default_handler(web::to(|| async {
+                        NamedFile::open_async("./build/index.html")
+                            .await
+                            .map_err(error::ErrorInternalServerError)
+                    })),
+            )
         })
         .disable_signals()
         .bind(&bind_address)?
@@ -119,13 +158,17 @@ async fn main() -> Result<()> {
     let mut shutdown_futures = FuturesUnordered::new();
     if environment::background_services_bool() {
         let background_news_updater_db_pool = db_pool.clone();
-        let background_rank_stints_pool = db_pool.clone();
+        let background_stats_pool = db_pool.clone();
         let background_comic_updater_db_pool = db_pool;
 
         let background_news_updater = Arc::clone(&news_updater);
         let background_comic_news_updater = news_updater;
         let background_comic_updater_trigger = comic_updater_trigger;
 
+        let background_news_updater_health = Arc::clone(&service_health);
+        let background_comic_updater_health = Arc::clone(&service_health);
+        let background_stats_health = service_health;
+
         let mut background_comic_updater_shutdown_receiver = shutdown_sender.subscribe();
 
         let background_news_updater = tokio::task::spawn(async move {
@@ -134,11 +177,13 @@ async fn main() -> Result<()> {
             while let Err(e) = background_news_updater
                 .background_news_updater(
                     &background_news_updater_db_pool,
+                    &background_news_updater_health,
                     &mut background_news_updater_shutdown_receiver,
                 )
                 .await
             {
                 error!("The background news updater returned an error: {}", e);
+                background_news_updater_health.record_failure(BackgroundService::NewsUpdater, &e);
                 info!("Waiting one minute before starting up again.");
                 sleep(Duration::from_mins(1)).await;
             }
@@ -153,70 +198,74 @@ async fn main() -> Result<()> {
                     &background_comic_updater_db_pool,
                     &background_comic_news_updater,
                     &background_comic_updater_trigger,
+                    &background_comic_updater_health,
                     &mut background_comic_updater_shutdown_receiver,
                 )
                 .await
             {
                 error!("The background comic updater returned an error: {}", e);
+                background_comic_updater_health.record_failure(BackgroundService::ComicUpdater, &e);
                 info!("Waiting one minute before starting up again.");
                 sleep(Duration::from_mins(1)).await;
             }
         });
 
-        let mut background_rank_stints_shutdown = shutdown_sender.subscribe();
-        let background_rank_stints_refresher = tokio::task::spawn(async move {
-            info!("Background rank stints refresher starting...");
+        let mut background_stats_shutdown = shutdown_sender.subscribe();
+        let background_stats_refresher = tokio::task::spawn(async move {
+            info!("Background stats refresher starting...");
+            let mut primed = false;
             loop {
-                tokio::select! {
-                    () = sleep(Duration::from_secs(30)) => {}
-                    _ = background_rank_stints_shutdown.recv() => {
-                        info!("Background rank stints refresher shutting down.");
-                        break;
+                // The stints feed `top_ranked_over_time`, so they have to be
+                // current before the responses are recomputed.
+                match refresh_rank_stints(&background_stats_pool).await {
+                    Ok(()) => {
+                        background_stats_health
+                            .record_success(BackgroundService::RankStintsRefresher);
                     }
+                    Err(e) => background_stats_health
+                        .record_failure(BackgroundService::RankStintsRefresher, &e),
                 }
-                let conn = background_rank_stints_pool.acquire().await;
-                let mut conn = match conn {
-                    Ok(c) => c,
-                    Err(e) => {
-                        error!("rank stints refresher: pool acquire failed: {e}");
-                        continue;
+
+                if primed {
+                    if let Err(e) = stats_response_cache
+                        .refresh_if_needed(&background_stats_pool)
+                        .await
+                    {
+                        error!("stats refresher: response cache refresh failed: {e}");
                     }
-                };
-                let dirty = match DbTopRankedStintRow::needs_refresh(&mut *conn).await {
-                    Ok(v) => v,
-                    Err(e) => {
-                        error!("rank stints refresher: needs_refresh check failed: {e}");
-                        continue;
+                } else {
+                    match api::prime_stats_response_cache(
+                        &stats_response_cache,
+                        &background_stats_pool,
+                    )
+                    .await
+                    {
+                        Ok(()) => {
+                            info!("stats refresher: response cache primed");
+                            primed = true;
+                        }
+                        Err(e) => error!("stats refresher: priming the response cache failed: {e}"),
                     }
-                };
-                if !dirty {
-                    continue;
                 }
-                match DbTopRankedStintRow::refresh_cache(&mut conn).await {
-                    Ok(()) => info!("rank stints refresher: cache refreshed"),
-                    Err(e) => error!("rank stints refresher: refresh_cache failed: {e}"),
+
+                tokio::select! {
+                    () = sleep(Duration::from_secs(30)) => {}
+                    _ = background_stats_shutdown.recv() => {
+                        info!("Background stats refresher shutting down.");
+                        break;
+                    }
                 }
             }
         });
 
         shutdown_futures.push(Either::Right(background_news_updater));
         shutdown_futures.push(Either::Right(background_comic_updater));
-        shutdown_futures.push(Either::Right(background_rank_stints_refresher));
+        shutdown_futures.push(Either::Right(background_stats_refresher));
     } else {
         // Background services are off (dev mode): do a one-time startup refresh so the
         // stints cache is current without needing the background task running.
-        let mut conn = db_pool.acquire().await?;
-        match DbTopRankedStintRow::needs_refresh(&mut *conn).await {
-            Ok(true) => {
-                info!("Refreshing rank stints cache on startup (dev mode)...");
-                match DbTopRankedStintRow::refresh_cache(&mut conn).await {
-                    Ok(()) => info!("Startup rank stints refresh complete."),
-                    Err(e) => error!("Startup rank stints refresh failed: {e}"),
-                }
-            }
-            Ok(false) => {}
-            Err(e) => error!("Startup rank stints refresh: needs_refresh check failed: {e}"),
-        }
+        // Failures are already logged, and the server is usable without the stints.
+        let _ = refresh_rank_stints(&db_pool).await;
     }
 
     let http_server = start_http_server()?;
@@ -281,6 +330,38 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Recomputes the rank stints cache of every item type an occurrence write has
+/// marked stale. Failures are logged and the other item types are still
+/// refreshed; the last failure is returned. The next call tries again.
+async fn refresh_rank_stints(pool: &DbPool) -> Result<()> {
+    let mut conn = match pool.acquire().await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("rank stints refresh: pool acquire failed: {e}");
+            return Err(e.into());
+        }
+    };
+    let mut result = Ok(());
+    for item_type in DbItemType::ALL {
+        let item_type_name = item_type.as_str();
+        match DbTopRankedStintRow::needs_refresh(&mut *conn, item_type).await {
+            Ok(true) => match DbTopRankedStintRow::refresh_cache(&mut conn, item_type).await {
+                Ok(()) => info!("rank stints refresh: {item_type_name} cache refreshed"),
+                Err(e) => {
+                    error!("rank stints refresh: {item_type_name} refresh_cache failed: {e}");
+                    result = Err(anyhow!("{item_type_name} refresh failed: {e}"));
+                }
+            },
+            Ok(false) => {}
+            Err(e) => {
+                error!("rank stints refresh: {item_type_name} needs_refresh check failed: {e}");
+                result = Err(anyhow!("{item_type_name} needs_refresh check failed: {e}"));
+            }
+        }
+    }
+    result
+}
+
 // Older extension clients only know how to send the token as a `token` field in the
 // JSON request body, so that path can't be removed outright. Query-string and
 // `Authorization: Bearer` delivery are checked first since they don't require
@@ -373,30 +454,60 @@ async fn extract_permissions(request: &mut ServiceRequest) -> Result<HashSet<Str
     Ok(permissions)
 }
 
-fn init_tracer() -> Result<Tracer, TraceError> {
-    opentelemetry_otlp::new_pipeline()
-        .tracing()
-        .with_trace_config(
-            opentelemetry::sdk::trace::config().with_resource(Resource::new(vec![KeyValue::new(
-                "service.name",
-                "qcext-server",
-            )])),
-        )
-        .with_exporter(
-            opentelemetry_otlp::new_exporter()
-                .http()
-                .with_endpoint("https://api.honeycomb.io/v1/traces")
-                .with_http_client(reqwest_011::Client::default())
-                .with_headers(HashMap::from([
-                    ("x-honeycomb-dataset".into(), "qcext-server-dataset".into()),
-                    (
-                        "x-honeycomb-team".into(),
-                        environment::honeycomb_key().into(),
-                    ),
-                ]))
-                .with_timeout(Duration::from_secs(2)),
-        ) // Replace with runtime::Tokio if using async main
-        .install_batch(opentelemetry::runtime::Tokio)
+/// Builds the tracer for the configured exporter, or `None` if traces are not
+/// exported.
+fn init_tracer(config: &TracingConfig) -> Result<Option<Tracer>> {
+    let trace_config = opentelemetry::sdk::trace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            "qcext-server",
+        )]));
+
+    let tracer = match &config.exporter {
+        TracingExporter::None => return Ok(None),
+        TracingExporter::Stdout => opentelemetry::sdk::export::trace::stdout::new_pipeline()
+            .with_trace_config(trace_config)
+            .install_simple(),
+        TracingExporter::Otlp(otlp) => match otlp.protocol {
+            OtlpProtocol::HttpProtobuf => opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_trace_config(trace_config)
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .http()
+                        .with_endpoint(otlp.endpoint.clone())
+                        .with_http_client(reqwest_011::Client::default())
+                        .with_headers(otlp.headers.clone())
+                        .with_timeout(Duration::from_secs(2)),
+                )
+                .install_batch(opentelemetry::runtime::Tokio)?,
+            OtlpProtocol::Grpc => {
+                let mut metadata = MetadataMap::new();
+                for (key, value) in &otlp.headers {
+                    metadata.insert(
+                        MetadataKey::<Ascii>::from_bytes(key.as_bytes())?,
+                        value.parse()?,
+                    );
+                }
+                opentelemetry_otlp::new_pipeline()
+                    .tracing()
+                    .with_trace_config(trace_config)
+                    .with_exporter(
+                        opentelemetry_otlp::new_exporter()
+                            .tonic()
+                            .with_endpoint(otlp.endpoint.clone())
+                            .with_metadata(metadata)
+                            .with_timeout(Duration::from_secs(2)),
+                    )
+                    .install_batch(opentelemetry::runtime::Tokio)?
+            }
+        },
+    };
+
+    Ok(Some(tracer))
 }
 
 struct DomainRootSpanBuilder;
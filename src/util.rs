@@ -11,12 +11,26 @@ use std::task::{Context, Poll};
 
 pub use comic_updater::*;
 pub use comic_updater_trigger::ComicUpdaterTrigger;
+pub use csv_response::csv_responses;
+pub use entity_tag::*;
+pub use export_cache::{ExportArchive, ExportArchiveCache};
+pub use metrics::{CacheCounters, MetricsWriter, RequestMetrics, record_request_metrics};
 pub use news_updater::*;
+pub use service_health::{BackgroundService, ServiceHealth, ServiceState, ServiceStatus};
+pub use stats_cache::StatsResponseCache;
+pub use telemetry::{OtlpProtocol, TracingConfig, TracingExporter};
 pub use token_cache::TokenPermissionsCache;
 
 mod comic_updater;
 mod comic_updater_trigger;
+mod csv_response;
+mod entity_tag;
+mod export_cache;
+mod metrics;
 mod news_updater;
+mod service_health;
+mod stats_cache;
+mod telemetry;
 mod token_cache;
 
 pub mod environment {
@@ -29,7 +43,6 @@ pub mod environment {
         pub database_min_connections(): u32;
         pub qc_timezone();
         pub background_services(): bool;
-        pub honeycomb_key();
     }
 }
 
@@ -1,5 +1,5 @@
 use crate::models::{ComicId, ImageType};
-use crate::util::{ComicUpdaterTrigger, NewsUpdater};
+use crate::util::{BackgroundService, ComicUpdaterTrigger, NewsUpdater, ServiceHealth};
 use anyhow::{Context, Result, anyhow};
 use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Timelike, Utc, Weekday};
 use const_format::concatcp;
@@ -37,6 +37,7 @@ impl ComicUpdater {
         db_pool: &DbPool,
         news_updater: &NewsUpdater,
         trigger: &ComicUpdaterTrigger,
+        health: &ServiceHealth,
         shutdown_receiver: &mut broadcast::Receiver<()>,
     ) -> anyhow::Result<()> {
         // Wait a short period of time to avoid hammering the website on frequent restarts due to some
@@ -52,6 +53,7 @@ impl ComicUpdater {
 
             trigger.record_run();
             let comic_id = self.fetch_latest_comic_data(db_pool).await?;
+            health.record_success(BackgroundService::ComicUpdater);
             news_updater.check_for(comic_id);
 
             let delay = time_until_next_update(now);
@@ -0,0 +1,368 @@
+//! CSV representations of JSON responses, for pulling the stats into
+//! spreadsheets.
+//!
+//! Any JSON response is flattened into one table. Scalar fields become
+//! columns, named by their path (`years.characters.rank`), and every element
+//! of a nested array becomes a row of its own, repeating the scalar fields of
+//! its parents. Sibling arrays are stacked on top of each other, leaving the
+//! columns of the other arrays empty. Objects keyed by item id, like the
+//! `characters` of the co-appearance stats, are treated like arrays whose
+//! elements get an extra `id` column.
+//!
+//! Fields are taken in alphabetical order and id-keyed elements in order of
+//! their ids, so the columns don't depend on whether `serde_json` keeps the
+//! order of object keys.
+
+use actix_web::body::{BoxBody, MessageBody, to_bytes};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{
+    self, Accept, ContentDisposition, DispositionParam, DispositionType, EntityTag, Header as _,
+    HeaderMap, HeaderValue, IfNoneMatch, TryIntoHeaderValue,
+};
+use actix_web::middleware::Next;
+use actix_web::{HttpMessage, HttpResponse, error};
+use serde_json::{Map, Value};
+
+/// Prefix of the `ETag`s of CSV representations, so that they never match
+/// the tag of the JSON representation of the same data.
+const CSV_TAG_PREFIX: &str = "csv.";
+
+type Row = Vec<(String, Value)>;
+
+/// Serves a CSV representation of successful JSON responses when the request
+/// asks for one with `?format=csv` or `Accept: text/csv`.
+///
+/// Wrap it around [`super::cache_stats_responses`], so that it can tell the
+/// `ETag`s of both representations apart.
+///
+/// # Errors
+///
+/// Returns the error of the wrapped service, or an internal server error if
+/// the response body cannot be read.
+pub async fn csv_responses(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let csv = wants_csv(&req);
+    retain_if_none_match(&mut req, csv)?;
+
+    let mut res = next.call(req).await?;
+    res.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    if !csv {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    tag_as_csv(res.headers_mut());
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if res.status() != StatusCode::OK || !is_json {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let headers = res.headers().clone();
+    let body = to_bytes(res.into_body()).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        error::ErrorInternalServerError(e.to_string())
+    })?;
+    let value: Value = serde_json::from_slice(&body).map_err(error::ErrorInternalServerError)?;
+
+    let file_name = format!(
+        "{}.csv",
+        req.path()
+            .rsplit('/')
+            .find(|s| !s.is_empty())
+            .unwrap_or("stats")
+    );
+    let mut csv_res = HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(file_name)],
+        })
+        .body(to_csv(&value));
+    for name in [header::ETAG, header::CACHE_CONTROL, header::VARY] {
+        for value in headers.get_all(&name) {
+            csv_res.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+
+    Ok(ServiceResponse::new(req, csv_res))
+}
+
+fn wants_csv(req: &ServiceRequest) -> bool {
+    req.query_string()
+        .split('&')
+        .any(|pair| pair == "format=csv")
+        || req
+            .get_header::<Accept>()
+            .is_some_and(|accept| accept.preference().essence_str() == "text/csv")
+}
+
+/// Keeps only the `If-None-Match` tags of the requested representation,
+/// without their CSV prefix, so that the conditional `GET` handling further
+/// in only ever sees tags it can compare.
+fn retain_if_none_match(req: &mut ServiceRequest, csv: bool) -> Result<(), actix_web::Error> {
+    let Ok(IfNoneMatch::Items(tags)) = IfNoneMatch::parse(&*req) else {
+        return Ok(());
+    };
+
+    let tags: Vec<EntityTag> = tags
+        .into_iter()
+        .filter_map(|tag| match tag.tag().strip_prefix(CSV_TAG_PREFIX) {
+            Some(tag_without_prefix) => {
+                csv.then(|| EntityTag::new(tag.weak, tag_without_prefix.to_owned()))
+            }
+            None => (!csv).then_some(tag),
+        })
+        .collect();
+
+    let headers = req.headers_mut();
+    if tags.is_empty() {
+        headers.remove(header::IF_NONE_MATCH);
+    } else {
+        let value = IfNoneMatch::Items(tags)
+            .try_into_value()
+            .map_err(error::ErrorInternalServerError)?;
+        headers.insert(header::IF_NONE_MATCH, value);
+    }
+
+    Ok(())
+}
+
+fn tag_as_csv(headers: &mut HeaderMap) {
+    let Some(tag) = headers
+        .get(header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<EntityTag>().ok())
+    else {
+        return;
+    };
+
+    let tag = EntityTag::new(tag.weak, format!("{CSV_TAG_PREFIX}{}", tag.tag()));
+    if let Ok(value) = HeaderValue::from_str(&tag.to_string()) {
+        headers.insert(header::ETAG, value);
+    }
+}
+
+/// Flattens `value` into a CSV table with a header row.
+fn to_csv(value: &Value) -> String {
+    let rows = flatten(value, "");
+
+    let mut columns: Vec<&str> = Vec::new();
+    for (column, _) in rows.iter().flatten() {
+        if !columns.contains(&column.as_str()) {
+            columns.push(column);
+        }
+    }
+
+    let mut out = String::new();
+    push_record(&mut out, columns.iter().map(|column| (*column).to_owned()));
+    for row in &rows {
+        push_record(
+            &mut out,
+            columns.iter().map(|column| {
+                row.iter()
+                    .find(|(c, _)| c == column)
+                    .map_or_else(String::new, |(_, value)| cell(value))
+            }),
+        );
+    }
+    out
+}
+
+fn flatten(value: &Value, prefix: &str) -> Vec<Row> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .flat_map(|item| flatten(item, prefix))
+            .collect(),
+        Value::Object(map) if is_id_map(map) => {
+            let mut items: Vec<_> = map.iter().collect();
+            items.sort_by_key(|(id, _)| id.parse::<u64>().ok());
+            items
+                .into_iter()
+                .flat_map(|(id, item)| {
+                    let id_column = (format!("{prefix}id"), Value::String(id.clone()));
+                    flatten(item, prefix).into_iter().map(move |mut row| {
+                        row.insert(0, id_column.clone());
+                        row
+                    })
+                })
+                .collect()
+        }
+        Value::Object(map) => {
+            let mut fields: Vec<_> = map.iter().collect();
+            fields.sort_unstable_by_key(|(key, _)| *key);
+
+            let mut scalars = Row::new();
+            let mut nested = Vec::new();
+            for (key, value) in fields {
+                let column = format!("{prefix}{key}");
+                if value.is_array() || value.is_object() {
+                    nested.extend(flatten(value, &format!("{column}.")));
+                } else {
+                    scalars.push((column, value.clone()));
+                }
+            }
+
+            if nested.is_empty() {
+                if scalars.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![scalars]
+                }
+            } else {
+                nested
+                    .into_iter()
+                    .map(|row| scalars.iter().cloned().chain(row).collect())
+                    .collect()
+            }
+        }
+        scalar => {
+            let column = prefix.strip_suffix('.').unwrap_or("value");
+            vec![vec![(column.to_owned(), scalar.clone())]]
+        }
+    }
+}
+
+/// Whether `map` is keyed by (item) ids rather than field names.
+fn is_id_map(map: &Map<String, Value>) -> bool {
+    !map.is_empty() && map.keys().all(|key| key.parse::<u64>().is_ok())
+}
+
+/// Characters that make spreadsheet applications read a cell as a formula.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        // Names and titles are user-submitted; a leading `'` keeps them text
+        // when the file is opened in a spreadsheet. Numbers are left alone,
+        // so negative ones stay numbers.
+        Value::String(s) if s.starts_with(FORMULA_PREFIXES) => format!("'{s}"),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn push_record(out: &mut String, cells: impl Iterator<Item = String>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if cell.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&cell);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn arrays_of_objects_become_one_row_each() {
+        let value = json!([
+            {"id": 1, "name": "Faye", "appearances": 10},
+            {"id": 2, "name": "Dora, \"the\" boss", "appearances": null},
+        ]);
+
+        assert_eq!(
+            to_csv(&value),
+            "appearances,id,name\r\n10,1,Faye\r\n,2,\"Dora, \"\"the\"\" boss\"\r\n"
+        );
+    }
+
+    #[test]
+    fn nested_arrays_repeat_the_parent_fields() {
+        let value = json!({
+            "years": [
+                {"year": 2004, "characters": [{"id": 1, "rank": 1}, {"id": 2, "rank": 2}]},
+                {"year": 2005, "characters": [{"id": 2, "rank": 1}]},
+            ],
+        });
+
+        assert_eq!(
+            to_csv(&value),
+            "years.year,years.characters.id,years.characters.rank\r\n\
+             2004,1,1\r\n\
+             2004,2,2\r\n\
+             2005,2,1\r\n"
+        );
+    }
+
+    #[test]
+    fn sibling_collections_are_stacked() {
+        let value = json!({
+            "characters": {"1": {"name": "Faye"}, "2": {"name": "Dora"}},
+            "pairs": [{"character1Id": 1, "character2Id": 2, "comicsTogether": 5}],
+        });
+
+        assert_eq!(
+            to_csv(&value),
+            "characters.id,characters.name,pairs.character1Id,pairs.character2Id,pairs.comicsTogether\r\n\
+             1,Faye,,,\r\n\
+             2,Dora,,,\r\n\
+             ,,1,2,5\r\n"
+        );
+    }
+
+    #[test]
+    fn columns_and_ids_are_sorted() {
+        let value = json!({
+            "10": {"name": "Dora", "appearances": 3},
+            "9": {"name": "Faye", "appearances": 5},
+        });
+
+        assert_eq!(
+            to_csv(&value),
+            "id,appearances,name\r\n9,5,Faye\r\n10,3,Dora\r\n"
+        );
+    }
+
+    #[test]
+    fn formulas_are_kept_as_text() {
+        let value = json!([
+            {"name": "=HYPERLINK(\"http://example.com\",\"Faye\")", "delta": -3},
+            {"name": "+1", "delta": 2},
+            {"name": "-Dora", "delta": 0},
+            {"name": "@SUM(A1)", "delta": 1},
+        ]);
+
+        assert_eq!(
+            to_csv(&value),
+            "delta,name\r\n\
+             -3,\"'=HYPERLINK(\"\"http://example.com\"\",\"\"Faye\"\")\"\r\n\
+             2,'+1\r\n\
+             0,'-Dora\r\n\
+             1,'@SUM(A1)\r\n"
+        );
+    }
+
+    #[test]
+    fn scalar_responses_have_a_single_value() {
+        assert_eq!(to_csv(&json!([3, 4])), "value\r\n3\r\n4\r\n");
+    }
+
+    #[test]
+    fn csv_tags_are_prefixed() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_static("\"d1234\""));
+
+        tag_as_csv(&mut headers);
+
+        assert_eq!(headers.get(header::ETAG).unwrap(), "\"csv.d1234\"");
+    }
+}
@@ -0,0 +1,370 @@
+//! `ETag` support: `If-Match` for versioned entities (comics and items) and
+//! conditional `GET` for cacheable read endpoints.
+//!
+//! Cacheable responses are tagged with the data version they were built from,
+//! which is the latest `change_log` sequence number. Every write to a comic,
+//! item, occurrence, image or news row appends to the change log, so a client
+//! holding a tag with the current data version has an up-to-date copy. That
+//! check needs no more than one indexed `MAX()` query, and is done before the
+//! handler runs.
+//!
+//! Stats responses are served from the [`StatsResponseCache`] when it is
+//! enabled, so they are tagged with the data version the cache was last
+//! refreshed at instead, which needs no query at all.
+//!
+//! Tags have the form `"<entity version>.d<data version>"` for responses
+//! about a single comic or item, `"d<data version>"` for other cacheable
+//! responses and `"<entity version>"` for responses that are not cacheable
+//! but still need to support `If-Match`.
+//! The CSV representation of a stats response has the tag of the JSON one
+//! prefixed with `csv.`, see [`csv_responses`](super::csv_responses).
+
+use crate::util::StatsResponseCache;
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{
+    self, ETag, EntityTag, Header as _, HeaderValue, IfMatch, IfNoneMatch,
+};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{HttpRequest, HttpResponse, error, web};
+use actix_web_grants::authorities::{AuthDetails, AuthoritiesCheck};
+use database::DbPool;
+use database::models::ChangeLogEntry;
+use database::models::stats::TopRankedStintRow;
+use serde::Serialize;
+use shared::token_permissions;
+
+/// Dataset responses change with every edit, so clients must revalidate, but
+/// a revalidation that ends in `304 Not Modified` is nearly free.
+const DATASET_CACHE_CONTROL: &str = "public, no-cache";
+/// Stats only shift noticeably over many edits; a few minutes of staleness
+/// saves the revalidation round trip when paging through the stats views.
+const STATS_CACHE_CONTROL: &str = "public, max-age=300";
+/// Responses that include hidden comics or editor data.
+const PRIVATE_CACHE_CONTROL: &str = "private, no-store";
+
+#[derive(Debug, Clone)]
+struct ResponseETag(EntityTag);
+
+#[derive(Debug, Clone, Copy)]
+struct Cacheable {
+    entity_version: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachePolicy {
+    cache_control: &'static str,
+    /// Token holders get a different representation (hidden comics, editor
+    /// data), which is never cached.
+    varies_with_token: bool,
+    /// Responses come from the stats response cache (or, when it is disabled,
+    /// may come from the stints cache), which lag behind the change log until
+    /// they have been refreshed.
+    uses_stats_cache: bool,
+    /// Every successful `GET` response is cacheable, without the handler
+    /// having to call [`mark_cacheable`].
+    cache_all: bool,
+}
+
+const DATASET_POLICY: CachePolicy = CachePolicy {
+    cache_control: DATASET_CACHE_CONTROL,
+    varies_with_token: true,
+    uses_stats_cache: false,
+    cache_all: false,
+};
+
+const STATS_POLICY: CachePolicy = CachePolicy {
+    cache_control: STATS_CACHE_CONTROL,
+    varies_with_token: false,
+    uses_stats_cache: true,
+    cache_all: true,
+};
+
+/// The strong `ETag` of an entity at the given row version.
+#[must_use]
+pub fn version_etag(version: u32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+fn cacheable_etag(entity_version: Option<u32>, data_version: u64) -> EntityTag {
+    EntityTag::new_strong(entity_version.map_or_else(
+        || format!("d{data_version}"),
+        |entity_version| format!("{entity_version}.d{data_version}"),
+    ))
+}
+
+fn entity_version_of(tag: &EntityTag) -> Option<u32> {
+    tag.tag().split('.').next()?.parse().ok()
+}
+
+fn data_version_of(tag: &EntityTag) -> Option<u64> {
+    tag.tag()
+        .rsplit('.')
+        .next()?
+        .strip_prefix('d')?
+        .parse()
+        .ok()
+}
+
+/// Whether an `If-Match` precondition holds for an entity at `version`.
+/// Requests without `If-Match` always pass, so clients that don't track
+/// versions keep working.
+#[must_use]
+pub fn if_match_satisfied(if_match: Option<&IfMatch>, version: u32) -> bool {
+    match if_match {
+        None | Some(IfMatch::Any) => true,
+        Some(IfMatch::Items(tags)) => tags
+            .iter()
+            .any(|tag| !tag.weak && entity_version_of(tag) == Some(version)),
+    }
+}
+
+/// Whether `If-None-Match` on `req` lists `etag`, for handlers that do their
+/// own conditional `GET` handling.
+#[must_use]
+pub fn is_not_modified(req: &HttpRequest, etag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        Err(_) => false,
+    }
+}
+
+/// Attaches an `ETag` to the response of `req`. Handlers declared with
+/// `api_endpoint` must return `Json<T>`, so they cannot set headers
+/// themselves; the middleware wrapping them copies the tag onto the response.
+pub fn set_response_etag(req: &HttpRequest, tag: EntityTag) {
+    req.extensions_mut().insert(ResponseETag(tag));
+}
+
+/// Marks the response of `req` as cacheable by the middleware wrapping it,
+/// which gives it an `ETag` and `Cache-Control` header. `entity_version` is
+/// the row version of the comic or item the response is about, if any, and
+/// becomes part of the tag so it can be used with `If-Match`.
+///
+/// Only mark responses that are fully determined by the request and the
+/// dataset; a `304 Not Modified` answer skips the handler entirely.
+pub fn mark_cacheable(req: &HttpRequest, entity_version: Option<u32>) {
+    req.extensions_mut().insert(Cacheable { entity_version });
+}
+
+/// Conditional `GET` middleware for the `comicdata` and `itemdata` scopes.
+///
+/// # Errors
+///
+/// Returns the error of the wrapped service, or a database error if the
+/// current data version cannot be read.
+pub async fn cache_dataset_responses(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    conditional_get(req, next, DATASET_POLICY).await
+}
+
+/// Conditional `GET` middleware for the `stats` scope and the graph export.
+///
+/// # Errors
+///
+/// Returns the error of the wrapped service, or a database error if the
+/// current data version cannot be read.
+pub async fn cache_stats_responses(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    conditional_get(req, next, STATS_POLICY).await
+}
+
+async fn conditional_get<B: MessageBody + 'static>(
+    mut req: ServiceRequest,
+    next: Next<B>,
+    policy: CachePolicy,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let private = policy.varies_with_token
+        && req
+            .extract::<AuthDetails>()
+            .await?
+            .has_authority(token_permissions::HAS_VALID_TOKEN);
+
+    let data_version = if req.method() == Method::GET && !private {
+        current_data_version(&req, policy).await?
+    } else {
+        None
+    };
+
+    if let Some(data_version) = data_version {
+        if let Some(tag) = fresh_tag(IfNoneMatch::parse(&req).ok(), data_version) {
+            let mut response = HttpResponse::NotModified();
+            response
+                .insert_header(ETag(tag))
+                .insert_header((header::CACHE_CONTROL, policy.cache_control));
+            if policy.varies_with_token {
+                response.insert_header((header::VARY, "authorization"));
+            }
+            return Ok(req.into_response(response.finish()));
+        }
+    }
+
+    let mut res = next.call(req).await?;
+
+    let (explicit_tag, cacheable) = {
+        let extensions = res.request().extensions();
+        (
+            extensions.get::<ResponseETag>().cloned(),
+            extensions.get::<Cacheable>().copied().or_else(|| {
+                policy.cache_all.then_some(Cacheable {
+                    entity_version: None,
+                })
+            }),
+        )
+    };
+    let (tag, cache_control) = match (explicit_tag, cacheable) {
+        (Some(ResponseETag(tag)), _) => (Some(tag), None),
+        (None, Some(Cacheable { entity_version })) => match data_version {
+            Some(data_version) if res.status() == StatusCode::OK => (
+                Some(cacheable_etag(entity_version, data_version)),
+                Some(policy.cache_control),
+            ),
+            _ => (
+                entity_version.map(version_etag),
+                Some(PRIVATE_CACHE_CONTROL),
+            ),
+        },
+        (None, None) => (None, None),
+    };
+
+    let headers = res.headers_mut();
+    if let Some(tag) = tag {
+        let value =
+            HeaderValue::from_str(&tag.to_string()).map_err(error::ErrorInternalServerError)?;
+        headers.insert(header::ETAG, value);
+    }
+    if let Some(cache_control) = cache_control {
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        );
+        if policy.varies_with_token {
+            headers.insert(header::VARY, HeaderValue::from_static("authorization"));
+        }
+    }
+
+    Ok(res.map_into_boxed_body())
+}
+
+/// The version of the data the response would be built from, or `None` if
+/// it cannot be told (in which case the response is not cached).
+async fn current_data_version(
+    req: &ServiceRequest,
+    policy: CachePolicy,
+) -> Result<Option<u64>, actix_web::Error> {
+    if policy.uses_stats_cache {
+        if let Some(cache) = req
+            .app_data::<web::Data<StatsResponseCache>>()
+            .filter(|cache| cache.is_enabled())
+        {
+            return Ok(cache.data_version());
+        }
+    }
+
+    let Some(pool) = req.app_data::<web::Data<DbPool>>() else {
+        return Ok(None);
+    };
+
+    let data_version = ChangeLogEntry::latest_seq(&****pool)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    if policy.uses_stats_cache
+        && TopRankedStintRow::any_needs_refresh(&****pool)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+    {
+        return Ok(None);
+    }
+
+    Ok(data_version)
+}
+
+/// The tag from `If-None-Match` that is still current, if any.
+fn fresh_tag(if_none_match: Option<IfNoneMatch>, data_version: u64) -> Option<EntityTag> {
+    match if_none_match? {
+        IfNoneMatch::Any => None,
+        IfNoneMatch::Items(tags) => tags
+            .into_iter()
+            .find(|tag| data_version_of(tag) == Some(data_version)),
+    }
+}
+
+/// A `412 Precondition Failed` error whose body is the entity's current
+/// state and whose `ETag` is its current version, so the client can redo its
+/// change on top of it.
+#[must_use]
+pub fn precondition_failed<T: Serialize>(
+    message: String,
+    version: u32,
+    current: &T,
+) -> actix_web::Error {
+    let response = HttpResponse::PreconditionFailed()
+        .insert_header(ETag(version_etag(version)))
+        .json(current);
+
+    error::InternalError::from_response(message, response).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_or_wildcard_if_match_is_satisfied() {
+        assert!(if_match_satisfied(None, 3));
+        assert!(if_match_satisfied(Some(&IfMatch::Any), 3));
+    }
+
+    #[test]
+    fn if_match_requires_the_current_version() {
+        let if_match = IfMatch::Items(vec![version_etag(2), version_etag(4)]);
+
+        assert!(if_match_satisfied(Some(&if_match), 4));
+        assert!(!if_match_satisfied(Some(&if_match), 3));
+    }
+
+    #[test]
+    fn if_match_accepts_cacheable_tags_of_the_current_version() {
+        let if_match = IfMatch::Items(vec![cacheable_etag(Some(4), 1234)]);
+
+        assert!(if_match_satisfied(Some(&if_match), 4));
+        assert!(!if_match_satisfied(Some(&if_match), 1234));
+    }
+
+    #[test]
+    fn weak_tags_never_satisfy_if_match() {
+        let if_match = IfMatch::Items(vec![EntityTag::new_weak("3".to_owned())]);
+
+        assert!(!if_match_satisfied(Some(&if_match), 3));
+    }
+
+    #[test]
+    fn only_tags_of_the_current_data_version_are_fresh() {
+        let if_none_match = || {
+            Some(IfNoneMatch::Items(vec![
+                cacheable_etag(Some(4), 1233),
+                cacheable_etag(None, 1234),
+            ]))
+        };
+
+        assert_eq!(
+            fresh_tag(if_none_match(), 1234),
+            Some(cacheable_etag(None, 1234))
+        );
+        assert_eq!(fresh_tag(if_none_match(), 1235), None);
+    }
+
+    #[test]
+    fn version_tags_are_never_fresh() {
+        let if_none_match = Some(IfNoneMatch::Items(vec![version_etag(7)]));
+
+        assert_eq!(fresh_tag(if_none_match, 7), None);
+    }
+}
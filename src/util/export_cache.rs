@@ -0,0 +1,114 @@
+//! The public dataset dump served by `/api/v3/export`, kept in memory.
+//!
+//! Building a dump reads every table, image data included, so the public
+//! archive is built at most once per data version (the latest `change_log`
+//! sequence number) and shared by every request for it. Requests that arrive
+//! while it is being built wait for that build instead of starting their own.
+
+use actix_web::web::Bytes;
+use std::future::Future;
+use tokio::sync::Mutex;
+
+/// A built dump archive.
+#[derive(Debug, Clone)]
+pub struct ExportArchive {
+    /// The data version read before the dump was loaded.
+    pub data_version: Option<u64>,
+    pub file_name: String,
+    pub data: Bytes,
+}
+
+/// The most recently built public dump archive.
+#[derive(Debug, Default)]
+pub struct ExportArchiveCache {
+    archive: Mutex<Option<ExportArchive>>,
+}
+
+impl ExportArchiveCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the archive built at `data_version`, building it with `build`
+    /// if the cached one was built at another version, or there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `build`, if it had to be called. The previously
+    /// cached archive is kept in that case.
+    pub async fn get_or_build<F, Fut, E>(
+        &self,
+        data_version: Option<u64>,
+        build: F,
+    ) -> Result<ExportArchive, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<ExportArchive, E>>,
+    {
+        let mut archive = self.archive.lock().await;
+        if let Some(cached) = archive
+            .as_ref()
+            .filter(|cached| cached.data_version == data_version)
+        {
+            return Ok(cached.clone());
+        }
+
+        let fresh = build().await?;
+        *archive = Some(fresh.clone());
+        drop(archive);
+        Ok(fresh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn archive(data_version: u64) -> ExportArchive {
+        ExportArchive {
+            data_version: Some(data_version),
+            file_name: format!("qcext-dump-{data_version}.tar.gz"),
+            data: Bytes::from_static(b"archive"),
+        }
+    }
+
+    #[tokio::test]
+    async fn the_archive_is_built_once_per_data_version() {
+        let cache = ExportArchiveCache::new();
+        let builds = &AtomicU32::new(0);
+        let get = |data_version| {
+            cache.get_or_build(Some(data_version), move || {
+                builds.fetch_add(1, Ordering::Relaxed);
+                async move { Ok::<_, ()>(archive(data_version)) }
+            })
+        };
+
+        assert_eq!(get(1).await.unwrap().file_name, "qcext-dump-1.tar.gz");
+        assert_eq!(get(1).await.unwrap().file_name, "qcext-dump-1.tar.gz");
+        assert_eq!(builds.load(Ordering::Relaxed), 1);
+
+        assert_eq!(get(2).await.unwrap().file_name, "qcext-dump-2.tar.gz");
+        assert_eq!(builds.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn a_failed_build_keeps_the_previous_archive() {
+        let cache = ExportArchiveCache::new();
+        cache
+            .get_or_build(Some(1), || async { Ok::<_, ()>(archive(1)) })
+            .await
+            .unwrap();
+
+        let failed = cache
+            .get_or_build(Some(2), || async { Err::<ExportArchive, _>("no database") })
+            .await;
+        assert_eq!(failed.unwrap_err(), "no database");
+
+        let cached = cache
+            .get_or_build(Some(1), || async { Err::<ExportArchive, _>("rebuilt") })
+            .await;
+        assert_eq!(cached.unwrap().data_version, Some(1));
+    }
+}
@@ -0,0 +1,295 @@
+//! Counters for the `/metrics` endpoint, and the Prometheus text format they
+//! are exposed in.
+//!
+//! Request counts and latencies are recorded per route pattern (e.g.
+//! `/api/v3/comicdata/{comicId}`) by [`record_request_metrics`], so the
+//! number of series is bounded by the number of endpoints. Everything else is
+//! read from the component it describes when the metrics are scraped.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write as _};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the request latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Hit and miss counts of a cache.
+#[derive(Debug, Default)]
+pub struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounters {
+    pub fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Default)]
+struct RouteStats {
+    statuses: BTreeMap<u16, u64>,
+    /// Requests per bucket of [`LATENCY_BUCKETS`], not cumulative; the last
+    /// one is for requests slower than every bound.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    count: u64,
+    sum_seconds: f64,
+}
+
+/// Request counts and latencies, per method and route pattern.
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    routes: DashMap<(String, String), RouteStats>,
+}
+
+impl RequestMetrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let mut route_stats = self
+            .routes
+            .entry((method.to_owned(), route.to_owned()))
+            .or_default();
+        let seconds = duration.as_secs_f64();
+        *route_stats.statuses.entry(status).or_default() += 1;
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        route_stats.buckets[bucket] += 1;
+        route_stats.count += 1;
+        route_stats.sum_seconds += seconds;
+    }
+
+    pub fn write_to(&self, out: &mut MetricsWriter) {
+        let mut routes: Vec<_> = self
+            .routes
+            .iter()
+            .map(|entry| {
+                let (method, route) = entry.key().clone();
+                let stats = entry.value();
+                (
+                    method,
+                    route,
+                    stats.statuses.clone(),
+                    stats.buckets,
+                    stats.count,
+                    stats.sum_seconds,
+                )
+            })
+            .collect();
+        routes.sort_by(|a, b| (&a.1, &a.0).cmp(&(&b.1, &b.0)));
+
+        out.metric(
+            "qcext_http_requests_total",
+            "counter",
+            "HTTP requests to the API, by method, route and status.",
+        );
+        for (method, route, statuses, ..) in &routes {
+            for (status, count) in statuses {
+                out.sample(
+                    "qcext_http_requests_total",
+                    &[
+                        ("method", method.as_str()),
+                        ("route", route.as_str()),
+                        ("status", status.to_string().as_str()),
+                    ],
+                    count,
+                );
+            }
+        }
+
+        out.metric(
+            "qcext_http_request_duration_seconds",
+            "histogram",
+            "Time taken to answer API requests, by method and route.",
+        );
+        for (method, route, _, buckets, count, sum_seconds) in &routes {
+            let mut cumulative = 0;
+            for (bound, in_bucket) in LATENCY_BUCKETS.iter().zip(buckets) {
+                cumulative += in_bucket;
+                out.sample(
+                    "qcext_http_request_duration_seconds_bucket",
+                    &[
+                        ("method", method.as_str()),
+                        ("route", route.as_str()),
+                        ("le", bound.to_string().as_str()),
+                    ],
+                    cumulative,
+                );
+            }
+            let labels = [("method", method.as_str()), ("route", route.as_str())];
+            out.sample(
+                "qcext_http_request_duration_seconds_bucket",
+                &[labels[0], labels[1], ("le", "+Inf")],
+                count,
+            );
+            out.sample(
+                "qcext_http_request_duration_seconds_sum",
+                &labels,
+                sum_seconds,
+            );
+            out.sample("qcext_http_request_duration_seconds_count", &labels, count);
+        }
+    }
+}
+
+/// Records the method, route pattern, status and latency of every request
+/// to the wrapped service in the [`RequestMetrics`].
+///
+/// # Errors
+///
+/// Returns the error of the wrapped service.
+pub async fn record_request_metrics(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let metrics = req.app_data::<web::Data<RequestMetrics>>().cloned();
+    let method = req.method().to_string();
+    let start = Instant::now();
+
+    let result = next.call(req).await;
+
+    if let Some(metrics) = metrics {
+        let (route, status) = match &result {
+            Ok(res) => (res.request().match_pattern(), res.status().as_u16()),
+            Err(e) => (None, e.as_response_error().status_code().as_u16()),
+        };
+        metrics.observe(
+            &method,
+            route.as_deref().unwrap_or("unmatched"),
+            status,
+            start.elapsed(),
+        );
+    }
+
+    result
+}
+
+/// Writes metrics in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts the metric family `name` of type `kind`.
+    pub fn metric(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl Display) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (label, value)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{label}=\"");
+                for c in value.chars() {
+                    match c {
+                        '\\' => self.out.push_str("\\\\"),
+                        '"' => self.out.push_str("\\\""),
+                        '\n' => self.out.push_str("\\n"),
+                        c => self.out.push(c),
+                    }
+                }
+                self.out.push('"');
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {value}");
+    }
+
+    #[must_use]
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_counters_count_hits_and_misses() {
+        let counters = CacheCounters::default();
+        counters.record(true);
+        counters.record(true);
+        counters.record(false);
+
+        assert_eq!(counters.hits(), 2);
+        assert_eq!(counters.misses(), 1);
+    }
+
+    #[test]
+    fn writes_samples_with_escaped_labels() {
+        let mut out = MetricsWriter::new();
+        out.metric("qcext_test", "gauge", "A test.");
+        out.sample("qcext_test", &[], 1);
+        out.sample("qcext_test", &[("a", "x\"y"), ("b", "c\\d\n")], 2.5);
+
+        assert_eq!(
+            out.finish(),
+            "# HELP qcext_test A test.\n\
+             # TYPE qcext_test gauge\n\
+             qcext_test 1\n\
+             qcext_test{a=\"x\\\"y\",b=\"c\\\\d\\n\"} 2.5\n"
+        );
+    }
+
+    #[test]
+    fn requests_are_counted_in_cumulative_buckets() {
+        let metrics = RequestMetrics::new();
+        let route = "/api/v3/comicdata/{comicId}";
+        metrics.observe("GET", route, 200, Duration::from_millis(3));
+        metrics.observe("GET", route, 200, Duration::from_millis(40));
+        metrics.observe("GET", route, 404, Duration::from_secs(20));
+
+        let mut out = MetricsWriter::new();
+        metrics.write_to(&mut out);
+        let text = out.finish();
+
+        let labels = r#"method="GET",route="/api/v3/comicdata/{comicId}""#;
+        for line in [
+            format!(r#"qcext_http_requests_total{{{labels},status="200"}} 2"#),
+            format!(r#"qcext_http_requests_total{{{labels},status="404"}} 1"#),
+            format!(r#"qcext_http_request_duration_seconds_bucket{{{labels},le="0.005"}} 1"#),
+            format!(r#"qcext_http_request_duration_seconds_bucket{{{labels},le="0.05"}} 2"#),
+            format!(r#"qcext_http_request_duration_seconds_bucket{{{labels},le="10"}} 2"#),
+            format!(r#"qcext_http_request_duration_seconds_bucket{{{labels},le="+Inf"}} 3"#),
+            format!("qcext_http_request_duration_seconds_count{{{labels}}} 3"),
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line} in\n{text}");
+        }
+    }
+}
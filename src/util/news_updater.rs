@@ -1,4 +1,5 @@
 use crate::models::ComicId;
+use crate::util::{BackgroundService, ServiceHealth};
 use anyhow::Result;
 use chrono::Utc;
 use database::DbPool;
@@ -43,9 +44,15 @@ impl NewsUpdater {
         self.update_set.lock().unwrap().insert(comic_id);
     }
 
+    /// The number of comics waiting for a news update check.
+    pub fn pending(&self) -> usize {
+        self.update_set.lock().unwrap().len()
+    }
+
     pub async fn background_news_updater(
         &self,
         db_pool: &DbPool,
+        health: &ServiceHealth,
         shutdown_receiver: &mut broadcast::Receiver<()>,
     ) -> anyhow::Result<()> {
         let mut process_set = HashSet::new();
@@ -58,6 +65,7 @@ impl NewsUpdater {
                 self.run_news_update(db_pool, process_set.iter()).await?;
                 process_set.clear();
             }
+            health.record_success(BackgroundService::NewsUpdater);
 
             {
                 select! {
@@ -0,0 +1,231 @@
+//! Bookkeeping of the background services' runs, for the readiness check.
+
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A background task whose runs are tracked by [`ServiceHealth`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BackgroundService {
+    ComicUpdater,
+    NewsUpdater,
+    RankStintsRefresher,
+}
+
+impl BackgroundService {
+    pub const ALL: [Self; 3] = [
+        Self::ComicUpdater,
+        Self::NewsUpdater,
+        Self::RankStintsRefresher,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ComicUpdater => "comic-updater",
+            Self::NewsUpdater => "news-updater",
+            Self::RankStintsRefresher => "rank-stints-refresher",
+        }
+    }
+
+    /// How long the service may go without a successful run before it is
+    /// considered stalled. The comic updater waits at most 12 hours between
+    /// runs; the others run every few seconds.
+    const fn max_age(self) -> Duration {
+        match self {
+            Self::ComicUpdater => Duration::from_hours(13),
+            Self::NewsUpdater | Self::RankStintsRefresher => Duration::from_mins(5),
+        }
+    }
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How a background service is doing.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceState {
+    /// Background services are turned off.
+    Disabled,
+    /// No run has succeeded yet, but the service has only just started.
+    Starting,
+    Ok,
+    /// The last run failed, but a run succeeded recently enough.
+    Degraded,
+    /// No run has succeeded for longer than the service's maximum age.
+    Stalled,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceStatus {
+    pub state: ServiceState,
+    pub last_success_age: Option<Duration>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+/// How many runs of a background service succeeded and failed since startup.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RunCounts {
+    pub successes: u64,
+    pub failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct Runs {
+    last_success: Option<Instant>,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    counts: RunCounts,
+}
+
+/// Shared record of when each background service last succeeded or failed.
+#[derive(Debug)]
+pub struct ServiceHealth {
+    enabled: bool,
+    started: Instant,
+    runs: [Mutex<Runs>; 3],
+}
+
+impl ServiceHealth {
+    #[must_use]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            started: Instant::now(),
+            runs: Default::default(),
+        }
+    }
+
+    /// Records that a run of `service` has just succeeded.
+    pub fn record_success(&self, service: BackgroundService) {
+        let mut runs = self.runs[service.index()]
+            .lock()
+            .expect("lock is not poisoned");
+        runs.last_success = Some(Instant::now());
+        runs.consecutive_failures = 0;
+        runs.counts.successes += 1;
+    }
+
+    /// Records that a run of `service` has just failed with `error`.
+    pub fn record_failure(&self, service: BackgroundService, error: &impl Display) {
+        let mut runs = self.runs[service.index()]
+            .lock()
+            .expect("lock is not poisoned");
+        runs.consecutive_failures = runs.consecutive_failures.saturating_add(1);
+        runs.last_error = Some(error.to_string());
+        runs.counts.failures += 1;
+    }
+
+    #[must_use]
+    pub fn run_counts(&self, service: BackgroundService) -> RunCounts {
+        self.runs[service.index()]
+            .lock()
+            .expect("lock is not poisoned")
+            .counts
+    }
+
+    #[must_use]
+    pub fn status(&self, service: BackgroundService) -> ServiceStatus {
+        self.status_at(service, Instant::now())
+    }
+
+    fn status_at(&self, service: BackgroundService, now: Instant) -> ServiceStatus {
+        let runs = self.runs[service.index()]
+            .lock()
+            .expect("lock is not poisoned");
+        let last_success_age = runs.last_success.map(|at| now.duration_since(at));
+        let state = if !self.enabled {
+            ServiceState::Disabled
+        } else if last_success_age.unwrap_or_else(|| now.duration_since(self.started))
+            > service.max_age()
+        {
+            ServiceState::Stalled
+        } else if runs.consecutive_failures > 0 {
+            ServiceState::Degraded
+        } else if last_success_age.is_none() {
+            ServiceState::Starting
+        } else {
+            ServiceState::Ok
+        };
+
+        ServiceStatus {
+            state,
+            last_success_age,
+            consecutive_failures: runs.consecutive_failures,
+            last_error: runs.last_error.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_service_is_starting_until_its_first_run() {
+        let health = ServiceHealth::new(true);
+
+        let status = health.status(BackgroundService::NewsUpdater);
+        assert_eq!(status.state, ServiceState::Starting);
+
+        health.record_success(BackgroundService::NewsUpdater);
+        let status = health.status(BackgroundService::NewsUpdater);
+        assert_eq!(status.state, ServiceState::Ok);
+        assert!(status.last_success_age.is_some());
+    }
+
+    #[test]
+    fn failures_degrade_a_service_until_it_succeeds_again() {
+        let health = ServiceHealth::new(true);
+        health.record_success(BackgroundService::ComicUpdater);
+        health.record_failure(BackgroundService::ComicUpdater, &"front page is empty");
+        health.record_failure(BackgroundService::ComicUpdater, &"front page is empty");
+
+        let status = health.status(BackgroundService::ComicUpdater);
+        assert_eq!(status.state, ServiceState::Degraded);
+        assert_eq!(status.consecutive_failures, 2);
+        assert_eq!(status.last_error.as_deref(), Some("front page is empty"));
+
+        health.record_success(BackgroundService::ComicUpdater);
+        let status = health.status(BackgroundService::ComicUpdater);
+        assert_eq!(status.state, ServiceState::Ok);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(
+            health.run_counts(BackgroundService::ComicUpdater),
+            RunCounts {
+                successes: 2,
+                failures: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn a_service_without_recent_success_is_stalled() {
+        let health = ServiceHealth::new(true);
+        health.record_success(BackgroundService::RankStintsRefresher);
+
+        let later = Instant::now() + Duration::from_mins(10);
+        let status = health.status_at(BackgroundService::RankStintsRefresher, later);
+        assert_eq!(status.state, ServiceState::Stalled);
+
+        let status = health.status_at(BackgroundService::ComicUpdater, later);
+        assert_eq!(status.state, ServiceState::Starting);
+    }
+
+    #[test]
+    fn services_are_disabled_without_background_services() {
+        let health = ServiceHealth::new(false);
+
+        let later = Instant::now() + Duration::from_hours(24);
+        for service in BackgroundService::ALL {
+            assert_eq!(
+                health.status_at(service, later).state,
+                ServiceState::Disabled
+            );
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! In-memory cache of computed stats responses.
+//!
+//! The stats endpoints run heavy window-function queries, but their results
+//! only change when the dataset does. The responses of a fixed set of keys
+//! (each endpoint's unfiltered response, and its grouped variants) are
+//! computed once, kept in memory, and recomputed in the background whenever a
+//! write to `Comic`, `Item` or `Occurrence` sets the `stats_responses` dirty
+//! flag in `stats_cache_meta`. Requests are always answered from memory once
+//! such a response has been computed; during a refresh they get the previous
+//! response.
+//!
+//! Responses that depend on request parameters (filters, item ids) have an
+//! unbounded key space, so they are only kept in a small least recently used
+//! cache, which is emptied on refresh instead of recomputed.
+
+use crate::util::CacheCounters;
+use dashmap::DashMap;
+use database::DbPool;
+use database::models::ChangeLogEntry;
+use database::models::stats::StatsResponseCacheMeta;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use std::any::Any;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+/// Upper bound on the number of cached responses to parameterised requests.
+/// When it is reached, the least recently used one is evicted.
+const MAX_TRANSIENT_ENTRIES: usize = 256;
+
+type CachedValue = Arc<dyn Any + Send + Sync>;
+type Compute = Arc<dyn Fn(DbPool) -> BoxFuture<'static, sqlx::Result<CachedValue>> + Send + Sync>;
+
+struct Entry {
+    value: CachedValue,
+    compute: Compute,
+}
+
+struct TransientEntry {
+    value: CachedValue,
+    last_used: AtomicU64,
+}
+
+/// A cache of computed stats responses, shared by all workers.
+pub struct StatsResponseCache {
+    enabled: bool,
+    entries: DashMap<String, Entry>,
+    transient: DashMap<String, TransientEntry>,
+    /// Ticks on every transient lookup, to order the transient entries by
+    /// when they were last used.
+    clock: AtomicU64,
+    /// Bumped whenever the transient entries are dropped, so that responses
+    /// computed from the data before a refresh are not cached after it.
+    generation: AtomicU64,
+    data_version: Mutex<Option<u64>>,
+    lookups: CacheCounters,
+}
+
+impl std::fmt::Debug for StatsResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatsResponseCache")
+            .field("enabled", &self.enabled)
+            .field("entries", &self.entries.len())
+            .field("transient", &self.transient.len())
+            .field("data_version", &self.data_version())
+            .field("lookups", &self.lookups)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StatsResponseCache {
+    /// Creates an empty cache. The cache relies on the background refresher
+    /// to be invalidated, so when background services are off, pass
+    /// `enabled: false` and every request is computed from the database.
+    #[must_use]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            entries: DashMap::new(),
+            transient: DashMap::new(),
+            clock: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+            data_version: Mutex::new(None),
+            lookups: CacheCounters::default(),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// How many requests were answered from the cache, and how many had to
+    /// be computed. Requests aren't counted while the cache is disabled.
+    #[must_use]
+    pub const fn lookups(&self) -> &CacheCounters {
+        &self.lookups
+    }
+
+    /// The `change_log` sequence number the cached responses were computed
+    /// at, or `None` if the cache has not been refreshed yet.
+    #[must_use]
+    pub fn data_version(&self) -> Option<u64> {
+        *self.data_version.lock().expect("lock is not poisoned")
+    }
+
+    /// Returns the cached response for `key`, computing and caching it with
+    /// `compute` if there is none. `compute` is kept for background refreshes,
+    /// so everything it depends on besides the pool must be captured in it
+    /// (and be part of `key`).
+    ///
+    /// Every entry is kept and recomputed on each refresh, so `key` must come
+    /// from a fixed set. Responses to requests with parameters go through
+    /// [`Self::get_or_compute_transient`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns the database error of `compute`, if it had to be called.
+    pub async fn get_or_compute<T, F, Fut>(
+        &self,
+        key: &str,
+        pool: &DbPool,
+        compute: F,
+    ) -> sqlx::Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: Fn(DbPool) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = sqlx::Result<T>> + Send + 'static,
+    {
+        if !self.enabled {
+            return compute(pool.clone()).await;
+        }
+
+        let cached = self
+            .entries
+            .get(key)
+            .and_then(|entry| entry.value.downcast_ref::<T>().cloned());
+        self.lookups.record(cached.is_some());
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let value = compute(pool.clone()).await?;
+        let compute: Compute = Arc::new(move |pool| {
+            compute(pool)
+                .map(|result| result.map(|value| Arc::new(value) as CachedValue))
+                .boxed()
+        });
+        self.entries.insert(
+            key.to_owned(),
+            Entry {
+                value: Arc::new(value.clone()),
+                compute,
+            },
+        );
+
+        Ok(value)
+    }
+
+    /// Like [`Self::get_or_compute`], for a response to a request with
+    /// parameters. The response is kept until the next refresh at most, and
+    /// only while it is among the most recently used ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns the database error of `compute`, if it had to be awaited.
+    pub async fn get_or_compute_transient<T>(
+        &self,
+        key: &str,
+        compute: impl Future<Output = sqlx::Result<T>>,
+    ) -> sqlx::Result<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if !self.enabled {
+            return compute.await;
+        }
+
+        let now = self.clock.fetch_add(1, Ordering::Relaxed);
+        let cached = self.transient.get(key).and_then(|entry| {
+            entry.last_used.store(now, Ordering::Relaxed);
+            entry.value.downcast_ref::<T>().cloned()
+        });
+        self.lookups.record(cached.is_some());
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let value = compute.await?;
+        if self.generation.load(Ordering::Acquire) == generation {
+            self.insert_transient(key, Arc::new(value.clone()), now);
+        }
+
+        Ok(value)
+    }
+
+    fn insert_transient(&self, key: &str, value: CachedValue, now: u64) {
+        if self.transient.len() >= MAX_TRANSIENT_ENTRIES && !self.transient.contains_key(key) {
+            let least_recently_used = self
+                .transient
+                .iter()
+                .min_by_key(|entry| entry.last_used.load(Ordering::Relaxed))
+                .map(|entry| entry.key().clone());
+            if let Some(evicted) = least_recently_used {
+                self.transient.remove(&evicted);
+            }
+        }
+        self.transient.insert(
+            key.to_owned(),
+            TransientEntry {
+                value,
+                last_used: AtomicU64::new(now),
+            },
+        );
+    }
+
+    /// Drops every response to a request with parameters.
+    fn clear_transient(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.transient.clear();
+    }
+
+    /// Records the `change_log` sequence number read before the cached
+    /// responses were computed, once they have been.
+    pub fn set_data_version(&self, data_version: Option<u64>) {
+        *self.data_version.lock().expect("lock is not poisoned") = data_version;
+    }
+
+    /// Recomputes every cached response if the dirty flag is set, and drops
+    /// the responses to requests with parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the flag cannot be read or claimed.
+    /// Responses that fail to recompute are dropped from the cache (and
+    /// computed again by the next request) instead.
+    #[tracing::instrument(skip(self, pool))]
+    pub async fn refresh_if_needed(&self, pool: &DbPool) -> sqlx::Result<()> {
+        if !StatsResponseCacheMeta::needs_refresh(&**pool).await? {
+            return Ok(());
+        }
+        StatsResponseCacheMeta::clear_needs_refresh(&**pool).await?;
+        self.clear_transient();
+
+        let data_version = ChangeLogEntry::latest_seq(&**pool).await?;
+        let computes: Vec<(String, Compute)> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(&entry.compute)))
+            .collect();
+
+        for (key, compute) in computes {
+            match compute(pool.clone()).await {
+                Ok(value) => {
+                    if let Some(mut entry) = self.entries.get_mut(&key) {
+                        entry.value = value;
+                    }
+                }
+                Err(e) => {
+                    error!("stats response cache: recomputing {key} failed: {e}");
+                    self.entries.remove(&key);
+                }
+            }
+        }
+
+        self.set_data_version(data_version);
+        info!(
+            "stats response cache: {} responses refreshed",
+            self.entries.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn transient(cache: &StatsResponseCache, key: &str, value: u32) -> u32 {
+        block_on(cache.get_or_compute_transient(key, async move { Ok(value) })).unwrap()
+    }
+
+    #[test]
+    fn new_cache_has_no_data_version() {
+        let cache = StatsResponseCache::new(true);
+        assert_eq!(cache.data_version(), None);
+    }
+
+    #[test]
+    fn set_data_version_is_reported() {
+        let cache = StatsResponseCache::new(true);
+        cache.set_data_version(Some(42));
+        assert_eq!(cache.data_version(), Some(42));
+    }
+
+    #[test]
+    fn transient_responses_are_kept_until_cleared() {
+        let cache = StatsResponseCache::new(true);
+        assert_eq!(transient(&cache, "compare?a=1&b=2", 1), 1);
+        assert_eq!(transient(&cache, "compare?a=1&b=2", 2), 1);
+
+        cache.clear_transient();
+        assert_eq!(transient(&cache, "compare?a=1&b=2", 3), 3);
+    }
+
+    #[test]
+    fn the_least_recently_used_transient_response_is_evicted() {
+        let cache = StatsResponseCache::new(true);
+        for i in 0..MAX_TRANSIENT_ENTRIES {
+            transient(&cache, &i.to_string(), 0);
+        }
+        transient(&cache, "0", 0);
+        transient(&cache, "new", 0);
+
+        assert_eq!(cache.transient.len(), MAX_TRANSIENT_ENTRIES);
+        assert!(cache.transient.contains_key("0"));
+        assert!(!cache.transient.contains_key("1"));
+        assert!(cache.transient.contains_key("new"));
+    }
+
+    #[test]
+    fn transient_responses_computed_across_a_refresh_are_not_kept() {
+        let cache = StatsResponseCache::new(true);
+        let value = block_on(cache.get_or_compute_transient("cast", async {
+            cache.clear_transient();
+            Ok(1)
+        }))
+        .unwrap();
+        assert_eq!(value, 1);
+
+        assert_eq!(transient(&cache, "cast", 2), 2);
+    }
+}
@@ -0,0 +1,235 @@
+//! Configuration of the trace exporter, read from the environment.
+//!
+//! Traces can be exported over OTLP, written to stdout or turned off. The
+//! OTLP settings use the standard `OTEL_EXPORTER_OTLP_*` variable names, so a
+//! local collector works with the same configuration as any other service.
+//! For existing deployments, setting only `HONEYCOMB_KEY` still exports to
+//! Honeycomb.
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use std::collections::HashMap;
+
+const HONEYCOMB_ENDPOINT: &str = "https://api.honeycomb.io/v1/traces";
+const DEFAULT_HONEYCOMB_DATASET: &str = "qcext-server-dataset";
+const DEFAULT_HTTP_ENDPOINT: &str = "http://localhost:4318/v1/traces";
+const DEFAULT_GRPC_ENDPOINT: &str = "http://localhost:4317";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TracingConfig {
+    pub exporter: TracingExporter,
+    /// The share of traces that are sampled, between 0 and 1. Spans whose
+    /// parent was sampled are always sampled.
+    pub sample_ratio: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TracingExporter {
+    None,
+    Stdout,
+    Otlp(OtlpConfig),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtlpConfig {
+    pub endpoint: String,
+    pub protocol: OtlpProtocol,
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    HttpProtobuf,
+    Grpc,
+}
+
+impl TracingConfig {
+    /// Reads the configuration from the environment:
+    ///
+    /// - `TRACING_EXPORTER`: `none`, `stdout` or `otlp`. Defaults to `otlp`
+    ///   if an endpoint or `HONEYCOMB_KEY` is set, and `none` otherwise.
+    /// - `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`: the URL traces are sent to.
+    /// - `OTEL_EXPORTER_OTLP_PROTOCOL`: `http/protobuf` (the default) or
+    ///   `grpc`.
+    /// - `OTEL_EXPORTER_OTLP_HEADERS`: `key=value` pairs separated by commas.
+    /// - `TRACING_SAMPLE_RATIO`: between 0 and 1, defaults to 1.
+    /// - `HONEYCOMB_KEY` and `HONEYCOMB_DATASET`: shorthand for exporting to
+    ///   Honeycomb.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable has an invalid value.
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok().filter(|v| !v.is_empty()))
+    }
+
+    fn from_lookup(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let sample_ratio = match var("TRACING_SAMPLE_RATIO") {
+            Some(ratio) => ratio
+                .parse::<f64>()
+                .ok()
+                .filter(|r| (0.0..=1.0).contains(r))
+                .ok_or_else(|| {
+                    anyhow!("TRACING_SAMPLE_RATIO must be between 0 and 1, not {ratio:?}")
+                })?,
+            None => 1.0,
+        };
+
+        let endpoint = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT");
+        let honeycomb_key = var("HONEYCOMB_KEY");
+        let exporter = var("TRACING_EXPORTER").unwrap_or_else(|| {
+            if endpoint.is_some() || honeycomb_key.is_some() {
+                String::from("otlp")
+            } else {
+                String::from("none")
+            }
+        });
+
+        let exporter = match &*exporter {
+            "none" => TracingExporter::None,
+            "stdout" => TracingExporter::Stdout,
+            "otlp" => {
+                let protocol = match var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref() {
+                    None | Some("http/protobuf") => OtlpProtocol::HttpProtobuf,
+                    Some("grpc") => OtlpProtocol::Grpc,
+                    Some(protocol) => bail!(
+                        "OTEL_EXPORTER_OTLP_PROTOCOL must be http/protobuf or grpc, not {protocol:?}"
+                    ),
+                };
+
+                let mut headers = HashMap::new();
+                if let Some(key) = &honeycomb_key {
+                    headers.insert(String::from("x-honeycomb-team"), key.clone());
+                    headers.insert(
+                        String::from("x-honeycomb-dataset"),
+                        var("HONEYCOMB_DATASET")
+                            .unwrap_or_else(|| String::from(DEFAULT_HONEYCOMB_DATASET)),
+                    );
+                }
+                if let Some(configured) = var("OTEL_EXPORTER_OTLP_HEADERS") {
+                    headers.extend(
+                        parse_headers(&configured).context("invalid OTEL_EXPORTER_OTLP_HEADERS")?,
+                    );
+                }
+
+                let endpoint = endpoint.unwrap_or_else(|| {
+                    String::from(if honeycomb_key.is_some() {
+                        HONEYCOMB_ENDPOINT
+                    } else if protocol == OtlpProtocol::Grpc {
+                        DEFAULT_GRPC_ENDPOINT
+                    } else {
+                        DEFAULT_HTTP_ENDPOINT
+                    })
+                });
+
+                TracingExporter::Otlp(OtlpConfig {
+                    endpoint,
+                    protocol,
+                    headers,
+                })
+            }
+            exporter => bail!("TRACING_EXPORTER must be none, stdout or otlp, not {exporter:?}"),
+        };
+
+        Ok(Self {
+            exporter,
+            sample_ratio,
+        })
+    }
+}
+
+/// Parses `key=value` pairs separated by commas.
+fn parse_headers(headers: &str) -> Result<HashMap<String, String>> {
+    headers
+        .split(',')
+        .map(str::trim)
+        .filter(|header| !header.is_empty())
+        .map(|header| {
+            let (key, value) = header
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected key=value, got {header:?}"))?;
+            Ok((key.trim().to_owned(), value.trim().to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<TracingConfig> {
+        TracingConfig::from_lookup(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| (*value).to_owned())
+        })
+    }
+
+    #[test]
+    fn tracing_is_off_without_configuration() {
+        let config = config(&[]).unwrap();
+
+        assert_eq!(config.exporter, TracingExporter::None);
+        assert!((config.sample_ratio - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn a_honeycomb_key_exports_to_honeycomb() {
+        let config = config(&[("HONEYCOMB_KEY", "secret")]).unwrap();
+
+        let TracingExporter::Otlp(otlp) = config.exporter else {
+            panic!("expected the OTLP exporter");
+        };
+        assert_eq!(otlp.endpoint, HONEYCOMB_ENDPOINT);
+        assert_eq!(otlp.protocol, OtlpProtocol::HttpProtobuf);
+        assert_eq!(otlp.headers["x-honeycomb-team"], "secret");
+        assert_eq!(
+            otlp.headers["x-honeycomb-dataset"],
+            DEFAULT_HONEYCOMB_DATASET
+        );
+    }
+
+    #[test]
+    fn reads_the_otlp_settings() {
+        let config = config(&[
+            ("TRACING_EXPORTER", "otlp"),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "grpc"),
+            (
+                "OTEL_EXPORTER_OTLP_HEADERS",
+                "authorization=Bearer abc, x-team = qc",
+            ),
+            ("TRACING_SAMPLE_RATIO", "0.25"),
+        ])
+        .unwrap();
+
+        let TracingExporter::Otlp(otlp) = config.exporter else {
+            panic!("expected the OTLP exporter");
+        };
+        assert_eq!(otlp.endpoint, DEFAULT_GRPC_ENDPOINT);
+        assert_eq!(otlp.protocol, OtlpProtocol::Grpc);
+        assert_eq!(otlp.headers["authorization"], "Bearer abc");
+        assert_eq!(otlp.headers["x-team"], "qc");
+        assert!((config.sample_ratio - 0.25).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn the_exporter_can_be_turned_off_or_written_to_stdout() {
+        let off = config(&[("TRACING_EXPORTER", "none"), ("HONEYCOMB_KEY", "secret")]).unwrap();
+        assert_eq!(off.exporter, TracingExporter::None);
+
+        let stdout = config(&[("TRACING_EXPORTER", "stdout")]).unwrap();
+        assert_eq!(stdout.exporter, TracingExporter::Stdout);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(config(&[("TRACING_EXPORTER", "jaeger")]).is_err());
+        assert!(config(&[("TRACING_SAMPLE_RATIO", "2")]).is_err());
+        assert!(
+            config(&[
+                ("TRACING_EXPORTER", "otlp"),
+                ("OTEL_EXPORTER_OTLP_HEADERS", "no-equals-sign"),
+            ])
+            .is_err()
+        );
+    }
+}
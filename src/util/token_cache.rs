@@ -1,5 +1,6 @@
 //! Per-token permission cache to avoid a DB round-trip on every request.
 
+use crate::util::CacheCounters;
 use dashmap::DashMap;
 use std::collections::HashSet;
 use std::time::{Duration, Instant};
@@ -14,6 +15,7 @@ const DEFAULT_TTL: Duration = Duration::from_mins(5);
 pub struct TokenPermissionsCache {
     ttl: Duration,
     inner: DashMap<String, (HashSet<String>, Instant)>,
+    lookups: CacheCounters,
 }
 
 impl Default for TokenPermissionsCache {
@@ -33,12 +35,19 @@ impl TokenPermissionsCache {
         Self {
             ttl,
             inner: DashMap::new(),
+            lookups: CacheCounters::default(),
         }
     }
 
     /// Returns the cached permissions for `token`, or `None` if absent or expired.
     #[must_use]
     pub fn get(&self, token: &str) -> Option<HashSet<String>> {
+        let permissions = self.get_unrecorded(token);
+        self.lookups.record(permissions.is_some());
+        permissions
+    }
+
+    fn get_unrecorded(&self, token: &str) -> Option<HashSet<String>> {
         let entry = self.inner.get(token)?;
         let (perms, inserted_at) = entry.value();
         if inserted_at.elapsed() < self.ttl {
@@ -50,6 +59,12 @@ impl TokenPermissionsCache {
         }
     }
 
+    /// How many lookups found permissions in the cache, and how many didn't.
+    #[must_use]
+    pub const fn lookups(&self) -> &CacheCounters {
+        &self.lookups
+    }
+
     /// Stores `permissions` for `token`, replacing any existing entry.
     pub fn set(&self, token: String, permissions: HashSet<String>) {
         self.inner.insert(token, (permissions, Instant::now()));
@@ -91,6 +106,18 @@ mod tests {
         assert_eq!(cache.inner.len(), 0);
     }
 
+    #[test]
+    fn get_counts_hits_and_misses() {
+        let cache = TokenPermissionsCache::new();
+        cache.set("tok5".to_string(), HashSet::from(["perm".to_string()]));
+        assert!(cache.get("tok5").is_some());
+        assert!(cache.get("unknown").is_none());
+        assert!(cache.get("unknown").is_none());
+
+        assert_eq!(cache.lookups().hits(), 1);
+        assert_eq!(cache.lookups().misses(), 2);
+    }
+
     #[test]
     fn set_overwrites_existing_entry() {
         let cache = TokenPermissionsCache::new();